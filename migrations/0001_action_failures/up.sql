@@ -0,0 +1 @@
+ALTER TABLE actions ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+ALTER TABLE settings DROP COLUMN action_log_max_days;
+ALTER TABLE settings DROP COLUMN action_log_max_entries;
+
+DROP TABLE action_log;
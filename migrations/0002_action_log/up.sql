@@ -0,0 +1,18 @@
+CREATE TABLE action_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    action_id INTEGER NOT NULL,
+    action_name TEXT NOT NULL,
+    event_summary TEXT NOT NULL,
+    started_at BIGINT NOT NULL,
+    duration_us BIGINT NOT NULL,
+    result TEXT NOT NULL,
+    error TEXT,
+    error_line INTEGER,
+    error_column INTEGER,
+    messages TEXT NOT NULL
+);
+CREATE INDEX action_log_action_id_idx ON action_log(action_id);
+CREATE INDEX action_log_started_at_idx ON action_log(started_at);
+
+ALTER TABLE settings ADD COLUMN action_log_max_entries INTEGER;
+ALTER TABLE settings ADD COLUMN action_log_max_days INTEGER;
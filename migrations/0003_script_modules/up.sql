@@ -0,0 +1,5 @@
+CREATE TABLE script_modules (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    source BLOB NOT NULL
+);
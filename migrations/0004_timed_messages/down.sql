@@ -0,0 +1 @@
+DROP TABLE timed_messages;
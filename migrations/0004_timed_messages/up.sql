@@ -0,0 +1,9 @@
+CREATE TABLE timed_messages (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    messages TEXT NOT NULL,
+    announcement_color TEXT,
+    interval_minutes INTEGER NOT NULL,
+    min_chat_messages INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE
+);
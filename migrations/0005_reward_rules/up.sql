@@ -0,0 +1,5 @@
+CREATE TABLE reward_rules (
+    reward_id TEXT NOT NULL PRIMARY KEY,
+    auto_fulfill TEXT NOT NULL DEFAULT 'never',
+    action_id INTEGER
+);
@@ -0,0 +1,4 @@
+DROP TABLE stats_emotes;
+DROP TABLE stats_chatters;
+DROP TABLE stats_minutes;
+DROP TABLE stream_sessions;
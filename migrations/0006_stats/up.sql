@@ -0,0 +1,40 @@
+CREATE TABLE stream_sessions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel TEXT NOT NULL,
+    started_at BIGINT NOT NULL,
+    ended_at BIGINT,
+    live BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE INDEX stream_sessions_channel_idx ON stream_sessions(channel, started_at);
+
+CREATE TABLE stats_minutes (
+    session_id INTEGER NOT NULL REFERENCES stream_sessions(id) ON DELETE CASCADE,
+    minute BIGINT NOT NULL,
+    messages INTEGER NOT NULL DEFAULT 0,
+    chatters INTEGER NOT NULL DEFAULT 0,
+    first_time_chatters INTEGER NOT NULL DEFAULT 0,
+    emotes INTEGER NOT NULL DEFAULT 0,
+    mod_actions INTEGER NOT NULL DEFAULT 0,
+    subs INTEGER NOT NULL DEFAULT 0,
+    bits INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (session_id, minute)
+);
+
+CREATE TABLE stats_chatters (
+    session_id INTEGER NOT NULL REFERENCES stream_sessions(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    user_login TEXT NOT NULL,
+    user_name TEXT NOT NULL,
+    messages INTEGER NOT NULL DEFAULT 0,
+    bits INTEGER NOT NULL DEFAULT 0,
+    first_time BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (session_id, user_id)
+);
+
+CREATE TABLE stats_emotes (
+    session_id INTEGER NOT NULL REFERENCES stream_sessions(id) ON DELETE CASCADE,
+    emote_id TEXT NOT NULL,
+    emote_name TEXT NOT NULL,
+    uses INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (session_id, emote_id)
+);
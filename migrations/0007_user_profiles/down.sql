@@ -0,0 +1,4 @@
+DROP TABLE mod_log;
+DROP TABLE user_notes;
+DROP TABLE chat_user_names;
+DROP TABLE chat_users;
@@ -0,0 +1,35 @@
+CREATE TABLE chat_users (
+    user_id TEXT NOT NULL PRIMARY KEY,
+    login TEXT NOT NULL,
+    name TEXT NOT NULL,
+    first_seen BIGINT NOT NULL,
+    last_seen BIGINT NOT NULL,
+    messages INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX chat_users_login_idx ON chat_users(login);
+
+CREATE TABLE chat_user_names (
+    user_id TEXT NOT NULL,
+    login TEXT NOT NULL,
+    name TEXT NOT NULL,
+    last_seen BIGINT NOT NULL,
+    PRIMARY KEY (user_id, login)
+);
+
+CREATE TABLE user_notes (
+    user_id TEXT NOT NULL PRIMARY KEY,
+    notes TEXT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE TABLE mod_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel TEXT NOT NULL,
+    user_id TEXT,
+    user_login TEXT NOT NULL,
+    action TEXT NOT NULL,
+    duration_secs BIGINT,
+    message TEXT,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX mod_log_user_login_idx ON mod_log(user_login);
@@ -0,0 +1,16 @@
+CREATE TABLE chat_events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    message_id TEXT,
+    user_id TEXT,
+    user_login TEXT,
+    user_name TEXT,
+    text TEXT,
+    status TEXT,
+    source TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX chat_events_channel_idx ON chat_events(channel, id);
+CREATE INDEX chat_events_message_id_idx ON chat_events(message_id);
+CREATE INDEX chat_events_user_id_idx ON chat_events(user_id);
@@ -0,0 +1,5 @@
+DROP TRIGGER chat_events_fts_update;
+DROP TRIGGER chat_events_fts_delete;
+DROP TRIGGER chat_events_fts_insert;
+DROP TABLE chat_events_fts;
+ALTER TABLE chat_events DROP COLUMN badges;
@@ -0,0 +1,29 @@
+ALTER TABLE chat_events ADD COLUMN badges TEXT;
+
+-- the irc tag of already saved events, e.g. "moderator/1,subscriber/12", tags end at the first space
+UPDATE chat_events
+SET badges = substr(
+    substr(source, instr(source, 'badges=') + 7),
+    1,
+    instr(substr(source, instr(source, 'badges=') + 7), ' ') - 1
+)
+WHERE kind IN ('privmsg', 'user_notice') AND instr(source, 'badges=') > 0;
+
+UPDATE chat_events SET badges = substr(badges, 1, instr(badges, ';') - 1) WHERE instr(badges, ';') > 0;
+
+CREATE VIRTUAL TABLE chat_events_fts USING fts5(text, content = 'chat_events', content_rowid = 'id');
+
+CREATE TRIGGER chat_events_fts_insert AFTER INSERT ON chat_events BEGIN
+    INSERT INTO chat_events_fts (rowid, text) VALUES (new.id, new.text);
+END;
+
+CREATE TRIGGER chat_events_fts_delete AFTER DELETE ON chat_events BEGIN
+    INSERT INTO chat_events_fts (chat_events_fts, rowid, text) VALUES ('delete', old.id, old.text);
+END;
+
+CREATE TRIGGER chat_events_fts_update AFTER UPDATE OF text ON chat_events BEGIN
+    INSERT INTO chat_events_fts (chat_events_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    INSERT INTO chat_events_fts (rowid, text) VALUES (new.id, new.text);
+END;
+
+INSERT INTO chat_events_fts (chat_events_fts) VALUES ('rebuild');
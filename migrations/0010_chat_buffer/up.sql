@@ -0,0 +1 @@
+ALTER TABLE settings ADD COLUMN chat_max_events INTEGER;
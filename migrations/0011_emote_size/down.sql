@@ -0,0 +1 @@
+ALTER TABLE settings DROP COLUMN chat_emote_size;
@@ -0,0 +1,5 @@
+CREATE TABLE asset_cache (
+    key TEXT NOT NULL PRIMARY KEY,
+    hash TEXT NOT NULL,
+    fetched_at BIGINT NOT NULL
+);
@@ -8,20 +8,25 @@ use eframe::{
 use egui_dock::DockState;
 use egui_toast::{Toast, ToastKind, ToastOptions, Toasts};
 use strum::IntoEnumIterator;
+use tracing::warn;
+use twitch_api::{
+    eventsub::channel::channel_points_custom_reward_redemption::RedemptionStatus,
+    helix::points::CustomRewardRedemptionStatus,
+};
 use twitch_irc::message::{ClearChatAction, FollowersOnlyMode};
 
 use crate::{
-    models::{self, settings::Settings},
+    models::{self, reward_rule::AutoFulfill, settings::Settings},
     twitch::{
-        api::twitch_get_channel_from_login,
-        types::{PrivmsgMessageExt, TwitchAccount, TwitchEvent},
+        api::{twitch_get_channel_from_login, twitch_get_stream_status},
+        types::{PendingRedemption, PrivmsgMessageExt, TwitchAccount, TwitchEvent},
     },
     ui::{
         fonts::load_fonts,
         state::{AppState, AppStateDiff},
-        tabs::Tabs,
+        tabs::{Tabs, chat::profile::TwitchProfile, redemptions::resolve_redemption},
     },
-    workers,
+    workers::{self, asset::AssetWorkerMessage, stats::StatsWorkerMessage, timers::TimersWorkerMessage},
 };
 
 pub struct App {
@@ -32,9 +37,10 @@ pub struct App {
 impl App {
     pub fn new(cctx: &CreationContext) -> Result<Box<Self>> {
         load_fonts(cctx);
+        egui_extras::install_image_loaders(&cctx.egui_ctx);
 
         let db_pool = models::create_database_pool()?;
-        let channels = workers::create_workers();
+        let channels = workers::create_workers(&db_pool);
 
         let toasts = Toasts::new()
             .anchor(Align2::RIGHT_TOP, pos2(10.0, 10.0))
@@ -86,7 +92,15 @@ impl App {
             }
 
             AppStateDiff::AccountLinked(client, token) => {
-                self.state.twitch_account = Some(TwitchAccount { client, token });
+                let account = TwitchAccount { client, token };
+                self.state
+                    .channels
+                    .asset_worker_message_tx
+                    .send(AssetWorkerMessage::SetAccount(account.clone()))
+                    .unwrap();
+                self.state.twitch_account = Some(account);
+                // restarted with the new token once the channel info arrives
+                self.state.stop_twitch_eventsub_worker();
                 if let Some(connected_channel_name) = &self.state.connected_channel_name
                     && self.state.did_we_join
                 {
@@ -96,7 +110,29 @@ impl App {
                 }
             }
             AppStateDiff::ChannelInfoUpdated(channel_info) => {
+                let is_new_channel = self
+                    .state
+                    .connected_channel_info
+                    .as_ref()
+                    .is_none_or(|info| info.broadcaster_id != channel_info.broadcaster_id);
+
                 self.state.connected_channel_info = Some(channel_info);
+
+                if is_new_channel {
+                    self.state.redemptions.reset();
+
+                    if let Some(channel_info) = &self.state.connected_channel_info {
+                        self.state
+                            .chat
+                            .third_party_emotes
+                            .set_channel(channel_info.broadcaster_id.as_str());
+                    }
+                    self.state.load_third_party_emotes();
+                }
+
+                if is_new_channel || self.state.twitch_eventsub_worker_handle.is_none() {
+                    self.state.start_twitch_eventsub_worker();
+                }
             }
 
             AppStateDiff::SetSettingsChannelError(error) => {
@@ -104,6 +140,123 @@ impl App {
                 self.state.connected_channel_info = None;
                 self.state.settings.channel_name_error = Some(error);
             }
+
+            AppStateDiff::CheckStreamStatus => {
+                if let (Some(account), Some(channel)) = (&self.state.twitch_account, &self.state.connected_channel_info)
+                {
+                    twitch_get_stream_status(&self.state.channels.ui_diff_tx, account, channel);
+                } else if self.state.is_live {
+                    self.apply_state_diff(AppStateDiff::StreamStatusUpdated(false));
+                }
+            }
+            AppStateDiff::StreamStatusUpdated(is_live) => {
+                self.state.is_live = is_live;
+                self.state
+                    .channels
+                    .timers_worker_message_tx
+                    .send(TimersWorkerMessage::SetLive(is_live))
+                    .unwrap();
+                self.state
+                    .channels
+                    .stats_worker_message_tx
+                    .send(StatsWorkerMessage::SetLive(is_live))
+                    .unwrap();
+            }
+
+            AppStateDiff::RunActionCommands(commands) => {
+                let (Some(account), Some(channel)) = (&self.state.twitch_account, &self.state.connected_channel_info)
+                else {
+                    warn!(
+                        "Dropped {} action commands, not logged in or connected.",
+                        commands.len()
+                    );
+                    return;
+                };
+
+                for command in commands {
+                    command.execute(&self.state.channels.ui_diff_tx, account, channel);
+                }
+            }
+            AppStateDiff::UserProfileLoaded {
+                user_id,
+                created_at,
+                followed_at,
+            } => {
+                if let Some(profile) = &mut self.state.chat.profile
+                    && profile.user_id == user_id
+                {
+                    profile.twitch = Some(TwitchProfile {
+                        created_at,
+                        followed_at,
+                    });
+                }
+            }
+
+            AppStateDiff::RedemptionQueueLoaded { rewards, redemptions } => {
+                self.state.redemptions.rewards = rewards;
+                self.state.redemptions.redemptions = redemptions;
+                self.state.redemptions.is_loading = false;
+            }
+            AppStateDiff::RedemptionResolved(id) => {
+                self.state
+                    .redemptions
+                    .redemptions
+                    .retain(|redemption| redemption.id != id);
+            }
+            AppStateDiff::RedemptionActionFinished { redemption, success } => {
+                if success && self.state.redemptions.auto_fulfill(&redemption.reward_id) == AutoFulfill::OnActionSuccess
+                {
+                    resolve_redemption(&self.state, &redemption, CustomRewardRedemptionStatus::Fulfilled);
+                }
+            }
+
+            AppStateDiff::ActionDisabled(id) => {
+                self.state.actions.needs_refresh = true;
+
+                if let Some(editor) = &mut self.state.actions.editor
+                    && editor.id == Some(id)
+                {
+                    editor.config.enabled = false;
+                }
+            }
+
+            AppStateDiff::ActionTestFinished { generation, result } => {
+                self.state.actions.apply_test_result(generation, result);
+            }
+
+            AppStateDiff::AssetLoaded { key, bytes } => {
+                self.state.assets.insert(key, bytes);
+            }
+            AppStateDiff::BadgeTitlesLoaded { channel_id, titles } => {
+                self.state.assets.insert_badge_titles(channel_id, titles);
+            }
+            AppStateDiff::ThirdPartyEmotesLoaded {
+                provider,
+                channel_id,
+                emotes,
+            } => {
+                self.state
+                    .chat
+                    .third_party_emotes
+                    .insert_set(provider, channel_id, emotes);
+            }
+
+            AppStateDiff::SearchResultsLoaded { generation, hits } => {
+                self.state.search.apply_results(generation, hits);
+            }
+            AppStateDiff::SearchContextLoaded { generation, context } => {
+                self.state.search.apply_context(generation, context);
+            }
+            AppStateDiff::ChatEventsSaved(ids) => {
+                self.state.chat.apply_saved_ids(ids);
+            }
+            AppStateDiff::ChatHistoryFlushed { channel, newest_id } => {
+                self.state.chat.start_history(&self.state.db_pool, &channel, newest_id);
+
+                if self.state.pending_join.take_if(|pending| *pending == channel).is_some() {
+                    self.state.join_channel(channel);
+                }
+            }
         }
     }
 
@@ -120,7 +273,25 @@ impl App {
                     }
                 }
 
-                self.state.chat.events.items.push(event);
+                self.state.chat.push_event(&self.state.db_pool, event);
+            }
+            TwitchEvent::Redemption(redemption) => {
+                if redemption.status != RedemptionStatus::Unfulfilled {
+                    return;
+                }
+
+                let redemption = PendingRedemption::from(&redemption);
+                if self.state.redemptions.auto_fulfill(&redemption.reward_id) == AutoFulfill::Always {
+                    resolve_redemption(&self.state, &redemption, CustomRewardRedemptionStatus::Fulfilled);
+                }
+
+                self.state.redemptions.redemptions.push(redemption);
+            }
+            TwitchEvent::StreamOnline(_) => {
+                self.apply_state_diff(AppStateDiff::StreamStatusUpdated(true));
+            }
+            TwitchEvent::StreamOffline(_) => {
+                self.apply_state_diff(AppStateDiff::StreamStatusUpdated(false));
             }
             TwitchEvent::Ping(_) => {}
             TwitchEvent::Pong(_) => {}
@@ -216,7 +387,7 @@ impl App {
                 }
             },
             event => {
-                self.state.chat.events.items.push(event);
+                self.state.chat.push_event(&self.state.db_pool, event);
             }
         }
     }
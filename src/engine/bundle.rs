@@ -0,0 +1,219 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Result, bail};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::{
+    engine::store::action_bucket,
+    models::{
+        SqlitePool,
+        action::{Action, ActionConfig},
+        kv_store::KvStore,
+    },
+};
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A portable set of actions, written to and read from a single json file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionBundle {
+    pub version: u32,
+    pub actions: Vec<BundledAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledAction {
+    pub name: String,
+    pub script: String,
+    pub config: ActionConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<BTreeMap<String, serde_json::Value>>,
+}
+
+/// What to do with a bundled action whose name already exists.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum ConflictStrategy {
+    #[default]
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub overwritten: usize,
+    pub renamed: usize,
+    pub skipped: usize,
+}
+
+impl ActionBundle {
+    pub fn export(pool: &SqlitePool, ids: &[i32], include_store: bool) -> Result<Self> {
+        let mut actions = Vec::new();
+
+        for action in Action::load_all(pool)?
+            .into_iter()
+            .filter(|action| ids.contains(&action.id))
+        {
+            let store = if include_store {
+                let mut store = BTreeMap::new();
+                for (key, value) in KvStore::get_bucket(pool, &action_bucket(action.id))? {
+                    store.insert(key, serde_json::from_str(&value)?);
+                }
+                Some(store)
+            } else {
+                None
+            };
+
+            actions.push(BundledAction {
+                script: action.script_source()?,
+                config: action.parsed_config()?,
+                name: action.name,
+                store,
+            });
+        }
+
+        return Ok(Self {
+            version: BUNDLE_VERSION,
+            actions,
+        });
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let bundle = serde_json::from_slice::<Self>(&fs::read(path)?)?;
+
+        if bundle.version > BUNDLE_VERSION {
+            bail!(
+                "Bundle version {} is newer than the supported version {}.",
+                bundle.version,
+                BUNDLE_VERSION
+            );
+        }
+
+        return Ok(bundle);
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+
+        return Ok(());
+    }
+
+    /// Names of bundled actions that already exist.
+    pub fn conflicts(&self, existing: &[Action]) -> Vec<String> {
+        return self
+            .actions
+            .iter()
+            .filter(|bundled| existing.iter().any(|action| action.name == bundled.name))
+            .map(|bundled| bundled.name.clone())
+            .collect();
+    }
+
+    /// Imports all actions or, if anything fails, none of them.
+    pub fn import(&self, pool: &SqlitePool, strategy: ConflictStrategy) -> Result<ImportSummary> {
+        use crate::schema::{actions, kv_store};
+
+        let mut db = pool.get()?;
+
+        return db.transaction(|db| {
+            let mut existing = load_actions(db)?;
+            let mut summary = ImportSummary::default();
+
+            for bundled in &self.actions {
+                let conflict = existing
+                    .iter()
+                    .find(|action| action.name == bundled.name)
+                    .map(|action| action.id);
+
+                let id = match (conflict, strategy) {
+                    (None, _) => {
+                        summary.imported += 1;
+                        insert_action(db, &bundled.name, bundled)?
+                    }
+                    (Some(_), ConflictStrategy::Skip) => {
+                        summary.skipped += 1;
+                        continue;
+                    }
+                    (Some(id), ConflictStrategy::Overwrite) => {
+                        summary.overwritten += 1;
+                        diesel::update(actions::table.find(id))
+                            .set((
+                                actions::script.eq(bundled.script.as_bytes()),
+                                actions::config.eq(serde_json::to_vec(&bundled.config)?),
+                                actions::failure_count.eq(0),
+                            ))
+                            .execute(db)?;
+                        if bundled.store.is_some() {
+                            diesel::delete(kv_store::table.filter(kv_store::bucket.eq(action_bucket(id))))
+                                .execute(db)?;
+                        }
+                        id
+                    }
+                    (Some(_), ConflictStrategy::Rename) => {
+                        summary.renamed += 1;
+                        let name = unique_name(&existing, &bundled.name);
+                        insert_action(db, &name, bundled)?
+                    }
+                };
+
+                if let Some(store) = &bundled.store {
+                    for (key, value) in store {
+                        let value = serde_json::to_string(value)?;
+
+                        diesel::insert_into(kv_store::table)
+                            .values(KvStore {
+                                bucket: action_bucket(id),
+                                key: key.clone(),
+                                value: value.clone(),
+                            })
+                            .on_conflict((kv_store::bucket, kv_store::key))
+                            .do_update()
+                            .set(kv_store::value.eq(value))
+                            .execute(db)?;
+                    }
+                }
+
+                existing = load_actions(db)?;
+            }
+
+            Ok(summary)
+        });
+    }
+}
+
+/// Like `Action::load_all`, but on the connection of the import transaction.
+fn load_actions(db: &mut SqliteConnection) -> Result<Vec<Action>> {
+    use crate::schema::actions;
+
+    let all_actions = actions::table
+        .order(actions::id.asc())
+        .select(Action::as_select())
+        .load(db)?;
+
+    return Ok(all_actions);
+}
+
+/// Like `Action::insert`, but on the connection of the import transaction.
+fn insert_action(db: &mut SqliteConnection, name: &str, bundled: &BundledAction) -> Result<i32> {
+    use crate::schema::actions;
+
+    let id = diesel::insert_into(actions::table)
+        .values((
+            actions::name.eq(name),
+            actions::script.eq(bundled.script.as_bytes()),
+            actions::config.eq(serde_json::to_vec(&bundled.config)?),
+        ))
+        .returning(actions::id)
+        .get_result::<i32>(db)?;
+
+    return Ok(id);
+}
+
+fn unique_name(existing: &[Action], name: &str) -> String {
+    return (2..)
+        .map(|n| format!("{name} ({n})"))
+        .find(|candidate| existing.iter().all(|action| &action.name != candidate))
+        .unwrap();
+}
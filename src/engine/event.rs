@@ -0,0 +1,251 @@
+use rhai::{Array, CustomType, Dynamic, TypeBuilder};
+use twitch_irc::message::{ClearChatAction, FollowersOnlyMode, UserNoticeEvent};
+
+use crate::twitch::types::{PrivmsgMessageExt, TwitchEvent};
+
+/// The event object scripts see as `event`.
+#[derive(Debug, Default, Clone)]
+pub struct ScriptEvent {
+    pub kind: String,
+    pub sub_kind: String,
+    pub channel: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub message_id: String,
+    pub text: String,
+    /// Logins mentioned with `@` in a chat message.
+    pub mentions: Vec<String>,
+    /// Twitch emotes used in a chat message.
+    pub emotes: Vec<String>,
+    /// Login of the sender of the message a chat message replies to.
+    pub reply_to: String,
+    pub badges: Vec<String>,
+    pub bits: i64,
+    pub amount: i64,
+    pub duration_secs: i64,
+    pub is_broadcaster: bool,
+    pub is_mod: bool,
+    pub is_vip: bool,
+    pub is_subscriber: bool,
+    pub is_first_message: bool,
+}
+
+impl ScriptEvent {
+    pub fn from_twitch_event(event: &TwitchEvent) -> Option<Self> {
+        return match event {
+            TwitchEvent::Privmsg(msg) => Some(Self {
+                kind: String::from("message"),
+                channel: msg.channel_login.clone(),
+                user_id: msg.sender.id.clone(),
+                user_login: msg.sender.login.clone(),
+                user_name: msg.sender.name.clone(),
+                message_id: msg.message_id.clone(),
+                text: msg.parsed.text(),
+                mentions: msg.parsed.mentions().map(str::to_owned).collect(),
+                emotes: msg.parsed.emotes().map(str::to_owned).collect(),
+                reply_to: msg.parsed.reply_to().unwrap_or_default().to_owned(),
+                badges: msg.badges.iter().map(|badge| badge.name.clone()).collect(),
+                bits: msg.bits.unwrap_or(0) as i64,
+                is_broadcaster: msg.is_by_broadcaster(),
+                is_mod: msg.is_by_mod() || msg.is_by_lead_mod(),
+                is_vip: msg.is_by_vip(),
+                is_subscriber: msg.is_by_subscriber(),
+                is_first_message: msg.is_first_message(),
+                ..Self::default()
+            }),
+            TwitchEvent::UserNotice(notice) => {
+                let (sub_kind, amount) = match &notice.event {
+                    UserNoticeEvent::SubOrResub {
+                        is_resub,
+                        cumulative_months,
+                        ..
+                    } => (if *is_resub { "resub" } else { "sub" }, *cumulative_months as i64),
+                    UserNoticeEvent::SubGift { num_gifted_months, .. } => ("subgift", *num_gifted_months as i64),
+                    UserNoticeEvent::SubMysteryGift { mass_gift_count, .. }
+                    | UserNoticeEvent::AnonSubMysteryGift { mass_gift_count, .. } => {
+                        ("submysterygift", *mass_gift_count as i64)
+                    }
+                    UserNoticeEvent::Raid { viewer_count, .. } => ("raid", *viewer_count as i64),
+                    UserNoticeEvent::BitsBadgeTier { threshold } => ("bitsbadgetier", *threshold as i64),
+                    _ => (notice.event_id.as_str(), 0),
+                };
+
+                Some(Self {
+                    kind: String::from("user_notice"),
+                    sub_kind: sub_kind.to_owned(),
+                    channel: notice.channel_login.clone(),
+                    user_id: notice.sender.id.clone(),
+                    user_login: notice.sender.login.clone(),
+                    user_name: notice.sender.name.clone(),
+                    message_id: notice.message_id.clone(),
+                    text: notice.message_text.clone().unwrap_or_default(),
+                    badges: notice.badges.iter().map(|badge| badge.name.clone()).collect(),
+                    amount,
+                    ..Self::default()
+                })
+            }
+            TwitchEvent::ClearChat(clear_chat) => {
+                let mut script_event = Self {
+                    kind: String::from("clear_chat"),
+                    channel: clear_chat.channel_login.clone(),
+                    ..Self::default()
+                };
+
+                match &clear_chat.action {
+                    ClearChatAction::ChatCleared => {
+                        script_event.sub_kind = String::from("clear");
+                    }
+                    ClearChatAction::UserBanned { user_login, user_id } => {
+                        script_event.sub_kind = String::from("ban");
+                        script_event.user_login = user_login.clone();
+                        script_event.user_id = user_id.clone();
+                    }
+                    ClearChatAction::UserTimedOut {
+                        user_login,
+                        user_id,
+                        timeout_length,
+                    } => {
+                        script_event.sub_kind = String::from("timeout");
+                        script_event.user_login = user_login.clone();
+                        script_event.user_id = user_id.clone();
+                        script_event.duration_secs = timeout_length.as_secs() as i64;
+                    }
+                }
+
+                Some(script_event)
+            }
+            TwitchEvent::ClearMsg(clear_msg) => Some(Self {
+                kind: String::from("clear_msg"),
+                channel: clear_msg.channel_login.clone(),
+                user_login: clear_msg.sender_login.clone(),
+                message_id: clear_msg.message_id.clone(),
+                text: clear_msg.message_text.clone(),
+                ..Self::default()
+            }),
+            TwitchEvent::RoomState(room_state) => {
+                let mut script_event = Self {
+                    kind: String::from("room_state"),
+                    channel: room_state.channel_login.clone(),
+                    ..Self::default()
+                };
+
+                if let Some(slow_mode) = room_state.slow_mode {
+                    script_event.sub_kind = String::from("slow_mode");
+                    script_event.duration_secs = slow_mode.as_secs() as i64;
+                } else if let Some(emote_only) = room_state.emote_only {
+                    script_event.sub_kind = String::from("emote_only");
+                    script_event.amount = emote_only as i64;
+                } else if let Some(followers_only) = &room_state.follwers_only {
+                    script_event.sub_kind = String::from("followers_only");
+                    if let FollowersOnlyMode::Enabled(duration) = followers_only {
+                        script_event.amount = 1;
+                        script_event.duration_secs = duration.as_secs() as i64;
+                    }
+                } else if let Some(subscribers_only) = room_state.subscribers_only {
+                    script_event.sub_kind = String::from("subscribers_only");
+                    script_event.amount = subscribers_only as i64;
+                }
+
+                Some(script_event)
+            }
+            TwitchEvent::Join(join) => Some(Self {
+                kind: String::from("join"),
+                channel: join.channel_login.clone(),
+                user_login: join.user_login.clone(),
+                ..Self::default()
+            }),
+            TwitchEvent::Part(part) => Some(Self {
+                kind: String::from("part"),
+                channel: part.channel_login.clone(),
+                user_login: part.user_login.clone(),
+                ..Self::default()
+            }),
+            TwitchEvent::Notice(notice) => Some(Self {
+                kind: String::from("notice"),
+                channel: notice.channel_login.clone().unwrap_or_default(),
+                sub_kind: notice.message_id.clone().unwrap_or_default(),
+                text: notice.message_text.clone(),
+                ..Self::default()
+            }),
+            TwitchEvent::Redemption(redemption) => Some(Self {
+                kind: String::from("redemption"),
+                sub_kind: redemption.reward.title.clone(),
+                channel: redemption.broadcaster_user_login.to_string(),
+                user_id: redemption.user_id.to_string(),
+                user_login: redemption.user_login.to_string(),
+                user_name: redemption.user_name.to_string(),
+                message_id: redemption.id.to_string(),
+                text: redemption.user_input.trim().to_owned(),
+                amount: redemption.reward.cost,
+                ..Self::default()
+            }),
+            TwitchEvent::Whisper(whisper) => Some(Self {
+                kind: String::from("whisper"),
+                user_id: whisper.sender.id.clone(),
+                user_login: whisper.sender.login.clone(),
+                user_name: whisper.sender.name.clone(),
+                text: whisper.message_text.clone(),
+                badges: whisper.badges.iter().map(|badge| badge.name.clone()).collect(),
+                ..Self::default()
+            }),
+            _ => None,
+        };
+    }
+
+    /// Short description of the event for the action log.
+    pub fn summary(&self) -> String {
+        let mut summary = self.kind.clone();
+
+        if !self.sub_kind.is_empty() {
+            summary.push('/');
+            summary.push_str(&self.sub_kind);
+        }
+
+        if !self.user_login.is_empty() {
+            summary.push_str(" by ");
+            summary.push_str(&self.user_login);
+        }
+
+        if !self.text.is_empty() {
+            summary.push_str(": ");
+            summary.extend(self.text.chars().take(200));
+        }
+
+        return summary;
+    }
+}
+
+impl CustomType for ScriptEvent {
+    fn build(mut builder: TypeBuilder<Self>) {
+        builder
+            .with_name("Event")
+            .with_get("kind", |event: &mut Self| event.kind.clone())
+            .with_get("sub_kind", |event: &mut Self| event.sub_kind.clone())
+            .with_get("channel", |event: &mut Self| event.channel.clone())
+            .with_get("user_id", |event: &mut Self| event.user_id.clone())
+            .with_get("user_login", |event: &mut Self| event.user_login.clone())
+            .with_get("user_name", |event: &mut Self| event.user_name.clone())
+            .with_get("message_id", |event: &mut Self| event.message_id.clone())
+            .with_get("text", |event: &mut Self| event.text.clone())
+            .with_get("mentions", |event: &mut Self| {
+                event.mentions.iter().cloned().map(Dynamic::from).collect::<Array>()
+            })
+            .with_get("emotes", |event: &mut Self| {
+                event.emotes.iter().cloned().map(Dynamic::from).collect::<Array>()
+            })
+            .with_get("reply_to", |event: &mut Self| event.reply_to.clone())
+            .with_get("badges", |event: &mut Self| {
+                event.badges.iter().cloned().map(Dynamic::from).collect::<Array>()
+            })
+            .with_get("bits", |event: &mut Self| event.bits)
+            .with_get("amount", |event: &mut Self| event.amount)
+            .with_get("duration_secs", |event: &mut Self| event.duration_secs)
+            .with_get("is_broadcaster", |event: &mut Self| event.is_broadcaster)
+            .with_get("is_mod", |event: &mut Self| event.is_mod)
+            .with_get("is_vip", |event: &mut Self| event.is_vip)
+            .with_get("is_subscriber", |event: &mut Self| event.is_subscriber)
+            .with_get("is_first_message", |event: &mut Self| event.is_first_message)
+            .with_fn("to_string", |event: &mut Self| format!("{event:?}"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::engine::event::ScriptEvent;
+
+/// Roles ordered from least to most privileged, derived from the badges of a chat message.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Display, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    #[default]
+    Everyone,
+    Subscriber,
+    #[strum(to_string = "VIP")]
+    Vip,
+    Moderator,
+    Broadcaster,
+}
+
+impl UserRole {
+    pub fn of(event: &ScriptEvent) -> Self {
+        return if event.is_broadcaster {
+            UserRole::Broadcaster
+        } else if event.is_mod {
+            UserRole::Moderator
+        } else if event.is_vip {
+            UserRole::Vip
+        } else if event.is_subscriber {
+            UserRole::Subscriber
+        } else {
+            UserRole::Everyone
+        };
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionGuards {
+    pub min_role: UserRole,
+    pub global_cooldown_secs: u64,
+    pub user_cooldown_secs: u64,
+    /// Replied to chat messages that hit a cooldown, `{remaining}` is replaced with the seconds left. Empty means no
+    /// reply.
+    pub cooldown_reply: String,
+}
+
+pub enum GuardOutcome {
+    Allowed,
+    MissingRole,
+    OnCooldown { remaining: Duration, notify: bool },
+}
+
+/// Cooldown bookkeeping for a single action, kept across reloads.
+#[derive(Debug, Default)]
+pub struct CooldownState {
+    last_run: Option<Instant>,
+    last_run_by_user: HashMap<String, Instant>,
+    notified_until: HashMap<String, Instant>,
+}
+
+impl CooldownState {
+    /// Checks the guards for an event and records the run if it is allowed.
+    pub fn check(&mut self, guards: &ActionGuards, event: &ScriptEvent, now: Instant) -> GuardOutcome {
+        let is_chat = event.kind == "message";
+
+        if is_chat && UserRole::of(event) < guards.min_role {
+            return GuardOutcome::MissingRole;
+        }
+
+        let global_cooldown = Duration::from_secs(guards.global_cooldown_secs);
+        let user_cooldown = Duration::from_secs(guards.user_cooldown_secs);
+
+        self.last_run_by_user
+            .retain(|_, last_run| now.duration_since(*last_run) < user_cooldown);
+        self.notified_until.retain(|_, until| *until > now);
+
+        let global_remaining = self
+            .last_run
+            .map(|last_run| global_cooldown.saturating_sub(now.duration_since(last_run)))
+            .unwrap_or_default();
+
+        let user_remaining = match (is_chat, self.last_run_by_user.get(&event.user_id)) {
+            (true, Some(last_run)) => user_cooldown.saturating_sub(now.duration_since(*last_run)),
+            _ => Duration::ZERO,
+        };
+
+        let remaining = global_remaining.max(user_remaining);
+        if !remaining.is_zero() {
+            // only tell each user once per cooldown so the reply itself can't be used to spam
+            let notify = is_chat && !self.notified_until.contains_key(&event.user_id);
+            if notify {
+                self.notified_until.insert(event.user_id.clone(), now + remaining);
+            }
+
+            return GuardOutcome::OnCooldown { remaining, notify };
+        }
+
+        self.last_run = Some(now);
+        if is_chat && !user_cooldown.is_zero() {
+            self.last_run_by_user.insert(event.user_id.clone(), now);
+        }
+
+        return GuardOutcome::Allowed;
+    }
+}
+
+pub fn format_cooldown_reply(template: &str, remaining: Duration) -> String {
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+
+    return template.replace("{remaining}", &seconds.to_string());
+}
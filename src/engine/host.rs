@@ -0,0 +1,255 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, mpsc},
+    time::{Duration, Instant},
+};
+
+use rhai::{Engine, EvalAltResult};
+use twitch_api::{
+    extra::AnnouncementColor,
+    helix::{channels::ChannelInformation, chat::UpdateChatSettingsBody},
+};
+
+use crate::{
+    twitch::{
+        api::{
+            twitch_ban_user, twitch_delete_all_messages, twitch_delete_message, twitch_mod_user,
+            twitch_patch_chat_settings, twitch_send_announcement, twitch_send_message, twitch_send_reply,
+            twitch_shoutout_user, twitch_timeout_user, twitch_unban_user, twitch_unmod_user, twitch_untimeout_user,
+            twitch_unvip_user, twitch_vip_user,
+        },
+        types::TwitchAccount,
+    },
+    ui::state::AppStateDiff,
+};
+
+/// Something a script asked to do on twitch, executed by the ui thread with the logged in account.
+#[derive(Debug, Clone)]
+pub enum ActionCommand {
+    SendMessage(String),
+    SendReply { parent_message_id: String, message: String },
+    SendAnnouncement { message: String, color: AnnouncementColor },
+    DeleteMessage(String),
+    DeleteAllMessages,
+    TimeoutUser { user_name: String, duration: Duration },
+    UntimeoutUser(String),
+    BanUser(String),
+    UnbanUser(String),
+    ShoutoutUser(String),
+    VipUser(String),
+    UnvipUser(String),
+    ModUser(String),
+    UnmodUser(String),
+    PatchChatSettings(UpdateChatSettingsBody),
+}
+
+impl ActionCommand {
+    pub fn execute(self, diff_tx: &mpsc::Sender<AppStateDiff>, account: &TwitchAccount, channel: &ChannelInformation) {
+        match self {
+            ActionCommand::SendMessage(message) => twitch_send_message(diff_tx, account, channel, &message),
+            ActionCommand::SendReply {
+                parent_message_id,
+                message,
+            } => twitch_send_reply(diff_tx, account, channel, &parent_message_id, &message),
+            ActionCommand::SendAnnouncement { message, color } => {
+                twitch_send_announcement(diff_tx, account, channel, &message, color)
+            }
+            ActionCommand::DeleteMessage(message_id) => twitch_delete_message(diff_tx, account, channel, &message_id),
+            ActionCommand::DeleteAllMessages => twitch_delete_all_messages(diff_tx, account, channel),
+            ActionCommand::TimeoutUser { user_name, duration } => {
+                twitch_timeout_user(diff_tx, account, channel, &user_name, duration)
+            }
+            ActionCommand::UntimeoutUser(user_name) => twitch_untimeout_user(diff_tx, account, channel, &user_name),
+            ActionCommand::BanUser(user_name) => twitch_ban_user(diff_tx, account, channel, &user_name),
+            ActionCommand::UnbanUser(user_name) => twitch_unban_user(diff_tx, account, channel, &user_name),
+            ActionCommand::ShoutoutUser(user_name) => twitch_shoutout_user(diff_tx, account, channel, &user_name),
+            ActionCommand::VipUser(user_name) => twitch_vip_user(diff_tx, account, channel, &user_name),
+            ActionCommand::UnvipUser(user_name) => twitch_unvip_user(diff_tx, account, channel, &user_name),
+            ActionCommand::ModUser(user_name) => twitch_mod_user(diff_tx, account, channel, &user_name),
+            ActionCommand::UnmodUser(user_name) => twitch_unmod_user(diff_tx, account, channel, &user_name),
+            ActionCommand::PatchChatSettings(body) => twitch_patch_chat_settings(diff_tx, account, channel, body),
+        }
+    }
+}
+
+impl fmt::Display for ActionCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ActionCommand::SendMessage(message) => write!(f, "send: {message}"),
+            ActionCommand::SendReply { message, .. } => write!(f, "reply: {message}"),
+            ActionCommand::SendAnnouncement { message, color } => write!(f, "announce ({color:?}): {message}"),
+            ActionCommand::DeleteMessage(message_id) => write!(f, "delete message {message_id}"),
+            ActionCommand::DeleteAllMessages => write!(f, "clear chat"),
+            ActionCommand::TimeoutUser { user_name, duration } => {
+                write!(f, "timeout {user_name} for {}s", duration.as_secs())
+            }
+            ActionCommand::UntimeoutUser(user_name) => write!(f, "untimeout {user_name}"),
+            ActionCommand::BanUser(user_name) => write!(f, "ban {user_name}"),
+            ActionCommand::UnbanUser(user_name) => write!(f, "unban {user_name}"),
+            ActionCommand::ShoutoutUser(user_name) => write!(f, "shoutout {user_name}"),
+            ActionCommand::VipUser(user_name) => write!(f, "vip {user_name}"),
+            ActionCommand::UnvipUser(user_name) => write!(f, "unvip {user_name}"),
+            ActionCommand::ModUser(user_name) => write!(f, "mod {user_name}"),
+            ActionCommand::UnmodUser(user_name) => write!(f, "unmod {user_name}"),
+            ActionCommand::PatchChatSettings(body) => write!(f, "chat settings {body:?}"),
+        };
+    }
+}
+
+/// State shared between the engine and the registered host functions for a single run.
+#[derive(Debug, Default)]
+pub struct HostContext {
+    pub reply_to: Option<String>,
+    pub commands: Vec<ActionCommand>,
+    pub output: Vec<String>,
+    pub deadline: Option<Instant>,
+}
+
+pub type SharedHostContext = Arc<Mutex<HostContext>>;
+
+pub fn register_host_api(engine: &mut Engine, context: &SharedHostContext) {
+    let push = {
+        let context = context.clone();
+        move |command: ActionCommand| context.lock().unwrap().commands.push(command)
+    };
+
+    let p = push.clone();
+    engine.register_fn("send_message", move |message: &str| {
+        p(ActionCommand::SendMessage(message.to_owned()))
+    });
+
+    let p = push.clone();
+    let ctx = context.clone();
+    engine.register_fn("reply", move |message: &str| {
+        let reply_to = ctx.lock().unwrap().reply_to.clone();
+        match reply_to {
+            Some(parent_message_id) => p(ActionCommand::SendReply {
+                parent_message_id,
+                message: message.to_owned(),
+            }),
+            None => p(ActionCommand::SendMessage(message.to_owned())),
+        }
+    });
+
+    let p = push.clone();
+    engine.register_fn("announce", move |message: &str| {
+        p(ActionCommand::SendAnnouncement {
+            message: message.to_owned(),
+            color: AnnouncementColor::Primary,
+        })
+    });
+
+    let p = push.clone();
+    engine.register_fn(
+        "announce",
+        move |message: &str, color: &str| -> Result<(), Box<EvalAltResult>> {
+            let color = parse_announcement_color(color)?;
+            p(ActionCommand::SendAnnouncement {
+                message: message.to_owned(),
+                color,
+            });
+            Ok(())
+        },
+    );
+
+    let p = push.clone();
+    engine.register_fn("delete_message", move |message_id: &str| {
+        p(ActionCommand::DeleteMessage(message_id.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("clear_chat", move || p(ActionCommand::DeleteAllMessages));
+
+    let p = push.clone();
+    engine.register_fn("timeout", move |user_name: &str, seconds: i64| {
+        p(ActionCommand::TimeoutUser {
+            user_name: user_name.to_owned(),
+            duration: Duration::from_secs(seconds.max(1) as u64),
+        })
+    });
+
+    let p = push.clone();
+    engine.register_fn("untimeout", move |user_name: &str| {
+        p(ActionCommand::UntimeoutUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("ban", move |user_name: &str| {
+        p(ActionCommand::BanUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("unban", move |user_name: &str| {
+        p(ActionCommand::UnbanUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("shoutout", move |user_name: &str| {
+        p(ActionCommand::ShoutoutUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("vip", move |user_name: &str| {
+        p(ActionCommand::VipUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("unvip", move |user_name: &str| {
+        p(ActionCommand::UnvipUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("make_mod", move |user_name: &str| {
+        p(ActionCommand::ModUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("unmod", move |user_name: &str| {
+        p(ActionCommand::UnmodUser(user_name.to_owned()))
+    });
+
+    let p = push.clone();
+    engine.register_fn("set_slow_mode", move |seconds: i64| {
+        let mut body = UpdateChatSettingsBody::default();
+        body.slow_mode = Some(seconds > 0);
+        if seconds > 0 {
+            body.slow_mode_wait_time = Some(seconds as u64);
+        }
+        p(ActionCommand::PatchChatSettings(body))
+    });
+
+    let p = push.clone();
+    engine.register_fn("set_emote_only", move |enabled: bool| {
+        let mut body = UpdateChatSettingsBody::default();
+        body.emote_mode = Some(enabled);
+        p(ActionCommand::PatchChatSettings(body))
+    });
+
+    let p = push.clone();
+    engine.register_fn("set_followers_only", move |minutes: i64| {
+        let mut body = UpdateChatSettingsBody::default();
+        body.follower_mode = Some(minutes >= 0);
+        if minutes > 0 {
+            body.follower_mode_duration = Some(minutes as u64); // duration is in minutes
+        }
+        p(ActionCommand::PatchChatSettings(body))
+    });
+
+    let p = push;
+    engine.register_fn("set_subscribers_only", move |enabled: bool| {
+        let mut body = UpdateChatSettingsBody::default();
+        body.subscriber_mode = Some(enabled);
+        p(ActionCommand::PatchChatSettings(body))
+    });
+}
+
+fn parse_announcement_color(color: &str) -> Result<AnnouncementColor, Box<EvalAltResult>> {
+    return match color.to_lowercase().as_str() {
+        "primary" => Ok(AnnouncementColor::Primary),
+        "blue" => Ok(AnnouncementColor::Blue),
+        "green" => Ok(AnnouncementColor::Green),
+        "orange" => Ok(AnnouncementColor::Orange),
+        "purple" => Ok(AnnouncementColor::Purple),
+        _ => Err(format!("Unknown announcement color '{color}'.").into()),
+    };
+}
@@ -1 +1,614 @@
+pub mod bundle;
+pub mod event;
+pub mod guard;
+pub mod host;
+pub mod modules;
+pub mod store;
+pub mod trigger;
 
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use chrono::Local;
+use egui_toast::ToastKind;
+use rhai::{AST, Array, Dynamic, Engine, EvalAltResult, Module, Scope};
+use tracing::{debug, info, warn};
+
+use crate::{
+    app::App,
+    engine::{
+        event::ScriptEvent,
+        guard::{CooldownState, GuardOutcome, UserRole, format_cooldown_reply},
+        host::{ActionCommand, SharedHostContext, register_host_api},
+        modules::{DatabaseModuleResolver, SharedModuleTracker},
+        store::{GLOBAL_BUCKET, ScriptStore, action_bucket},
+        trigger::CompiledTrigger,
+    },
+    models::{
+        SqlitePool,
+        action::{Action, ActionConfig, ActionLimits},
+        action_log::{ActionLogEntry, ActionLogRetention, ActionRunResult, NewActionLogEntry},
+        reward_rule::RewardRule,
+    },
+    twitch::types::{PendingRedemption, TwitchEvent},
+    ui::state::AppStateDiff,
+};
+
+const TEST_BUCKET_PREFIX: &str = "test:";
+/// Ticks arrive every second.
+const LOG_PRUNE_INTERVAL_TICKS: u32 = 600;
+
+pub struct CompiledAction {
+    pub action: Action,
+    pub config: ActionConfig,
+    pub triggers: Vec<CompiledTrigger>,
+    pub ast: AST,
+    /// Library modules the script imports, directly or through other modules.
+    pub imports: BTreeSet<String>,
+}
+
+pub struct ActionEngine {
+    engine: Engine,
+    host: SharedHostContext,
+    modules: SharedModuleTracker,
+    db_pool: SqlitePool,
+    state_diff_tx: mpsc::Sender<AppStateDiff>,
+    actions: Vec<CompiledAction>,
+    /// Actions that failed to compile, retried when a library changes.
+    uncompiled: Vec<Action>,
+    cooldowns: HashMap<i32, CooldownState>,
+    /// Action ids bound to channel point rewards, by reward id.
+    reward_actions: HashMap<String, i32>,
+    log_retention: ActionLogRetention,
+    ticks_since_prune: u32,
+}
+
+impl ActionEngine {
+    pub fn new(db_pool: SqlitePool, state_diff_tx: mpsc::Sender<AppStateDiff>) -> Self {
+        let host = SharedHostContext::default();
+        let modules = SharedModuleTracker::default();
+
+        return Self {
+            engine: create_script_engine(&db_pool, &host, &modules),
+            host,
+            modules,
+            db_pool,
+            state_diff_tx,
+            actions: Vec::new(),
+            uncompiled: Vec::new(),
+            cooldowns: HashMap::new(),
+            reward_actions: HashMap::new(),
+            log_retention: ActionLogRetention::default(),
+            ticks_since_prune: 0,
+        };
+    }
+
+    pub fn reload(&mut self) -> Result<()> {
+        let actions = Action::load_all(&self.db_pool)?;
+
+        self.actions.clear();
+        self.uncompiled.clear();
+        for action in actions {
+            self.compile_and_add(action);
+        }
+
+        self.cooldowns
+            .retain(|id, _| self.actions.iter().any(|compiled| compiled.action.id == *id));
+
+        info!("Loaded {} actions.", self.actions.len());
+
+        self.reload_reward_rules();
+
+        return Ok(());
+    }
+
+    pub fn reload_reward_rules(&mut self) {
+        match RewardRule::load_all(&self.db_pool) {
+            Ok(rules) => {
+                self.reward_actions = rules
+                    .into_iter()
+                    .filter_map(|rule| Some((rule.reward_id, rule.action_id?)))
+                    .collect();
+            }
+            Err(err) => warn!("Failed to load reward rules: {}", err),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &TwitchEvent) {
+        let Some(script_event) = ScriptEvent::from_twitch_event(event) else {
+            return;
+        };
+
+        let now = Instant::now();
+
+        let bound_action = match event {
+            TwitchEvent::Redemption(redemption) => self
+                .reward_actions
+                .get(redemption.reward.id.as_str())
+                .and_then(|action_id| {
+                    self.actions
+                        .iter()
+                        .position(|compiled| compiled.action.id == *action_id && compiled.config.enabled)
+                })
+                .map(|index| (index, PendingRedemption::from(redemption))),
+            _ => None,
+        };
+
+        let mut matched = Vec::new();
+        for (index, compiled) in self.actions.iter().enumerate() {
+            if !compiled.config.enabled || bound_action.as_ref().is_some_and(|(bound, _)| *bound == index) {
+                continue;
+            }
+
+            let Some(args) = compiled
+                .triggers
+                .iter()
+                .find_map(|trigger| trigger.matches_event(&script_event))
+            else {
+                continue;
+            };
+
+            let guards = &compiled.config.guards;
+            match self
+                .cooldowns
+                .entry(compiled.action.id)
+                .or_default()
+                .check(guards, &script_event, now)
+            {
+                GuardOutcome::Allowed => matched.push((index, args)),
+                GuardOutcome::MissingRole => {
+                    debug!(
+                        "Action '{}' requires {}, {} is {}.",
+                        compiled.action.name,
+                        guards.min_role,
+                        script_event.user_login,
+                        UserRole::of(&script_event)
+                    );
+                }
+                GuardOutcome::OnCooldown { remaining, notify } => {
+                    if notify && !guards.cooldown_reply.is_empty() {
+                        let command = ActionCommand::SendReply {
+                            parent_message_id: script_event.message_id.clone(),
+                            message: format_cooldown_reply(&guards.cooldown_reply, remaining),
+                        };
+
+                        self.state_diff_tx
+                            .send(AppStateDiff::RunActionCommands(vec![command]))
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        for (index, args) in matched {
+            self.run_action(index, script_event.clone(), args);
+        }
+
+        // bound actions run for every redemption of their reward, without triggers or guards
+        if let Some((index, redemption)) = bound_action {
+            let success = self.run_action(index, script_event, Vec::new());
+
+            self.state_diff_tx
+                .send(AppStateDiff::RedemptionActionFinished { redemption, success })
+                .unwrap();
+        }
+    }
+
+    /// Recompiles the actions importing `module_name` and retries the ones that failed to compile before.
+    pub fn recompile_dependents(&mut self, module_name: &str) {
+        let mut stale = std::mem::take(&mut self.uncompiled);
+
+        let mut index = 0;
+        while index < self.actions.len() {
+            if self.actions[index].imports.contains(module_name) {
+                stale.push(self.actions.remove(index).action);
+            } else {
+                index += 1;
+            }
+        }
+
+        if stale.is_empty() {
+            return;
+        }
+
+        info!(
+            "Recompiling {} actions after module '{}' changed.",
+            stale.len(),
+            module_name
+        );
+
+        for action in stale {
+            self.compile_and_add(action);
+        }
+        self.actions.sort_by_key(|compiled| compiled.action.id);
+    }
+
+    fn compile_and_add(&mut self, action: Action) {
+        match compile_action(&self.engine, &self.modules, action.clone()) {
+            Ok(compiled) => self.actions.push(compiled),
+            Err(err) => {
+                warn!("Failed to compile action: {}", err);
+                self.uncompiled.push(action);
+            }
+        }
+    }
+
+    pub fn set_log_retention(&mut self, retention: ActionLogRetention) {
+        self.log_retention = retention;
+        self.prune_log();
+    }
+
+    fn prune_log(&mut self) {
+        self.ticks_since_prune = 0;
+
+        match ActionLogEntry::prune(&self.db_pool, self.log_retention) {
+            Ok(0) => {}
+            Ok(deleted) => debug!("Pruned {} action log entries.", deleted),
+            Err(err) => warn!("Failed to prune action log: {}", err),
+        }
+    }
+
+    pub fn handle_tick(&mut self) {
+        let now = Local::now();
+
+        self.ticks_since_prune += 1;
+        if self.ticks_since_prune >= LOG_PRUNE_INTERVAL_TICKS {
+            self.prune_log();
+        }
+
+        let mut due = Vec::new();
+        for (index, compiled) in self.actions.iter_mut().enumerate() {
+            if !compiled.config.enabled {
+                continue;
+            }
+
+            let mut is_due = false;
+            for trigger in &mut compiled.triggers {
+                is_due |= trigger.poll_schedule(now);
+            }
+
+            if is_due {
+                due.push(index);
+            }
+        }
+
+        for index in due {
+            let script_event = ScriptEvent {
+                kind: String::from("schedule"),
+                ..ScriptEvent::default()
+            };
+
+            self.run_action(index, script_event, Vec::new());
+        }
+    }
+
+    /// Returns whether the action ran without an error.
+    fn run_action(&mut self, index: usize, script_event: ScriptEvent, args: Vec<String>) -> bool {
+        let compiled = &self.actions[index];
+        let started_at = Local::now();
+        let event_summary = script_event.summary();
+
+        let start = Instant::now();
+        let run = execute_action(
+            &mut self.engine,
+            &self.host,
+            &self.db_pool,
+            compiled,
+            script_event,
+            args,
+            "",
+        );
+        let duration = start.elapsed();
+
+        if let Some(error) = &run.error {
+            warn!("Action '{}' failed: {}", compiled.action.name, error);
+        }
+
+        let result = match (&run.error, run.limit_violation) {
+            (None, _) => ActionRunResult::Success,
+            (Some(_), false) => ActionRunResult::Error,
+            (Some(_), true) => ActionRunResult::LimitExceeded,
+        };
+
+        let entry = NewActionLogEntry {
+            action_id: compiled.action.id,
+            action_name: compiled.action.name.clone(),
+            event_summary,
+            started_at: started_at.timestamp_millis(),
+            duration_us: duration.as_micros() as i64,
+            result: <&str>::from(result).to_owned(),
+            error: run.error.clone(),
+            error_line: run.error_line.map(|line| line as i32),
+            error_column: run.error_column.map(|column| column as i32),
+            messages: serde_json::to_string(&run.commands.iter().map(ToString::to_string).collect::<Vec<_>>())
+                .unwrap_or_default(),
+        };
+
+        if let Err(err) = ActionLogEntry::insert(&self.db_pool, &entry) {
+            warn!("Failed to write action log: {}", err);
+        }
+
+        if !run.commands.is_empty() {
+            self.state_diff_tx
+                .send(AppStateDiff::RunActionCommands(run.commands))
+                .unwrap();
+        }
+
+        let success = run.error.is_none();
+        if run.limit_violation {
+            self.handle_limit_violation(index, run.error.unwrap_or_default());
+        }
+
+        return success;
+    }
+
+    fn handle_limit_violation(&mut self, index: usize, error: String) {
+        let compiled = &mut self.actions[index];
+
+        App::show_toast(
+            &self.state_diff_tx,
+            ToastKind::Error,
+            &format!("Action '{}' was stopped: {}", compiled.action.name, error),
+        );
+
+        let failure_count = match Action::record_failure(&self.db_pool, compiled.action.id) {
+            Ok(failure_count) => failure_count,
+            Err(err) => {
+                warn!("Failed to record action failure: {}", err);
+                return;
+            }
+        };
+        compiled.action.failure_count = failure_count;
+
+        if failure_count < compiled.config.limits.max_failures {
+            return;
+        }
+
+        compiled.config.enabled = false;
+        if let Err(err) = Action::set_config(&self.db_pool, compiled.action.id, &compiled.config) {
+            warn!("Failed to disable action: {}", err);
+        }
+
+        App::show_toast(
+            &self.state_diff_tx,
+            ToastKind::Warning,
+            &format!(
+                "Action '{}' was disabled after {} limit violations.",
+                compiled.action.name, failure_count
+            ),
+        );
+
+        self.state_diff_tx
+            .send(AppStateDiff::ActionDisabled(compiled.action.id))
+            .unwrap();
+    }
+}
+
+/// Everything a single run of an action produced.
+#[derive(Debug, Default, Clone)]
+pub struct ActionRun {
+    pub output: Vec<String>,
+    pub commands: Vec<ActionCommand>,
+    pub error: Option<String>,
+    pub error_line: Option<usize>,
+    pub error_column: Option<usize>,
+    pub limit_violation: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ActionTestResult {
+    pub triggered: bool,
+    pub missing_role: bool,
+    pub run: ActionRun,
+}
+
+/// Runs an action against an event without sending anything to twitch, storage goes to separate test buckets.
+pub fn test_action(db_pool: &SqlitePool, action: Action, event: &TwitchEvent) -> ActionTestResult {
+    let host = SharedHostContext::default();
+    let modules = SharedModuleTracker::default();
+    let mut engine = create_script_engine(db_pool, &host, &modules);
+
+    let compiled = match compile_action(&engine, &modules, action) {
+        Ok(compiled) => compiled,
+        Err(err) => {
+            return ActionTestResult {
+                run: ActionRun {
+                    error: Some(err.to_string()),
+                    ..ActionRun::default()
+                },
+                ..ActionTestResult::default()
+            };
+        }
+    };
+
+    let Some(script_event) = ScriptEvent::from_twitch_event(event) else {
+        return ActionTestResult::default();
+    };
+
+    let args = compiled
+        .triggers
+        .iter()
+        .find_map(|trigger| trigger.matches_event(&script_event));
+    let triggered = args.is_some();
+    let missing_role = script_event.kind == "message" && UserRole::of(&script_event) < compiled.config.guards.min_role;
+
+    let run = execute_action(
+        &mut engine,
+        &host,
+        db_pool,
+        &compiled,
+        script_event,
+        args.unwrap_or_default(),
+        TEST_BUCKET_PREFIX,
+    );
+
+    return ActionTestResult {
+        triggered,
+        missing_role,
+        run,
+    };
+}
+
+pub fn create_script_engine(db_pool: &SqlitePool, host: &SharedHostContext, modules: &SharedModuleTracker) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.set_module_resolver(DatabaseModuleResolver::new(db_pool.clone(), modules.clone()));
+    // runs use the limits of their action, compiling and importing modules the default ones
+    apply_limits(&mut engine, &ActionLimits::default());
+
+    engine.build_type::<ScriptEvent>();
+    engine.build_type::<ScriptStore>();
+    register_host_api(&mut engine, host);
+
+    let print_host = host.clone();
+    engine.on_print(move |text| {
+        info!("[action] {text}");
+        print_host.lock().unwrap().output.push(text.to_owned());
+    });
+    engine.on_debug(|text, source, pos| debug!("[action] {} @ {pos}: {text}", source.unwrap_or("script")));
+
+    let progress_host = host.clone();
+    let progress_modules = modules.clone();
+    engine.on_progress(move |operations| {
+        if operations % 256 != 0 {
+            return None;
+        }
+
+        let deadline = progress_host
+            .lock()
+            .unwrap()
+            .deadline
+            .or(progress_modules.lock().unwrap().deadline)?;
+        if Instant::now() >= deadline {
+            return Some(Dynamic::from("timed out"));
+        }
+
+        return None;
+    });
+
+    return engine;
+}
+
+/// Evaluates a library like an import does, so top-level code that hits the limits is caught before it is saved.
+pub fn check_library(db_pool: &SqlitePool, name: &str, source: &str) -> Result<()> {
+    let host = SharedHostContext::default();
+    let modules = SharedModuleTracker::default();
+    let engine = create_script_engine(db_pool, &host, &modules);
+
+    let ast = engine.compile(source)?;
+
+    host.lock().unwrap().deadline = Some(Instant::now() + Duration::from_millis(ActionLimits::default().timeout_ms));
+    let result = Module::eval_ast_as_new(Scope::new(), &ast, &engine);
+    host.lock().unwrap().deadline = None;
+
+    if let Err(err) = result
+        && is_limit_violation(&err)
+    {
+        bail!("'{name}' hits the action limits when imported: {err}");
+    }
+
+    return Ok(());
+}
+
+/// Compiles an action with its imports resolved and embedded, so runs never hit the database for modules.
+pub fn compile_action(engine: &Engine, modules: &SharedModuleTracker, action: Action) -> Result<CompiledAction> {
+    let source = action.script_source()?;
+    let config = action.parsed_config()?;
+
+    modules.lock().unwrap().take_resolved();
+    let ast = engine.compile_into_self_contained(&Scope::new(), source);
+    let imports = modules.lock().unwrap().take_resolved();
+    let ast = ast.map_err(|err| anyhow::anyhow!("'{}': {}", action.name, err))?;
+
+    let triggers = config
+        .triggers
+        .iter()
+        .map(CompiledTrigger::compile)
+        .collect::<Result<Vec<_>>>()
+        .map_err(|err| anyhow::anyhow!("'{}': {}", action.name, err))?;
+
+    return Ok(CompiledAction {
+        action,
+        config,
+        triggers,
+        ast,
+        imports,
+    });
+}
+
+fn apply_limits(engine: &mut Engine, limits: &ActionLimits) {
+    engine
+        .set_max_operations(limits.max_operations)
+        .set_max_call_levels(limits.max_call_depth)
+        .set_max_string_size(limits.max_string_size)
+        .set_max_array_size(limits.max_array_size)
+        .set_max_map_size(limits.max_array_size);
+}
+
+fn is_limit_violation(err: &EvalAltResult) -> bool {
+    return matches!(
+        err.unwrap_inner(),
+        EvalAltResult::ErrorTooManyOperations(..)
+            | EvalAltResult::ErrorStackOverflow(..)
+            | EvalAltResult::ErrorDataTooLarge(..)
+            | EvalAltResult::ErrorTerminated(..)
+    );
+}
+
+fn execute_action(
+    engine: &mut Engine,
+    host: &SharedHostContext,
+    db_pool: &SqlitePool,
+    compiled: &CompiledAction,
+    script_event: ScriptEvent,
+    args: Vec<String>,
+    bucket_prefix: &str,
+) -> ActionRun {
+    {
+        let mut host = host.lock().unwrap();
+        host.commands.clear();
+        host.output.clear();
+        host.reply_to = (script_event.kind == "message").then(|| script_event.message_id.clone());
+        host.deadline = Some(Instant::now() + Duration::from_millis(compiled.config.limits.timeout_ms));
+    }
+
+    apply_limits(engine, &compiled.config.limits);
+
+    let mut scope = Scope::new();
+    scope.push_constant("event", script_event);
+    scope.push_constant("args", args.into_iter().map(Dynamic::from).collect::<Array>());
+    scope.push_constant(
+        "store",
+        ScriptStore::new(
+            db_pool.clone(),
+            format!("{bucket_prefix}{}", action_bucket(compiled.action.id)),
+        ),
+    );
+    scope.push_constant(
+        "global_store",
+        ScriptStore::new(db_pool.clone(), format!("{bucket_prefix}{GLOBAL_BUCKET}")),
+    );
+
+    let result = engine.run_ast_with_scope(&mut scope, &compiled.ast);
+    // the engine is shared, later compiles and runs must not inherit the limits of this action
+    apply_limits(engine, &ActionLimits::default());
+    let limit_violation = result.as_ref().is_err_and(|err| is_limit_violation(err));
+    let position = result.as_ref().err().map(|err| err.position()).unwrap_or_default();
+    let error = result.err().map(|err| err.to_string());
+
+    let mut host = host.lock().unwrap();
+    host.deadline = None;
+
+    return ActionRun {
+        output: std::mem::take(&mut host.output),
+        commands: std::mem::take(&mut host.commands),
+        error,
+        error_line: position.line(),
+        error_column: position.position(),
+        limit_violation,
+    };
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rhai::{Engine, EvalAltResult, Module, ModuleResolver, Position, Scope, Shared};
+
+use crate::models::{SqlitePool, action::ActionLimits, script_module::ScriptModule};
+
+/// Remembers which modules got resolved so the engine knows what an action depends on.
+#[derive(Debug, Default)]
+pub struct ModuleTracker {
+    resolved: BTreeSet<String>,
+    loading: Vec<String>,
+    /// Until when the top-level code of the imported modules may run, they are evaluated while compiling.
+    pub deadline: Option<Instant>,
+}
+
+pub type SharedModuleTracker = Arc<Mutex<ModuleTracker>>;
+
+impl ModuleTracker {
+    pub fn take_resolved(&mut self) -> BTreeSet<String> {
+        return std::mem::take(&mut self.resolved);
+    }
+}
+
+/// Resolves `import "name"` against the `script_modules` table.
+pub struct DatabaseModuleResolver {
+    pool: SqlitePool,
+    tracker: SharedModuleTracker,
+}
+
+impl DatabaseModuleResolver {
+    pub fn new(pool: SqlitePool, tracker: SharedModuleTracker) -> Self {
+        return Self { pool, tracker };
+    }
+
+    fn load(&self, engine: &Engine, path: &str, pos: Position) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        let source = ScriptModule::load_source(&self.pool, path)
+            .map_err(|err| format!("Failed to load module '{path}': {err}"))?
+            .ok_or_else(|| EvalAltResult::ErrorModuleNotFound(path.to_owned(), pos))?;
+
+        let mut ast = engine
+            .compile(source)
+            .map_err(|err| EvalAltResult::ErrorInModule(path.to_owned(), err.into(), pos))?;
+        ast.set_source(path);
+
+        let module = Module::eval_ast_as_new(Scope::new(), &ast, engine)
+            .map_err(|err| EvalAltResult::ErrorInModule(path.to_owned(), err, pos))?;
+
+        return Ok(module.into());
+    }
+}
+
+impl ModuleResolver for DatabaseModuleResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        _source: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        {
+            let mut tracker = self.tracker.lock().unwrap();
+
+            if tracker.loading.iter().any(|loading| loading == path) {
+                let chain = tracker.loading.join(" -> ");
+                return Err(format!("Circular import of module '{path}': {chain} -> {path}").into());
+            }
+
+            if tracker.loading.is_empty() {
+                let timeout = Duration::from_millis(ActionLimits::default().timeout_ms);
+                tracker.deadline = Some(Instant::now() + timeout);
+            }
+
+            tracker.loading.push(path.to_owned());
+            tracker.resolved.insert(path.to_owned());
+        }
+
+        let result = self.load(engine, path, pos);
+
+        {
+            let mut tracker = self.tracker.lock().unwrap();
+
+            tracker.loading.pop();
+            if tracker.loading.is_empty() {
+                tracker.deadline = None;
+            }
+        }
+
+        return result;
+    }
+}
@@ -0,0 +1,78 @@
+use rhai::{Array, CustomType, Dynamic, EvalAltResult, TypeBuilder};
+
+use crate::models::{SqlitePool, kv_store::KvStore};
+
+pub const GLOBAL_BUCKET: &str = "global";
+
+pub fn action_bucket(action_id: i32) -> String {
+    return format!("action:{action_id}");
+}
+
+/// A `KvStore` bucket scripts see as `store` (per action) or `global_store`.
+#[derive(Clone)]
+pub struct ScriptStore {
+    pool: SqlitePool,
+    bucket: String,
+}
+
+impl ScriptStore {
+    pub fn new(pool: SqlitePool, bucket: String) -> Self {
+        return Self { pool, bucket };
+    }
+
+    fn get(&mut self, key: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+        let value = KvStore::get_value::<serde_json::Value>(&self.pool, &self.bucket, key).map_err(to_script_error)?;
+
+        return match value {
+            Some(value) => rhai::serde::to_dynamic(value),
+            None => Ok(Dynamic::UNIT),
+        };
+    }
+
+    fn set(&mut self, key: &str, value: Dynamic) -> Result<(), Box<EvalAltResult>> {
+        let value = rhai::serde::from_dynamic::<serde_json::Value>(&value)?;
+
+        return KvStore::set_value(&self.pool, self.bucket.clone(), key.to_owned(), value).map_err(to_script_error);
+    }
+
+    fn incr_by(&mut self, key: &str, by: i64) -> Result<i64, Box<EvalAltResult>> {
+        let current = KvStore::get_value::<serde_json::Value>(&self.pool, &self.bucket, key)
+            .map_err(to_script_error)?
+            .and_then(|value| value.as_i64())
+            .unwrap_or(0);
+
+        let next = current
+            .checked_add(by)
+            .ok_or_else(|| to_script_error(anyhow::anyhow!("{key} would overflow")))?;
+        KvStore::set_value(&self.pool, self.bucket.clone(), key.to_owned(), next).map_err(to_script_error)?;
+
+        return Ok(next);
+    }
+
+    fn keys(&mut self) -> Result<Array, Box<EvalAltResult>> {
+        let keys = KvStore::get_keys(&self.pool, &self.bucket).map_err(to_script_error)?;
+
+        return Ok(keys.into_iter().map(Dynamic::from).collect());
+    }
+
+    fn delete(&mut self, key: &str) -> Result<bool, Box<EvalAltResult>> {
+        return KvStore::delete_value(&self.pool, &self.bucket, key).map_err(to_script_error);
+    }
+}
+
+impl CustomType for ScriptStore {
+    fn build(mut builder: TypeBuilder<Self>) {
+        builder
+            .with_name("Store")
+            .with_fn("get", Self::get)
+            .with_fn("set", Self::set)
+            .with_fn("incr", |store: &mut Self, key: &str| store.incr_by(key, 1))
+            .with_fn("incr", Self::incr_by)
+            .with_fn("keys", Self::keys)
+            .with_fn("delete", Self::delete);
+    }
+}
+
+fn to_script_error(err: anyhow::Error) -> Box<EvalAltResult> {
+    return format!("Store error: {err}").into();
+}
@@ -0,0 +1,192 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use cron::Schedule;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::engine::event::ScriptEvent;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// `!name` at the start of a chat message, `name` is stored without the prefix.
+    Command { name: String },
+    /// Regex matched against the text of a chat message.
+    Regex { pattern: String },
+    /// A specific kind of twitch event.
+    Event { kind: EventKind },
+    /// Every `seconds` seconds.
+    Interval { seconds: u64 },
+    /// A cron expression, either 5 fields (minute precision) or 6 fields (second precision).
+    Cron { expression: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Message,
+    Sub,
+    Resub,
+    SubGift,
+    SubMysteryGift,
+    Raid,
+    BitsBadgeTier,
+    Announcement,
+    ChatCleared,
+    MessageDeleted,
+    UserTimedOut,
+    UserBanned,
+    SlowMode,
+    EmoteOnly,
+    FollowersOnly,
+    SubscribersOnly,
+    Join,
+    Part,
+    Notice,
+    Whisper,
+    Redemption,
+}
+
+impl EventKind {
+    pub fn matches(&self, event: &ScriptEvent) -> bool {
+        let (kind, sub_kind) = match self {
+            EventKind::Message => ("message", None),
+            EventKind::Sub => ("user_notice", Some("sub")),
+            EventKind::Resub => ("user_notice", Some("resub")),
+            EventKind::SubGift => ("user_notice", Some("subgift")),
+            EventKind::SubMysteryGift => ("user_notice", Some("submysterygift")),
+            EventKind::Raid => ("user_notice", Some("raid")),
+            EventKind::BitsBadgeTier => ("user_notice", Some("bitsbadgetier")),
+            EventKind::Announcement => ("user_notice", Some("announcement")),
+            EventKind::ChatCleared => ("clear_chat", Some("clear")),
+            EventKind::MessageDeleted => ("clear_msg", None),
+            EventKind::UserTimedOut => ("clear_chat", Some("timeout")),
+            EventKind::UserBanned => ("clear_chat", Some("ban")),
+            EventKind::SlowMode => ("room_state", Some("slow_mode")),
+            EventKind::EmoteOnly => ("room_state", Some("emote_only")),
+            EventKind::FollowersOnly => ("room_state", Some("followers_only")),
+            EventKind::SubscribersOnly => ("room_state", Some("subscribers_only")),
+            EventKind::Join => ("join", None),
+            EventKind::Part => ("part", None),
+            EventKind::Notice => ("notice", None),
+            EventKind::Whisper => ("whisper", None),
+            EventKind::Redemption => ("redemption", None),
+        };
+
+        return event.kind == kind && sub_kind.is_none_or(|sub_kind| event.sub_kind == sub_kind);
+    }
+}
+
+pub enum CompiledTrigger {
+    Command(String),
+    Regex(Regex),
+    Event(EventKind),
+    Interval {
+        every: Duration,
+        last_run: Instant,
+    },
+    Cron {
+        schedule: Box<Schedule>,
+        next_run: Option<DateTime<Local>>,
+    },
+}
+
+impl CompiledTrigger {
+    pub fn compile(trigger: &Trigger) -> Result<Self> {
+        return Ok(match trigger {
+            Trigger::Command { name } => {
+                CompiledTrigger::Command(format!("!{}", name.trim().trim_start_matches('!').to_lowercase()))
+            }
+            Trigger::Regex { pattern } => CompiledTrigger::Regex(Regex::new(pattern)?),
+            Trigger::Event { kind } => CompiledTrigger::Event(*kind),
+            Trigger::Interval { seconds } => CompiledTrigger::Interval {
+                every: Duration::from_secs((*seconds).max(1)),
+                last_run: Instant::now(),
+            },
+            Trigger::Cron { expression } => {
+                let schedule = parse_cron(expression)?;
+                let next_run = schedule.upcoming(Local).next();
+
+                CompiledTrigger::Cron {
+                    schedule: Box::new(schedule),
+                    next_run,
+                }
+            }
+        });
+    }
+
+    /// Returns the command arguments if this trigger matches the event.
+    pub fn matches_event(&self, event: &ScriptEvent) -> Option<Vec<String>> {
+        return match self {
+            CompiledTrigger::Command(command) => {
+                if event.kind != "message" {
+                    return None;
+                }
+
+                let mut words = event.text.split_whitespace();
+                let first = words.next()?;
+
+                if !first.eq_ignore_ascii_case(command) {
+                    return None;
+                }
+
+                Some(words.map(String::from).collect())
+            }
+            CompiledTrigger::Regex(regex) => {
+                if event.kind != "message" || !regex.is_match(&event.text) {
+                    return None;
+                }
+
+                Some(Vec::new())
+            }
+            CompiledTrigger::Event(kind) => kind.matches(event).then(Vec::new),
+            CompiledTrigger::Interval { .. } | CompiledTrigger::Cron { .. } => None,
+        };
+    }
+
+    /// Returns true if a schedule is due, and advances it.
+    pub fn poll_schedule(&mut self, now: DateTime<Local>) -> bool {
+        return match self {
+            CompiledTrigger::Interval { every, last_run } => {
+                if last_run.elapsed() < *every {
+                    return false;
+                }
+
+                *last_run = Instant::now();
+                true
+            }
+            CompiledTrigger::Cron { schedule, next_run } => {
+                let Some(next) = *next_run else {
+                    return false;
+                };
+
+                if next > now {
+                    return false;
+                }
+
+                *next_run = schedule.after(&now).next();
+                true
+            }
+            _ => false,
+        };
+    }
+}
+
+pub fn parse_cron(expression: &str) -> Result<Schedule> {
+    let expression = expression.trim();
+
+    // the cron crate wants seconds, accept the common 5 field format too
+    let schedule = if expression.split_whitespace().count() == 5 {
+        Schedule::from_str(&format!("0 {expression}"))?
+    } else {
+        Schedule::from_str(expression)?
+    };
+
+    return Ok(schedule);
+}
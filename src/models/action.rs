@@ -0,0 +1,171 @@
+use anyhow::Result;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{guard::ActionGuards, trigger::Trigger},
+    models::SqlitePool,
+};
+
+#[derive(Debug, Default, Clone, Insertable, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::actions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Action {
+    pub id: i32,
+    pub name: String,
+    pub script: Vec<u8>,
+    pub config: Vec<u8>,
+    pub failure_count: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionConfig {
+    pub enabled: bool,
+    pub triggers: Vec<Trigger>,
+    pub guards: ActionGuards,
+    pub limits: ActionLimits,
+}
+
+impl Default for ActionConfig {
+    fn default() -> Self {
+        return Self {
+            enabled: true,
+            triggers: Vec::new(),
+            guards: ActionGuards::default(),
+            limits: ActionLimits::default(),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionLimits {
+    pub max_operations: u64,
+    pub max_call_depth: usize,
+    pub max_string_size: usize,
+    pub max_array_size: usize,
+    pub timeout_ms: u64,
+    /// Limit violations after which the action gets disabled.
+    pub max_failures: i32,
+}
+
+impl Default for ActionLimits {
+    fn default() -> Self {
+        return Self {
+            max_operations: 100_000,
+            max_call_depth: 32,
+            max_string_size: 10_000,
+            max_array_size: 1_000,
+            timeout_ms: 1_000,
+            max_failures: 3,
+        };
+    }
+}
+
+impl Action {
+    pub fn load_all(pool: &SqlitePool) -> Result<Vec<Action>> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        let all_actions = actions::table
+            .order(actions::id.asc())
+            .select(Action::as_select())
+            .load(&mut db)?;
+
+        return Ok(all_actions);
+    }
+
+    pub fn insert(pool: &SqlitePool, name: &str, script: &str, config: &ActionConfig) -> Result<i32> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        let id = diesel::insert_into(actions::table)
+            .values((
+                actions::name.eq(name),
+                actions::script.eq(script.as_bytes()),
+                actions::config.eq(serde_json::to_vec(config)?),
+            ))
+            .returning(actions::id)
+            .get_result::<i32>(&mut db)?;
+
+        return Ok(id);
+    }
+
+    pub fn update(pool: &SqlitePool, id: i32, name: &str, script: &str, config: &ActionConfig) -> Result<()> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        diesel::update(actions::table.find(id))
+            .set((
+                actions::name.eq(name),
+                actions::script.eq(script.as_bytes()),
+                actions::config.eq(serde_json::to_vec(config)?),
+            ))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn delete(pool: &SqlitePool, id: i32) -> Result<()> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        diesel::delete(actions::table.find(id)).execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn set_config(pool: &SqlitePool, id: i32, config: &ActionConfig) -> Result<()> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        diesel::update(actions::table.find(id))
+            .set(actions::config.eq(serde_json::to_vec(config)?))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn record_failure(pool: &SqlitePool, id: i32) -> Result<i32> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        let failure_count = diesel::update(actions::table.find(id))
+            .set(actions::failure_count.eq(actions::failure_count + 1))
+            .returning(actions::failure_count)
+            .get_result::<i32>(&mut db)?;
+
+        return Ok(failure_count);
+    }
+
+    pub fn reset_failures(pool: &SqlitePool, id: i32) -> Result<()> {
+        use crate::schema::actions;
+
+        let mut db = pool.get()?;
+
+        diesel::update(actions::table.find(id))
+            .set(actions::failure_count.eq(0))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn script_source(&self) -> Result<String> {
+        return Ok(String::from_utf8(self.script.clone())?);
+    }
+
+    pub fn parsed_config(&self) -> Result<ActionConfig> {
+        if self.config.is_empty() {
+            return Ok(ActionConfig::default());
+        }
+
+        return Ok(serde_json::from_slice(&self.config)?);
+    }
+}
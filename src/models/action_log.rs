@@ -0,0 +1,162 @@
+use anyhow::Result;
+use chrono::{Duration, Local};
+use diesel::prelude::*;
+use strum::{Display, EnumIter, EnumString, IntoStaticStr};
+
+use crate::models::SqlitePool;
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::action_log)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ActionLogEntry {
+    pub id: i32,
+    pub action_id: i32,
+    pub action_name: String,
+    pub event_summary: String,
+    /// Unix timestamp in milliseconds.
+    pub started_at: i64,
+    pub duration_us: i64,
+    pub result: String,
+    pub error: Option<String>,
+    pub error_line: Option<i32>,
+    pub error_column: Option<i32>,
+    /// Json array of the commands the run sent.
+    pub messages: String,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::action_log)]
+pub struct NewActionLogEntry {
+    pub action_id: i32,
+    pub action_name: String,
+    pub event_summary: String,
+    pub started_at: i64,
+    pub duration_us: i64,
+    pub result: String,
+    pub error: Option<String>,
+    pub error_line: Option<i32>,
+    pub error_column: Option<i32>,
+    pub messages: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ActionRunResult {
+    Success,
+    Error,
+    LimitExceeded,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ActionLogFilter {
+    pub action_id: Option<i32>,
+    pub result: Option<ActionRunResult>,
+    /// Matched against the event summary and the error.
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionLogRetention {
+    pub max_entries: i32,
+    pub max_days: i32,
+}
+
+impl Default for ActionLogRetention {
+    fn default() -> Self {
+        return Self {
+            max_entries: 10_000,
+            max_days: 30,
+        };
+    }
+}
+
+impl ActionLogEntry {
+    pub fn insert(pool: &SqlitePool, entry: &NewActionLogEntry) -> Result<()> {
+        use crate::schema::action_log;
+
+        let mut db = pool.get()?;
+
+        diesel::insert_into(action_log::table).values(entry).execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    /// Newest entries first.
+    pub fn load_page(pool: &SqlitePool, filter: &ActionLogFilter, offset: i64, limit: i64) -> Result<Vec<Self>> {
+        use crate::schema::action_log;
+
+        let mut db = pool.get()?;
+
+        let mut query = action_log::table.into_boxed();
+
+        if let Some(action_id) = filter.action_id {
+            query = query.filter(action_log::action_id.eq(action_id));
+        }
+
+        if let Some(result) = filter.result {
+            query = query.filter(action_log::result.eq(<&str>::from(result)));
+        }
+
+        let search = filter.query.trim();
+        if !search.is_empty() {
+            let pattern = format!("%{}%", search.replace('%', "\\%").replace('_', "\\_"));
+            query = query.filter(
+                action_log::event_summary
+                    .like(pattern.clone())
+                    .escape('\\')
+                    .or(action_log::error.like(pattern).escape('\\')),
+            );
+        }
+
+        let entries = query
+            .order(action_log::id.desc())
+            .offset(offset)
+            .limit(limit)
+            .select(Self::as_select())
+            .load(&mut db)?;
+
+        return Ok(entries);
+    }
+
+    pub fn clear(pool: &SqlitePool) -> Result<usize> {
+        use crate::schema::action_log;
+
+        let mut db = pool.get()?;
+
+        let deleted = diesel::delete(action_log::table).execute(&mut db)?;
+
+        return Ok(deleted);
+    }
+
+    /// Drops entries older than the retention period and everything past the newest `max_entries`.
+    pub fn prune(pool: &SqlitePool, retention: ActionLogRetention) -> Result<usize> {
+        use crate::schema::action_log;
+
+        let mut db = pool.get()?;
+
+        let cutoff = (Local::now() - Duration::days(i64::from(retention.max_days))).timestamp_millis();
+        let mut deleted =
+            diesel::delete(action_log::table.filter(action_log::started_at.lt(cutoff))).execute(&mut db)?;
+
+        let newest_dropped = action_log::table
+            .order(action_log::id.desc())
+            .offset(i64::from(retention.max_entries))
+            .select(action_log::id)
+            .first::<i32>(&mut db)
+            .optional()?;
+
+        if let Some(id) = newest_dropped {
+            deleted += diesel::delete(action_log::table.filter(action_log::id.le(id))).execute(&mut db)?;
+        }
+
+        return Ok(deleted);
+    }
+
+    pub fn run_result(&self) -> Option<ActionRunResult> {
+        return self.result.parse().ok();
+    }
+
+    pub fn parsed_messages(&self) -> Vec<String> {
+        return serde_json::from_str(&self.messages).unwrap_or_default();
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use diesel::prelude::*;
+
+use crate::models::SqlitePool;
+
+/// Which file of the asset cache holds an asset, the files are named by the hash of their content.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::asset_cache)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CachedAsset {
+    pub key: String,
+    pub hash: String,
+    /// Unix timestamp in milliseconds.
+    pub fetched_at: i64,
+}
+
+impl CachedAsset {
+    pub fn load(pool: &SqlitePool, key: &str) -> Result<Option<Self>> {
+        use crate::schema::asset_cache;
+
+        let mut db = pool.get()?;
+
+        let asset = asset_cache::table
+            .find(key)
+            .select(Self::as_select())
+            .first(&mut db)
+            .optional()?;
+
+        return Ok(asset);
+    }
+
+    pub fn save(pool: &SqlitePool, asset: &CachedAsset) -> Result<()> {
+        use crate::schema::asset_cache;
+
+        let mut db = pool.get()?;
+
+        diesel::replace_into(asset_cache::table)
+            .values(asset)
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    /// Forgets assets fetched before `before` and returns the hashes still in use.
+    pub fn expire(pool: &SqlitePool, before: i64) -> Result<Vec<String>> {
+        use crate::schema::asset_cache;
+
+        let mut db = pool.get()?;
+
+        let hashes = db.transaction(|db| {
+            diesel::delete(asset_cache::table.filter(asset_cache::fetched_at.lt(before))).execute(db)?;
+
+            asset_cache::table
+                .select(asset_cache::hash)
+                .distinct()
+                .load::<String>(db)
+        })?;
+
+        return Ok(hashes);
+    }
+}
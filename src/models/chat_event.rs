@@ -0,0 +1,432 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl,
+    prelude::*,
+    sql_types::{Bool, Nullable, Text},
+    sqlite::Sqlite,
+};
+use strum::{Display, EnumIter, EnumString, IntoStaticStr};
+use twitch_irc::message::{AsRawIRC, Badge, IRCMessage, ServerMessage};
+
+use crate::{
+    models::SqlitePool,
+    schema::chat_events,
+    twitch::types::{PrivmsgMessageExt, TwitchEvent},
+};
+
+type ChatEventCondition = Box<dyn BoxableExpression<chat_events::table, Sqlite, SqlType = Nullable<Bool>>>;
+
+/// A chat event of a joined channel, kept so the chat still shows what happened before a restart or reconnect.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::chat_events)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ChatEvent {
+    pub id: i32,
+    pub channel: String,
+    pub kind: String,
+    pub message_id: Option<String>,
+    pub user_id: Option<String>,
+    pub user_login: Option<String>,
+    pub user_name: Option<String>,
+    pub text: Option<String>,
+    /// Set once a moderator removed the message.
+    pub status: Option<String>,
+    /// The raw irc line, or the json payload for EventSub events.
+    pub source: String,
+    /// Unix timestamp in milliseconds.
+    pub created_at: i64,
+    /// The irc badges tag of chat messages, e.g. `moderator/1,subscriber/12`.
+    pub badges: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::chat_events)]
+pub struct NewChatEvent {
+    pub channel: String,
+    pub kind: String,
+    pub message_id: Option<String>,
+    pub user_id: Option<String>,
+    pub user_login: Option<String>,
+    pub user_name: Option<String>,
+    pub text: Option<String>,
+    pub source: String,
+    pub created_at: i64,
+    pub badges: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChatEventKind {
+    Privmsg,
+    UserNotice,
+    Notice,
+    Follow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChatEventStatus {
+    Deleted,
+    TimedOut,
+    Banned,
+}
+
+/// Who sent a message, going by the badges they had at the time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum ChatRole {
+    Broadcaster,
+    Moderator,
+    #[strum(to_string = "VIP")]
+    Vip,
+    Subscriber,
+    Viewer,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum ChatSearchStatus {
+    #[default]
+    #[strum(to_string = "Any status")]
+    Any,
+    /// Deleted, timed out or banned.
+    #[strum(to_string = "Removed")]
+    Removed,
+    #[strum(to_string = "Deleted")]
+    Deleted,
+    #[strum(to_string = "Timed out")]
+    TimedOut,
+    #[strum(to_string = "Banned")]
+    Banned,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ChatSearch {
+    /// Words the text has to contain, each matches as a prefix.
+    pub text: String,
+    /// Part of the login or display name.
+    pub user: String,
+    pub channel: Option<String>,
+    pub role: Option<ChatRole>,
+    pub status: ChatSearchStatus,
+    /// Unix timestamp in milliseconds.
+    pub since: Option<i64>,
+    /// Unix timestamp in milliseconds, exclusive.
+    pub until: Option<i64>,
+}
+
+impl NewChatEvent {
+    /// The channel `from_event` keeps the event for, without building it.
+    pub fn channel_of(event: &TwitchEvent) -> Option<&str> {
+        return match event {
+            TwitchEvent::Privmsg(msg) => Some(&msg.channel_login),
+            TwitchEvent::UserNotice(notice) => Some(&notice.channel_login),
+            TwitchEvent::Notice(notice) => notice.channel_login.as_deref(),
+            TwitchEvent::Follow(follow) => Some(follow.broadcaster_user_login.as_str()),
+            _ => None,
+        };
+    }
+
+    /// Only events that are shown in the chat history are kept.
+    pub fn from_event(event: &TwitchEvent) -> Option<Self> {
+        return match event {
+            TwitchEvent::Privmsg(msg) => Some(Self {
+                channel: msg.channel_login.clone(),
+                kind: <&str>::from(ChatEventKind::Privmsg).to_owned(),
+                message_id: Some(msg.message_id.clone()),
+                user_id: Some(msg.sender.id.clone()),
+                user_login: Some(msg.sender.login.clone()),
+                user_name: Some(msg.sender.name.clone()),
+                text: Some(msg.parsed.text()),
+                source: msg.source.as_raw_irc(),
+                created_at: msg.server_timestamp.timestamp_millis(),
+                badges: Some(badges_tag(&msg.badges)),
+            }),
+            TwitchEvent::UserNotice(notice) => Some(Self {
+                channel: notice.channel_login.clone(),
+                kind: <&str>::from(ChatEventKind::UserNotice).to_owned(),
+                message_id: Some(notice.message_id.clone()),
+                user_id: Some(notice.sender.id.clone()),
+                user_login: Some(notice.sender.login.clone()),
+                user_name: Some(notice.sender.name.clone()),
+                text: notice.message_text.clone(),
+                source: notice.source.as_raw_irc(),
+                created_at: notice.server_timestamp.timestamp_millis(),
+                badges: Some(badges_tag(&notice.badges)),
+            }),
+            TwitchEvent::Notice(notice) => Some(Self {
+                // notices without a channel are about the connection, not the chat
+                channel: notice.channel_login.clone()?,
+                kind: <&str>::from(ChatEventKind::Notice).to_owned(),
+                message_id: None,
+                user_id: None,
+                user_login: None,
+                user_name: None,
+                text: Some(notice.message_text.clone()),
+                source: notice.source.as_raw_irc(),
+                created_at: Utc::now().timestamp_millis(),
+                badges: None,
+            }),
+            TwitchEvent::Follow(follow) => Some(Self {
+                channel: follow.broadcaster_user_login.to_string(),
+                kind: <&str>::from(ChatEventKind::Follow).to_owned(),
+                message_id: None,
+                user_id: Some(follow.user_id.to_string()),
+                user_login: Some(follow.user_login.to_string()),
+                user_name: Some(follow.user_name.to_string()),
+                text: None,
+                source: serde_json::to_string(follow).ok()?,
+                created_at: DateTime::parse_from_rfc3339(follow.followed_at.as_str())
+                    .map(|time| time.timestamp_millis())
+                    .unwrap_or_else(|_| Utc::now().timestamp_millis()),
+                badges: None,
+            }),
+            _ => None,
+        };
+    }
+}
+
+impl ChatEvent {
+    /// The ids the events were saved with, in order.
+    pub fn insert_all(pool: &SqlitePool, events: &[NewChatEvent]) -> Result<Vec<i32>> {
+        let mut db = pool.get()?;
+
+        let ids = db.transaction(|db| {
+            let mut ids = Vec::with_capacity(events.len());
+            for event in events {
+                ids.push(
+                    diesel::insert_into(chat_events::table)
+                        .values(event)
+                        .returning(chat_events::id)
+                        .get_result::<i32>(db)?,
+                );
+            }
+
+            return diesel::QueryResult::Ok(ids);
+        })?;
+
+        return Ok(ids);
+    }
+
+    pub fn newest_id(pool: &SqlitePool, channel: &str) -> Result<Option<i32>> {
+        let mut db = pool.get()?;
+
+        let id = chat_events::table
+            .filter(chat_events::channel.eq(channel))
+            .select(dsl::max(chat_events::id))
+            .first::<Option<i32>>(&mut db)?;
+
+        return Ok(id);
+    }
+
+    /// Up to `limit` events of `channel` older than `before_id`, oldest first.
+    pub fn load_page(pool: &SqlitePool, channel: &str, before_id: i32, limit: i64) -> Result<Vec<Self>> {
+        let mut db = pool.get()?;
+
+        let mut events = chat_events::table
+            .filter(chat_events::channel.eq(channel))
+            .filter(chat_events::id.lt(before_id))
+            .order(chat_events::id.desc())
+            .limit(limit)
+            .select(Self::as_select())
+            .load(&mut db)?;
+        events.reverse();
+
+        return Ok(events);
+    }
+
+    pub fn set_status_by_message_id(pool: &SqlitePool, message_id: &str, status: ChatEventStatus) -> Result<()> {
+        let mut db = pool.get()?;
+
+        diesel::update(chat_events::table.filter(chat_events::message_id.eq(message_id)))
+            .set(chat_events::status.eq(<&str>::from(status)))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    /// Marks the messages the user sent in `channel` since `since` (unix timestamp in milliseconds).
+    pub fn set_status_for_user(
+        pool: &SqlitePool,
+        channel: &str,
+        user_id: &str,
+        since: i64,
+        status: ChatEventStatus,
+    ) -> Result<()> {
+        let mut db = pool.get()?;
+
+        diesel::update(
+            chat_events::table
+                .filter(chat_events::channel.eq(channel))
+                .filter(chat_events::user_id.eq(user_id))
+                .filter(chat_events::kind.eq(<&str>::from(ChatEventKind::Privmsg)))
+                .filter(chat_events::created_at.ge(since)),
+        )
+        .set(chat_events::status.eq(<&str>::from(status)))
+        .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    /// Up to `limit` events matching `search` older than `before_id`, newest first.
+    pub fn search(pool: &SqlitePool, search: &ChatSearch, before_id: Option<i32>, limit: i64) -> Result<Vec<Self>> {
+        use crate::schema::chat_events_fts;
+
+        let mut db = pool.get()?;
+
+        let mut query = chat_events::table.select(Self::as_select()).into_boxed();
+
+        if let Some(terms) = fts_query(&search.text) {
+            query = query.filter(
+                chat_events::id.eq_any(
+                    chat_events_fts::table
+                        .select(chat_events_fts::rowid)
+                        .filter(dsl::sql::<Bool>("chat_events_fts MATCH ").bind::<Text, _>(terms)),
+                ),
+            );
+        }
+
+        let user = search.user.trim().trim_start_matches('@');
+        if !user.is_empty() {
+            let pattern = format!(
+                "%{}%",
+                user.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+            );
+            query = query.filter(
+                chat_events::user_login
+                    .like(pattern.clone())
+                    .escape('\\')
+                    .or(chat_events::user_name.like(pattern).escape('\\')),
+            );
+        }
+
+        if let Some(channel) = &search.channel {
+            query = query.filter(chat_events::channel.eq(channel.clone()));
+        }
+
+        if let Some(role) = search.role {
+            let condition: ChatEventCondition = match role {
+                ChatRole::Broadcaster => has_badge("broadcaster"),
+                ChatRole::Moderator => Box::new(has_badge("moderator").or(has_badge("lead_moderator"))),
+                ChatRole::Vip => has_badge("vip"),
+                ChatRole::Subscriber => has_badge("subscriber"),
+                ChatRole::Viewer => Box::new(
+                    chat_events::badges.is_not_null().and(dsl::not(
+                        has_badge("broadcaster")
+                            .or(has_badge("moderator"))
+                            .or(has_badge("lead_moderator"))
+                            .or(has_badge("vip"))
+                            .or(has_badge("subscriber")),
+                    )),
+                ),
+            };
+            query = query.filter(condition);
+        }
+
+        query = match search.status {
+            ChatSearchStatus::Any => query,
+            ChatSearchStatus::Removed => query.filter(chat_events::status.is_not_null()),
+            ChatSearchStatus::Deleted => query.filter(chat_events::status.eq(<&str>::from(ChatEventStatus::Deleted))),
+            ChatSearchStatus::TimedOut => query.filter(chat_events::status.eq(<&str>::from(ChatEventStatus::TimedOut))),
+            ChatSearchStatus::Banned => query.filter(chat_events::status.eq(<&str>::from(ChatEventStatus::Banned))),
+        };
+
+        if let Some(since) = search.since {
+            query = query.filter(chat_events::created_at.ge(since));
+        }
+        if let Some(until) = search.until {
+            query = query.filter(chat_events::created_at.lt(until));
+        }
+        if let Some(before_id) = before_id {
+            query = query.filter(chat_events::id.lt(before_id));
+        }
+
+        let events = query.order(chat_events::id.desc()).limit(limit).load(&mut db)?;
+
+        return Ok(events);
+    }
+
+    /// The events of the channel around `event`, `event` included, oldest first.
+    pub fn load_context(pool: &SqlitePool, event: &ChatEvent, radius: i64) -> Result<Vec<Self>> {
+        let mut events = Self::load_page(pool, &event.channel, event.id, radius)?;
+
+        let mut db = pool.get()?;
+
+        let newer = chat_events::table
+            .filter(chat_events::channel.eq(&event.channel))
+            .filter(chat_events::id.ge(event.id))
+            .order(chat_events::id.asc())
+            .limit(radius + 1)
+            .select(Self::as_select())
+            .load(&mut db)?;
+        events.extend(newer);
+
+        return Ok(events);
+    }
+
+    pub fn channels(pool: &SqlitePool) -> Result<Vec<String>> {
+        let mut db = pool.get()?;
+
+        let channels = chat_events::table
+            .select(chat_events::channel)
+            .distinct()
+            .order(chat_events::channel.asc())
+            .load(&mut db)?;
+
+        return Ok(channels);
+    }
+
+    pub fn status(&self) -> Option<ChatEventStatus> {
+        return self.status.as_deref()?.parse().ok();
+    }
+
+    /// Rebuilds the event as it was received, with moderation applied to chat messages.
+    pub fn to_event(&self) -> Option<TwitchEvent> {
+        if self.kind.parse() == Ok(ChatEventKind::Follow) {
+            return serde_json::from_str(&self.source).ok().map(TwitchEvent::Follow);
+        }
+
+        let source = IRCMessage::parse(&self.source).ok()?;
+        let mut event = TwitchEvent::try_from(ServerMessage::try_from(source).ok()?).ok()?;
+
+        if let TwitchEvent::Privmsg(msg) = &mut event {
+            match self.status() {
+                Some(ChatEventStatus::Deleted) => msg.mark_deleted(),
+                Some(ChatEventStatus::TimedOut) => msg.mark_timeouted(),
+                Some(ChatEventStatus::Banned) => msg.mark_banned(),
+                None => {}
+            }
+        }
+
+        return Some(event);
+    }
+}
+
+fn has_badge(name: &str) -> ChatEventCondition {
+    return Box::new(
+        chat_events::badges
+            .like(format!("{name}/%"))
+            .or(chat_events::badges.like(format!("%,{name}/%"))),
+    );
+}
+
+/// Quotes every word so the input is never taken for FTS5 syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let terms = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+
+    if terms.is_empty() {
+        return None;
+    }
+
+    return Some(terms.join(" "));
+}
+
+fn badges_tag(badges: &[Badge]) -> String {
+    return badges
+        .iter()
+        .map(|badge| format!("{}/{}", badge.name, badge.version))
+        .collect::<Vec<_>>()
+        .join(",");
+}
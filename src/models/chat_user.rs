@@ -0,0 +1,151 @@
+use anyhow::Result;
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::models::SqlitePool;
+
+/// Everyone we have seen chatting, across all channels and sessions.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::chat_users)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ChatUser {
+    pub user_id: String,
+    pub login: String,
+    pub name: String,
+    /// Unix timestamp in milliseconds.
+    pub first_seen: i64,
+    /// Unix timestamp in milliseconds.
+    pub last_seen: i64,
+    pub messages: i32,
+}
+
+/// A name a user went by before renaming.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::chat_user_names)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ChatUserName {
+    pub user_id: String,
+    pub login: String,
+    pub name: String,
+    /// Unix timestamp in milliseconds of the last message under this name.
+    pub last_seen: i64,
+}
+
+impl ChatUser {
+    pub fn load(pool: &SqlitePool, user_id: &str) -> Result<Option<Self>> {
+        use crate::schema::chat_users;
+
+        let mut db = pool.get()?;
+
+        let user = chat_users::table
+            .find(user_id)
+            .select(Self::as_select())
+            .first(&mut db)
+            .optional()?;
+
+        return Ok(user);
+    }
+
+    /// Adds the activity collected since the last call, `messages` and `last_seen` of `activity` only cover that
+    /// stretch. Renames are moved to the name history.
+    pub fn record_activity(pool: &SqlitePool, activity: &[ChatUser]) -> Result<()> {
+        use crate::schema::{chat_user_names, chat_users};
+
+        let mut db = pool.get()?;
+
+        db.transaction(|db| {
+            for user in activity {
+                let previous = chat_users::table
+                    .find(&user.user_id)
+                    .select(Self::as_select())
+                    .first(db)
+                    .optional()?;
+
+                let Some(previous) = previous else {
+                    diesel::insert_into(chat_users::table).values(user).execute(db)?;
+                    continue;
+                };
+
+                if previous.login != user.login {
+                    diesel::replace_into(chat_user_names::table)
+                        .values(&ChatUserName {
+                            user_id: previous.user_id.clone(),
+                            login: previous.login.clone(),
+                            name: previous.name.clone(),
+                            last_seen: previous.last_seen,
+                        })
+                        .execute(db)?;
+                }
+
+                diesel::update(chat_users::table.find(&user.user_id))
+                    .set((
+                        chat_users::login.eq(&user.login),
+                        chat_users::name.eq(&user.name),
+                        chat_users::last_seen.eq(user.last_seen),
+                        chat_users::messages.eq(previous.messages + user.messages),
+                    ))
+                    .execute(db)?;
+            }
+
+            return diesel::QueryResult::Ok(());
+        })?;
+
+        return Ok(());
+    }
+}
+
+impl ChatUserName {
+    pub fn load_for_user(pool: &SqlitePool, user_id: &str) -> Result<Vec<Self>> {
+        use crate::schema::chat_user_names;
+
+        let mut db = pool.get()?;
+
+        let names = chat_user_names::table
+            .filter(chat_user_names::user_id.eq(user_id))
+            .order(chat_user_names::last_seen.desc())
+            .select(Self::as_select())
+            .load(&mut db)?;
+
+        return Ok(names);
+    }
+}
+
+/// Free-form moderator notes about a user.
+pub struct UserNote;
+
+impl UserNote {
+    pub fn load(pool: &SqlitePool, user_id: &str) -> Result<String> {
+        use crate::schema::user_notes;
+
+        let mut db = pool.get()?;
+
+        let notes = user_notes::table
+            .find(user_id)
+            .select(user_notes::notes)
+            .first::<String>(&mut db)
+            .optional()?;
+
+        return Ok(notes.unwrap_or_default());
+    }
+
+    pub fn save(pool: &SqlitePool, user_id: &str, notes: &str) -> Result<()> {
+        use crate::schema::user_notes;
+
+        let mut db = pool.get()?;
+
+        if notes.trim().is_empty() {
+            diesel::delete(user_notes::table.find(user_id)).execute(&mut db)?;
+            return Ok(());
+        }
+
+        diesel::replace_into(user_notes::table)
+            .values((
+                user_notes::user_id.eq(user_id),
+                user_notes::notes.eq(notes),
+                user_notes::updated_at.eq(Utc::now().timestamp_millis()),
+            ))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+}
@@ -58,4 +58,58 @@ impl KvStore {
 
         return Ok(());
     }
+
+    pub fn get_keys(pool: &SqlitePool, bucket: &str) -> Result<Vec<String>> {
+        use crate::schema::kv_store;
+
+        let mut db = pool.get()?;
+
+        let keys = kv_store::table
+            .filter(kv_store::bucket.eq(bucket))
+            .order(kv_store::key.asc())
+            .select(kv_store::key)
+            .load::<String>(&mut db)?;
+
+        return Ok(keys);
+    }
+
+    pub fn delete_value(pool: &SqlitePool, bucket: &str, key: &str) -> Result<bool> {
+        use crate::schema::kv_store;
+
+        let mut db = pool.get()?;
+
+        let deleted = diesel::delete(
+            kv_store::table
+                .filter(kv_store::bucket.eq(bucket))
+                .filter(kv_store::key.eq(key)),
+        )
+        .execute(&mut db)?;
+
+        return Ok(deleted > 0);
+    }
+
+    /// All key/value pairs of a bucket, values are still serialized.
+    pub fn get_bucket(pool: &SqlitePool, bucket: &str) -> Result<Vec<(String, String)>> {
+        use crate::schema::kv_store;
+
+        let mut db = pool.get()?;
+
+        let entries = kv_store::table
+            .filter(kv_store::bucket.eq(bucket))
+            .order(kv_store::key.asc())
+            .select((kv_store::key, kv_store::value))
+            .load::<(String, String)>(&mut db)?;
+
+        return Ok(entries);
+    }
+
+    pub fn delete_bucket(pool: &SqlitePool, bucket: &str) -> Result<usize> {
+        use crate::schema::kv_store;
+
+        let mut db = pool.get()?;
+
+        let deleted = diesel::delete(kv_store::table.filter(kv_store::bucket.eq(bucket))).execute(&mut db)?;
+
+        return Ok(deleted);
+    }
 }
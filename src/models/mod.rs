@@ -1,5 +1,15 @@
+pub mod action;
+pub mod action_log;
+pub mod asset_cache;
+pub mod chat_event;
+pub mod chat_user;
 pub mod kv_store;
+pub mod mod_log;
+pub mod reward_rule;
+pub mod script_module;
 pub mod settings;
+pub mod stats;
+pub mod timed_message;
 
 use anyhow::Result;
 use diesel::{
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use diesel::prelude::*;
+use strum::{Display, EnumString, IntoStaticStr};
+
+use crate::models::SqlitePool;
+
+/// A moderation action seen in chat, no matter who took it.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::mod_log)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ModLogEntry {
+    pub id: i32,
+    pub channel: String,
+    /// Unknown for deleted messages, twitch only sends the login there.
+    pub user_id: Option<String>,
+    pub user_login: String,
+    pub action: String,
+    pub duration_secs: Option<i64>,
+    /// The deleted message.
+    pub message: Option<String>,
+    /// Unix timestamp in milliseconds.
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::mod_log)]
+pub struct NewModLogEntry {
+    pub channel: String,
+    pub user_id: Option<String>,
+    pub user_login: String,
+    pub action: String,
+    pub duration_secs: Option<i64>,
+    pub message: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ModAction {
+    Timeout,
+    Ban,
+    DeleteMessage,
+}
+
+impl ModLogEntry {
+    pub fn insert(pool: &SqlitePool, entry: &NewModLogEntry) -> Result<()> {
+        use crate::schema::mod_log;
+
+        let mut db = pool.get()?;
+
+        diesel::insert_into(mod_log::table).values(entry).execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    /// Entries of the user under their current login or any of the given previous ones, newest first.
+    pub fn load_for_user(pool: &SqlitePool, user_id: &str, logins: &[String]) -> Result<Vec<Self>> {
+        use crate::schema::mod_log;
+
+        let mut db = pool.get()?;
+
+        let entries = mod_log::table
+            .filter(
+                mod_log::user_id
+                    .eq(user_id)
+                    .or(mod_log::user_id.is_null().and(mod_log::user_login.eq_any(logins))),
+            )
+            .order(mod_log::created_at.desc())
+            .limit(100)
+            .select(Self::as_select())
+            .load(&mut db)?;
+
+        return Ok(entries);
+    }
+
+    pub fn action(&self) -> Option<ModAction> {
+        return self.action.parse().ok();
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use diesel::prelude::*;
+use strum::{Display, EnumIter, EnumString, IntoStaticStr};
+
+use crate::models::SqlitePool;
+
+/// How a channel point reward is handled when it gets redeemed.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::reward_rules)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RewardRule {
+    pub reward_id: String,
+    pub auto_fulfill: String,
+    /// Action run for every redemption of the reward.
+    pub action_id: Option<i32>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum AutoFulfill {
+    #[default]
+    Never,
+    Always,
+    /// Once the bound action ran without an error.
+    OnActionSuccess,
+}
+
+impl RewardRule {
+    pub fn load_all(pool: &SqlitePool) -> Result<Vec<RewardRule>> {
+        use crate::schema::reward_rules;
+
+        let mut db = pool.get()?;
+
+        let rules = reward_rules::table.select(RewardRule::as_select()).load(&mut db)?;
+
+        return Ok(rules);
+    }
+
+    pub fn upsert(pool: &SqlitePool, rule: &RewardRule) -> Result<()> {
+        use crate::schema::reward_rules;
+
+        let mut db = pool.get()?;
+
+        diesel::insert_into(reward_rules::table)
+            .values(rule)
+            .on_conflict(reward_rules::reward_id)
+            .do_update()
+            .set((
+                reward_rules::auto_fulfill.eq(&rule.auto_fulfill),
+                reward_rules::action_id.eq(rule.action_id),
+            ))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn auto_fulfill(&self) -> AutoFulfill {
+        return self.auto_fulfill.parse().unwrap_or_default();
+    }
+}
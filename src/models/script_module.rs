@@ -0,0 +1,88 @@
+use anyhow::Result;
+use diesel::prelude::*;
+
+use crate::models::SqlitePool;
+
+/// A Rhai library that actions can `import` by name.
+#[derive(Debug, Default, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::script_modules)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ScriptModule {
+    pub id: i32,
+    pub name: String,
+    pub source: Vec<u8>,
+}
+
+impl ScriptModule {
+    pub fn load_all(pool: &SqlitePool) -> Result<Vec<ScriptModule>> {
+        use crate::schema::script_modules;
+
+        let mut db = pool.get()?;
+
+        let modules = script_modules::table
+            .order(script_modules::name.asc())
+            .select(ScriptModule::as_select())
+            .load(&mut db)?;
+
+        return Ok(modules);
+    }
+
+    pub fn load_source(pool: &SqlitePool, name: &str) -> Result<Option<String>> {
+        use crate::schema::script_modules;
+
+        let mut db = pool.get()?;
+
+        let source = script_modules::table
+            .filter(script_modules::name.eq(name))
+            .select(script_modules::source)
+            .first::<Vec<u8>>(&mut db)
+            .optional()?;
+
+        return Ok(source.map(String::from_utf8).transpose()?);
+    }
+
+    pub fn insert(pool: &SqlitePool, name: &str, source: &str) -> Result<i32> {
+        use crate::schema::script_modules;
+
+        let mut db = pool.get()?;
+
+        let id = diesel::insert_into(script_modules::table)
+            .values((
+                script_modules::name.eq(name),
+                script_modules::source.eq(source.as_bytes()),
+            ))
+            .returning(script_modules::id)
+            .get_result::<i32>(&mut db)?;
+
+        return Ok(id);
+    }
+
+    pub fn update(pool: &SqlitePool, id: i32, name: &str, source: &str) -> Result<()> {
+        use crate::schema::script_modules;
+
+        let mut db = pool.get()?;
+
+        diesel::update(script_modules::table.find(id))
+            .set((
+                script_modules::name.eq(name),
+                script_modules::source.eq(source.as_bytes()),
+            ))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn delete(pool: &SqlitePool, id: i32) -> Result<()> {
+        use crate::schema::script_modules;
+
+        let mut db = pool.get()?;
+
+        diesel::delete(script_modules::table.find(id)).execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn source_text(&self) -> Result<String> {
+        return Ok(String::from_utf8(self.source.clone())?);
+    }
+}
@@ -1,8 +1,15 @@
 use anyhow::Result;
 use diesel::prelude::*;
 use egui_dock::DockState;
+use strum::IntoEnumIterator;
 
-use crate::{app::App, models::SqlitePool, twitch::api::twitch_relink_account, ui::tabs::Tabs};
+use crate::{
+    app::App,
+    models::SqlitePool,
+    twitch::{api::twitch_relink_account, third_party::EmoteProvider},
+    ui::tabs::Tabs,
+    workers::action::ActionWorkerMessage,
+};
 
 #[derive(Debug, Default, Clone, Insertable, Queryable, Selectable)]
 #[diesel(table_name = crate::schema::settings)]
@@ -14,6 +21,12 @@ pub struct Settings {
     pub channel: Option<String>,
     pub user_access_token: Option<String>,
     pub user_refresh_token: Option<String>,
+    pub action_log_max_entries: Option<i32>,
+    pub action_log_max_days: Option<i32>,
+    pub chat_max_events: Option<i32>,
+    pub chat_emote_size: Option<f32>,
+    /// Comma separated names of the enabled third party emote providers.
+    pub chat_emote_providers: Option<String>,
 }
 
 impl Settings {
@@ -21,7 +34,15 @@ impl Settings {
         let stored_settings = Settings::load(&app.state.db_pool)?;
 
         if let Some(tree_str) = stored_settings.tree {
-            let saved_tree = serde_json::from_str::<DockState<Tabs>>(&tree_str)?;
+            let mut saved_tree = serde_json::from_str::<DockState<Tabs>>(&tree_str)?;
+
+            // tabs added since the layout was saved
+            for tab in Tabs::iter() {
+                if saved_tree.find_tab(&tab).is_none() {
+                    saved_tree.push_to_first_leaf(tab);
+                }
+            }
+
             app.tree = saved_tree;
         }
 
@@ -33,6 +54,35 @@ impl Settings {
             app.state.settings.channel_name = channel_name;
         }
 
+        let retention = &mut app.state.settings.action_log_retention;
+        if let Some(max_entries) = stored_settings.action_log_max_entries {
+            retention.max_entries = max_entries;
+        }
+        if let Some(max_days) = stored_settings.action_log_max_days {
+            retention.max_days = max_days;
+        }
+        app.state
+            .channels
+            .action_worker_message_tx
+            .send(ActionWorkerMessage::SetLogRetention(*retention))
+            .unwrap();
+
+        if let Some(max_events) = stored_settings.chat_max_events {
+            app.state.chat.max_events = max_events as usize;
+        }
+        if let Some(emote_size) = stored_settings.chat_emote_size {
+            app.state.chat.emote_size = emote_size;
+        }
+        if let Some(providers) = stored_settings.chat_emote_providers {
+            let emotes = &mut app.state.chat.third_party_emotes;
+            for provider in EmoteProvider::iter() {
+                emotes.set_enabled(
+                    provider,
+                    providers.split(',').any(|name| name == <&str>::from(provider)),
+                );
+            }
+        }
+
         if let Some(access_token) = stored_settings.user_access_token
             && let Some(refresh_token) = stored_settings.user_refresh_token
         {
@@ -60,6 +110,17 @@ impl Settings {
                 .clone()
                 .and_then(|account| account.token.refresh_token)
                 .map(|token| token.take()),
+            action_log_max_entries: Some(app.state.settings.action_log_retention.max_entries),
+            action_log_max_days: Some(app.state.settings.action_log_retention.max_days),
+            chat_max_events: Some(app.state.chat.max_events as i32),
+            chat_emote_size: Some(app.state.chat.emote_size),
+            chat_emote_providers: Some(
+                EmoteProvider::iter()
+                    .filter(|provider| app.state.chat.third_party_emotes.enabled.contains(provider))
+                    .map(<&str>::from)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
         };
         settings.store(&app.state.db_pool)?;
 
@@ -0,0 +1,298 @@
+use anyhow::Result;
+use diesel::{dsl, prelude::*};
+
+use crate::models::SqlitePool;
+
+/// A stretch of chat activity in one channel, either a live stream or offline chat in between.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::stream_sessions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct StreamSession {
+    pub id: i32,
+    pub channel: String,
+    /// Unix timestamp in milliseconds.
+    pub started_at: i64,
+    /// Unix timestamp in milliseconds, unset while the session is still running.
+    pub ended_at: Option<i64>,
+    pub live: bool,
+}
+
+/// Activity of one minute of a session.
+#[derive(Debug, Default, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::stats_minutes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct StatsMinute {
+    pub session_id: i32,
+    /// Unix timestamp in milliseconds of the start of the minute.
+    pub minute: i64,
+    pub messages: i32,
+    /// Unique chatters within the minute.
+    pub chatters: i32,
+    pub first_time_chatters: i32,
+    pub emotes: i32,
+    pub mod_actions: i32,
+    pub subs: i32,
+    pub bits: i32,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::stats_chatters)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct StatsChatter {
+    pub session_id: i32,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub messages: i32,
+    pub bits: i32,
+    /// Wrote their first message in the channel ever during this session.
+    pub first_time: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::stats_emotes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct StatsEmote {
+    pub session_id: i32,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub uses: i32,
+}
+
+impl StreamSession {
+    pub fn start(pool: &SqlitePool, channel: &str, started_at: i64, live: bool) -> Result<i32> {
+        use crate::schema::stream_sessions;
+
+        let mut db = pool.get()?;
+
+        let id = diesel::insert_into(stream_sessions::table)
+            .values((
+                stream_sessions::channel.eq(channel),
+                stream_sessions::started_at.eq(started_at),
+                stream_sessions::live.eq(live),
+            ))
+            .returning(stream_sessions::id)
+            .get_result::<i32>(&mut db)?;
+
+        return Ok(id);
+    }
+
+    pub fn end(pool: &SqlitePool, id: i32, ended_at: i64) -> Result<()> {
+        use crate::schema::stream_sessions;
+
+        let mut db = pool.get()?;
+
+        diesel::update(stream_sessions::table.find(id))
+            .set(stream_sessions::ended_at.eq(ended_at))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn set_live(pool: &SqlitePool, id: i32) -> Result<()> {
+        use crate::schema::stream_sessions;
+
+        let mut db = pool.get()?;
+
+        diesel::update(stream_sessions::table.find(id))
+            .set(stream_sessions::live.eq(true))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    /// Ends sessions left running by a previous run at their last recorded minute.
+    pub fn end_dangling(pool: &SqlitePool) -> Result<usize> {
+        use crate::schema::{stats_minutes, stream_sessions};
+
+        let mut db = pool.get()?;
+
+        let dangling = stream_sessions::table
+            .filter(stream_sessions::ended_at.is_null())
+            .select((stream_sessions::id, stream_sessions::started_at))
+            .load::<(i32, i64)>(&mut db)?;
+
+        for (id, started_at) in &dangling {
+            let last_minute = stats_minutes::table
+                .filter(stats_minutes::session_id.eq(id))
+                .select(dsl::max(stats_minutes::minute))
+                .first::<Option<i64>>(&mut db)?;
+
+            let ended_at = last_minute.map_or(*started_at, |minute| minute + 60_000);
+            diesel::update(stream_sessions::table.find(id))
+                .set(stream_sessions::ended_at.eq(ended_at))
+                .execute(&mut db)?;
+        }
+
+        return Ok(dangling.len());
+    }
+
+    pub fn channels(pool: &SqlitePool) -> Result<Vec<String>> {
+        use crate::schema::stream_sessions;
+
+        let mut db = pool.get()?;
+
+        let channels = stream_sessions::table
+            .select(stream_sessions::channel)
+            .distinct()
+            .order(stream_sessions::channel.asc())
+            .load(&mut db)?;
+
+        return Ok(channels);
+    }
+
+    /// Sessions that started after `since`, newest first.
+    pub fn load_since(pool: &SqlitePool, channel: Option<&str>, since: Option<i64>) -> Result<Vec<Self>> {
+        use crate::schema::stream_sessions;
+
+        let mut db = pool.get()?;
+
+        let mut query = stream_sessions::table.into_boxed();
+
+        if let Some(channel) = channel {
+            query = query.filter(stream_sessions::channel.eq(channel));
+        }
+
+        if let Some(since) = since {
+            query = query.filter(stream_sessions::started_at.ge(since));
+        }
+
+        let sessions = query
+            .order(stream_sessions::started_at.desc())
+            .select(Self::as_select())
+            .load(&mut db)?;
+
+        return Ok(sessions);
+    }
+}
+
+/// A name and a total, e.g. a chatter and their message count.
+#[derive(Debug, Clone)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub value: i64,
+}
+
+impl StatsMinute {
+    pub fn load_for_sessions(pool: &SqlitePool, session_ids: &[i32]) -> Result<Vec<Self>> {
+        use crate::schema::stats_minutes;
+
+        let mut db = pool.get()?;
+
+        let minutes = stats_minutes::table
+            .filter(stats_minutes::session_id.eq_any(session_ids))
+            .order(stats_minutes::minute.asc())
+            .select(Self::as_select())
+            .load(&mut db)?;
+
+        return Ok(minutes);
+    }
+}
+
+impl StatsChatter {
+    pub fn count_unique(pool: &SqlitePool, session_ids: &[i32]) -> Result<(i64, i64)> {
+        use crate::schema::stats_chatters;
+
+        let mut db = pool.get()?;
+
+        let chatters = stats_chatters::table
+            .filter(stats_chatters::session_id.eq_any(session_ids))
+            .select(dsl::count(stats_chatters::user_id).aggregate_distinct())
+            .first(&mut db)?;
+        let first_time_chatters = stats_chatters::table
+            .filter(stats_chatters::session_id.eq_any(session_ids))
+            .filter(stats_chatters::first_time.eq(true))
+            .select(dsl::count(stats_chatters::user_id).aggregate_distinct())
+            .first(&mut db)?;
+
+        return Ok((chatters, first_time_chatters));
+    }
+
+    pub fn top_by_messages(pool: &SqlitePool, session_ids: &[i32], limit: i64) -> Result<Vec<LeaderboardEntry>> {
+        use crate::schema::stats_chatters;
+
+        let mut db = pool.get()?;
+
+        let total = dsl::sum(stats_chatters::messages);
+        let rows = stats_chatters::table
+            .filter(stats_chatters::session_id.eq_any(session_ids))
+            .group_by(stats_chatters::user_id)
+            .select((dsl::max(stats_chatters::user_name), total))
+            .order(total.desc())
+            .limit(limit)
+            .load::<(Option<String>, Option<i64>)>(&mut db)?;
+
+        return Ok(to_leaderboard(rows));
+    }
+
+    pub fn top_by_bits(pool: &SqlitePool, session_ids: &[i32], limit: i64) -> Result<Vec<LeaderboardEntry>> {
+        use crate::schema::stats_chatters;
+
+        let mut db = pool.get()?;
+
+        let total = dsl::sum(stats_chatters::bits);
+        let rows = stats_chatters::table
+            .filter(stats_chatters::session_id.eq_any(session_ids))
+            .filter(stats_chatters::bits.gt(0))
+            .group_by(stats_chatters::user_id)
+            .select((dsl::max(stats_chatters::user_name), total))
+            .order(total.desc())
+            .limit(limit)
+            .load::<(Option<String>, Option<i64>)>(&mut db)?;
+
+        return Ok(to_leaderboard(rows));
+    }
+}
+
+impl StatsEmote {
+    pub fn top_by_uses(pool: &SqlitePool, session_ids: &[i32], limit: i64) -> Result<Vec<LeaderboardEntry>> {
+        use crate::schema::stats_emotes;
+
+        let mut db = pool.get()?;
+
+        let total = dsl::sum(stats_emotes::uses);
+        let rows = stats_emotes::table
+            .filter(stats_emotes::session_id.eq_any(session_ids))
+            .group_by(stats_emotes::emote_id)
+            .select((dsl::max(stats_emotes::emote_name), total))
+            .order(total.desc())
+            .limit(limit)
+            .load::<(Option<String>, Option<i64>)>(&mut db)?;
+
+        return Ok(to_leaderboard(rows));
+    }
+}
+
+fn to_leaderboard(rows: Vec<(Option<String>, Option<i64>)>) -> Vec<LeaderboardEntry> {
+    return rows
+        .into_iter()
+        .map(|(name, value)| LeaderboardEntry {
+            name: name.unwrap_or_default(),
+            value: value.unwrap_or(0),
+        })
+        .collect();
+}
+
+/// Writes the buckets of a session, rows already stored are replaced with the new totals.
+pub fn save_stats(
+    pool: &SqlitePool,
+    minutes: &[StatsMinute],
+    chatters: &[StatsChatter],
+    emotes: &[StatsEmote],
+) -> Result<()> {
+    use crate::schema::{stats_chatters, stats_emotes, stats_minutes};
+
+    let mut db = pool.get()?;
+
+    db.transaction(|db| {
+        diesel::replace_into(stats_minutes::table).values(minutes).execute(db)?;
+        diesel::replace_into(stats_chatters::table)
+            .values(chatters)
+            .execute(db)?;
+        diesel::replace_into(stats_emotes::table).values(emotes).execute(db)?;
+
+        return diesel::QueryResult::Ok(());
+    })?;
+
+    return Ok(());
+}
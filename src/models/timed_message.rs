@@ -0,0 +1,138 @@
+use anyhow::Result;
+use diesel::prelude::*;
+use twitch_api::extra::AnnouncementColor;
+
+use crate::models::SqlitePool;
+
+pub const ANNOUNCEMENT_COLORS: [&str; 5] = ["primary", "blue", "green", "orange", "purple"];
+
+/// A set of chat messages posted in rotation while the stream is live.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::timed_messages)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TimedMessage {
+    pub id: i32,
+    pub name: String,
+    /// One message per line.
+    pub messages: String,
+    /// Posted as an announcement in this color, or as a plain chat message if unset.
+    pub announcement_color: Option<String>,
+    pub interval_minutes: i32,
+    /// Chat messages that have to arrive since the last post before posting again.
+    pub min_chat_messages: i32,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::timed_messages)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewTimedMessage {
+    pub name: String,
+    pub messages: String,
+    pub announcement_color: Option<String>,
+    pub interval_minutes: i32,
+    pub min_chat_messages: i32,
+    pub enabled: bool,
+}
+
+impl Default for NewTimedMessage {
+    fn default() -> Self {
+        return Self {
+            name: String::from("New Timer"),
+            messages: String::new(),
+            announcement_color: None,
+            interval_minutes: 15,
+            min_chat_messages: 5,
+            enabled: true,
+        };
+    }
+}
+
+impl TimedMessage {
+    pub fn load_all(pool: &SqlitePool) -> Result<Vec<TimedMessage>> {
+        use crate::schema::timed_messages;
+
+        let mut db = pool.get()?;
+
+        let timers = timed_messages::table
+            .order(timed_messages::id.asc())
+            .select(TimedMessage::as_select())
+            .load(&mut db)?;
+
+        return Ok(timers);
+    }
+
+    pub fn insert(pool: &SqlitePool, timer: &NewTimedMessage) -> Result<i32> {
+        use crate::schema::timed_messages;
+
+        let mut db = pool.get()?;
+
+        let id = diesel::insert_into(timed_messages::table)
+            .values(timer)
+            .returning(timed_messages::id)
+            .get_result::<i32>(&mut db)?;
+
+        return Ok(id);
+    }
+
+    pub fn update(pool: &SqlitePool, id: i32, timer: &NewTimedMessage) -> Result<()> {
+        use crate::schema::timed_messages;
+
+        let mut db = pool.get()?;
+
+        diesel::update(timed_messages::table.find(id))
+            .set(timer)
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn set_enabled(pool: &SqlitePool, id: i32, enabled: bool) -> Result<()> {
+        use crate::schema::timed_messages;
+
+        let mut db = pool.get()?;
+
+        diesel::update(timed_messages::table.find(id))
+            .set(timed_messages::enabled.eq(enabled))
+            .execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn delete(pool: &SqlitePool, id: i32) -> Result<()> {
+        use crate::schema::timed_messages;
+
+        let mut db = pool.get()?;
+
+        diesel::delete(timed_messages::table.find(id)).execute(&mut db)?;
+
+        return Ok(());
+    }
+
+    pub fn message_lines(&self) -> Vec<&str> {
+        return self
+            .messages
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    pub fn color(&self) -> Option<AnnouncementColor> {
+        return self
+            .announcement_color
+            .as_deref()
+            .and_then(|color| AnnouncementColor::try_from(color).ok());
+    }
+
+    pub fn to_new(&self) -> NewTimedMessage {
+        return NewTimedMessage {
+            name: self.name.clone(),
+            messages: self.messages.clone(),
+            announcement_color: self.announcement_color.clone(),
+            interval_minutes: self.interval_minutes,
+            min_chat_messages: self.min_chat_messages,
+            enabled: self.enabled,
+        };
+    }
+}
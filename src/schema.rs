@@ -1,11 +1,80 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    action_log (id) {
+        id -> Integer,
+        action_id -> Integer,
+        action_name -> Text,
+        event_summary -> Text,
+        started_at -> BigInt,
+        duration_us -> BigInt,
+        result -> Text,
+        error -> Nullable<Text>,
+        error_line -> Nullable<Integer>,
+        error_column -> Nullable<Integer>,
+        messages -> Text,
+    }
+}
+
 diesel::table! {
     actions (id) {
         id -> Integer,
         name -> Text,
         script -> Binary,
         config -> Binary,
+        failure_count -> Integer,
+    }
+}
+
+diesel::table! {
+    asset_cache (key) {
+        key -> Text,
+        hash -> Text,
+        fetched_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    chat_events (id) {
+        id -> Integer,
+        channel -> Text,
+        kind -> Text,
+        message_id -> Nullable<Text>,
+        user_id -> Nullable<Text>,
+        user_login -> Nullable<Text>,
+        user_name -> Nullable<Text>,
+        text -> Nullable<Text>,
+        status -> Nullable<Text>,
+        source -> Text,
+        created_at -> BigInt,
+        badges -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    chat_events_fts (rowid) {
+        rowid -> Integer,
+        text -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    chat_user_names (user_id, login) {
+        user_id -> Text,
+        login -> Text,
+        name -> Text,
+        last_seen -> BigInt,
+    }
+}
+
+diesel::table! {
+    chat_users (user_id) {
+        user_id -> Text,
+        login -> Text,
+        name -> Text,
+        first_seen -> BigInt,
+        last_seen -> BigInt,
+        messages -> Integer,
     }
 }
 
@@ -17,6 +86,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    mod_log (id) {
+        id -> Integer,
+        channel -> Text,
+        user_id -> Nullable<Text>,
+        user_login -> Text,
+        action -> Text,
+        duration_secs -> Nullable<BigInt>,
+        message -> Nullable<Text>,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    reward_rules (reward_id) {
+        reward_id -> Text,
+        auto_fulfill -> Text,
+        action_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    script_modules (id) {
+        id -> Integer,
+        name -> Text,
+        source -> Binary,
+    }
+}
+
 diesel::table! {
     settings (id) {
         id -> Integer,
@@ -25,7 +123,100 @@ diesel::table! {
         channel -> Nullable<Text>,
         user_access_token -> Nullable<Text>,
         user_refresh_token -> Nullable<Text>,
+        action_log_max_entries -> Nullable<Integer>,
+        action_log_max_days -> Nullable<Integer>,
+        chat_max_events -> Nullable<Integer>,
+        chat_emote_size -> Nullable<Float>,
+        chat_emote_providers -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    stats_chatters (session_id, user_id) {
+        session_id -> Integer,
+        user_id -> Text,
+        user_login -> Text,
+        user_name -> Text,
+        messages -> Integer,
+        bits -> Integer,
+        first_time -> Bool,
+    }
+}
+
+diesel::table! {
+    stats_emotes (session_id, emote_id) {
+        session_id -> Integer,
+        emote_id -> Text,
+        emote_name -> Text,
+        uses -> Integer,
+    }
+}
+
+diesel::table! {
+    stats_minutes (session_id, minute) {
+        session_id -> Integer,
+        minute -> BigInt,
+        messages -> Integer,
+        chatters -> Integer,
+        first_time_chatters -> Integer,
+        emotes -> Integer,
+        mod_actions -> Integer,
+        subs -> Integer,
+        bits -> Integer,
+    }
+}
+
+diesel::table! {
+    stream_sessions (id) {
+        id -> Integer,
+        channel -> Text,
+        started_at -> BigInt,
+        ended_at -> Nullable<BigInt>,
+        live -> Bool,
+    }
+}
+
+diesel::table! {
+    timed_messages (id) {
+        id -> Integer,
+        name -> Text,
+        messages -> Text,
+        announcement_color -> Nullable<Text>,
+        interval_minutes -> Integer,
+        min_chat_messages -> Integer,
+        enabled -> Bool,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(actions, kv_store, settings,);
+diesel::table! {
+    user_notes (user_id) {
+        user_id -> Text,
+        notes -> Text,
+        updated_at -> BigInt,
+    }
+}
+
+diesel::joinable!(stats_chatters -> stream_sessions (session_id));
+diesel::joinable!(stats_emotes -> stream_sessions (session_id));
+diesel::joinable!(stats_minutes -> stream_sessions (session_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    action_log,
+    actions,
+    asset_cache,
+    chat_events,
+    chat_events_fts,
+    chat_user_names,
+    chat_users,
+    kv_store,
+    mod_log,
+    reward_rules,
+    script_modules,
+    settings,
+    stats_chatters,
+    stats_emotes,
+    stats_minutes,
+    stream_sessions,
+    timed_messages,
+    user_notes,
+);
@@ -7,15 +7,21 @@ use twitch_api::{
     client::ClientDefault,
     extra::AnnouncementColor,
     helix::{
-        channels::ChannelInformation,
+        channels::{ChannelInformation, GetChannelFollowersRequest},
         chat::{SendAShoutoutRequest, UpdateChatSettingsBody, UpdateChatSettingsRequest},
+        points::{
+            CustomRewardRedemptionStatus, GetCustomRewardRedemptionRequest, GetCustomRewardRequest,
+            UpdateRedemptionStatusBody, UpdateRedemptionStatusRequest,
+        },
+        streams::GetStreamsRequest,
     },
+    types::UserId,
 };
 use twitch_oauth2::{DeviceUserTokenBuilder, Scope, UserToken};
 
 use crate::{
     app::App,
-    twitch::types::TwitchAccount,
+    twitch::types::{ChannelReward, PendingRedemption, TwitchAccount},
     ui::state::{AppState, AppStateDiff},
 };
 
@@ -101,13 +107,171 @@ pub unsafe fn twitch_get_channel_from_login(state: &AppState, channel: &str) {
     });
 }
 
-/// # Safety
-/// This function assumes a valid twitch account is logged in.
-pub unsafe fn twitch_send_message(state: &AppState, message: &str) {
-    let ui_diff_tx = state.channels.ui_diff_tx.clone();
-    let account = state.twitch_account.as_ref().unwrap();
-    let channel = state.connected_channel_info.as_ref().unwrap();
+/// Looks up when the user created their account and since when they follow the channel.
+pub fn twitch_get_user_profile(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+    user_id: &str,
+) {
+    let diff_tx = diff_tx.clone();
+    let client = account.client.clone();
+    let token = account.token.clone();
+    let broadcaster_id = channel.broadcaster_id.clone();
+    let user_id = UserId::from(user_id);
+
+    tokio::spawn(async move {
+        let created_at = match client.get_user_from_id(&user_id, &token).await {
+            Ok(user) => user.map(|user| user.created_at.to_string()),
+            Err(err) => {
+                warn!("Failed to get user information: {}", err);
+                None
+            }
+        };
+
+        let request = GetChannelFollowersRequest::broadcaster_id(&broadcaster_id).user_id(&user_id);
+        let followed_at = match client.req_get(request, &token).await {
+            Ok(response) => response
+                .data
+                .into_iter()
+                .next()
+                .map(|follower| follower.followed_at.to_string()),
+            Err(err) => {
+                warn!("Failed to get follow information: {}", err);
+                None
+            }
+        };
+
+        diff_tx
+            .send(AppStateDiff::UserProfileLoaded {
+                user_id: user_id.to_string(),
+                created_at,
+                followed_at,
+            })
+            .unwrap();
+    });
+}
+
+pub fn twitch_get_stream_status(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+) {
+    let diff_tx = diff_tx.clone();
+    let client = account.client.clone();
+    let token = account.token.clone();
+    let broadcaster_id = channel.broadcaster_id.clone();
+
+    tokio::spawn(async move {
+        let ids = [&broadcaster_id];
+        let request = GetStreamsRequest::user_ids(&ids[..]);
+
+        match client.req_get(request, &token).await {
+            Ok(response) => {
+                diff_tx
+                    .send(AppStateDiff::StreamStatusUpdated(!response.data.is_empty()))
+                    .unwrap();
+            }
+            Err(err) => {
+                warn!("Failed to get stream status: {}", err);
+            }
+        }
+    });
+}
+
+/// Loads all rewards and the unfulfilled redemptions of the rewards we are allowed to manage.
+pub fn twitch_get_redemption_queue(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+) {
+    let diff_tx = diff_tx.clone();
+    let client = account.client.clone();
+    let token = account.token.clone();
+    let broadcaster_id = channel.broadcaster_id.clone();
+
+    tokio::spawn(async move {
+        let all_rewards = client.req_get(GetCustomRewardRequest::broadcaster_id(&broadcaster_id), &token);
+        let manageable_rewards = client.req_get(
+            GetCustomRewardRequest::broadcaster_id(&broadcaster_id).only_manageable_rewards(true),
+            &token,
+        );
+
+        let (all_rewards, manageable_rewards) = match tokio::try_join!(all_rewards, manageable_rewards) {
+            Ok((all_rewards, manageable_rewards)) => (all_rewards.data, manageable_rewards.data),
+            Err(err) => {
+                warn!("Failed to get channel rewards: {}", err);
+                App::show_toast(&diff_tx, ToastKind::Error, "Failed to get channel rewards.");
+                return;
+            }
+        };
+
+        let mut redemptions = Vec::new();
+        for reward in &manageable_rewards {
+            let mut request = GetCustomRewardRedemptionRequest::broadcaster_id(&broadcaster_id)
+                .reward_id(&reward.id)
+                .status(CustomRewardRedemptionStatus::Unfulfilled);
+            request.first = Some(50);
+
+            match client.req_get(request, &token).await {
+                Ok(response) => redemptions.extend(response.data.into_iter().map(PendingRedemption::from)),
+                Err(err) => warn!("Failed to get redemptions of reward {}: {}", reward.title, err),
+            }
+        }
+
+        let rewards = all_rewards
+            .into_iter()
+            .map(|reward| {
+                let is_manageable = manageable_rewards.iter().any(|manageable| manageable.id == reward.id);
+                ChannelReward::from_helix(reward, is_manageable)
+            })
+            .collect();
+
+        diff_tx
+            .send(AppStateDiff::RedemptionQueueLoaded { rewards, redemptions })
+            .unwrap();
+    });
+}
 
+pub fn twitch_update_redemption_status(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+    redemption: &PendingRedemption,
+    status: CustomRewardRedemptionStatus,
+) {
+    let diff_tx = diff_tx.clone();
+    let client = account.client.clone();
+    let token = account.token.clone();
+    let broadcaster_id = channel.broadcaster_id.clone();
+    let reward_id = redemption.reward_id.clone();
+    let redemption_id = redemption.id.clone();
+
+    tokio::spawn(async move {
+        let request = UpdateRedemptionStatusRequest::new(&broadcaster_id, &*reward_id, &*redemption_id);
+
+        match client
+            .req_patch(request, UpdateRedemptionStatusBody::status(status), &token)
+            .await
+        {
+            Ok(_) => {
+                diff_tx.send(AppStateDiff::RedemptionResolved(redemption_id)).unwrap();
+            }
+            Err(err) => {
+                warn!("Failed to update redemption: {}", err);
+                App::show_toast(&diff_tx, ToastKind::Error, "Failed to update redemption.");
+            }
+        }
+    });
+}
+
+pub fn twitch_send_message(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+    message: &str,
+) {
+    let diff_tx = diff_tx.clone();
     let client = account.client.clone();
     let token = account.token.clone();
     let user_id = account.token.user_id.clone();
@@ -122,19 +286,49 @@ pub unsafe fn twitch_send_message(state: &AppState, message: &str) {
             Ok(_) => {}
             Err(err) => {
                 warn!("Failed to send message: {}", err);
-                App::show_toast(&ui_diff_tx, ToastKind::Error, "Failed to send message.");
+                App::show_toast(&diff_tx, ToastKind::Error, "Failed to send message.");
             }
         }
     });
 }
 
-/// # Safety
-/// This function assumes a valid twitch account is logged in.
-pub unsafe fn twitch_send_announcement(state: &AppState, message: &str) {
-    let ui_diff_tx = state.channels.ui_diff_tx.clone();
-    let account = state.twitch_account.as_ref().unwrap();
-    let channel = state.connected_channel_info.as_ref().unwrap();
+pub fn twitch_send_reply(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+    parent_message_id: &str,
+    message: &str,
+) {
+    let diff_tx = diff_tx.clone();
+    let client = account.client.clone();
+    let token = account.token.clone();
+    let user_id = account.token.user_id.clone();
+    let broadcaster_id = channel.broadcaster_id.clone();
+    let parent_message_id = parent_message_id.to_owned();
+    let message = message.trim().to_string();
 
+    tokio::spawn(async move {
+        match client
+            .send_chat_message_reply(broadcaster_id, user_id, &*parent_message_id, &*message, &token)
+            .await
+        {
+            Ok(_) => {}
+            Err(err) => {
+                warn!("Failed to send reply: {}", err);
+                App::show_toast(&diff_tx, ToastKind::Error, "Failed to send reply.");
+            }
+        }
+    });
+}
+
+pub fn twitch_send_announcement(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+    message: &str,
+    color: AnnouncementColor,
+) {
+    let diff_tx = diff_tx.clone();
     let client = account.client.clone();
     let token = account.token.clone();
     let user_id = account.token.user_id.clone();
@@ -143,26 +337,25 @@ pub unsafe fn twitch_send_announcement(state: &AppState, message: &str) {
 
     tokio::spawn(async move {
         match client
-            .send_chat_announcement(broadcaster_id, user_id, &*message, AnnouncementColor::Primary, &token)
+            .send_chat_announcement(broadcaster_id, user_id, &*message, color, &token)
             .await
         {
             Ok(_) => {}
             Err(err) => {
                 warn!("Failed to send announcement: {}", err);
-                App::show_toast(&ui_diff_tx, ToastKind::Error, "Failed to send announcement.");
+                App::show_toast(&diff_tx, ToastKind::Error, "Failed to send announcement.");
             }
         }
     });
 }
 
-// TODO: continue refactoring this
-/// # Safety
-/// This function assumes a valid twitch account is logged in.
-pub unsafe fn twitch_delete_message(state: &AppState, message_id: &str) {
-    let ui_diff_tx = state.channels.ui_diff_tx.clone();
-    let account = state.twitch_account.as_ref().unwrap();
-    let channel = state.connected_channel_info.as_ref().unwrap();
-
+pub fn twitch_delete_message(
+    diff_tx: &mpsc::Sender<AppStateDiff>,
+    account: &TwitchAccount,
+    channel: &ChannelInformation,
+    message_id: &str,
+) {
+    let diff_tx = diff_tx.clone();
     let client = account.client.clone();
     let token = account.token.clone();
     let user_id = account.token.user_id.clone();
@@ -177,7 +370,7 @@ pub unsafe fn twitch_delete_message(state: &AppState, message_id: &str) {
             Ok(_) => {}
             Err(err) => {
                 warn!("Failed to delete message: {}", err);
-                App::show_toast(&ui_diff_tx, ToastKind::Error, "Failed to delete message.");
+                App::show_toast(&diff_tx, ToastKind::Error, "Failed to delete message.");
             }
         }
     });
@@ -0,0 +1,295 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use linkify::LinkFinder;
+use twitch_irc::message::PrivmsgMessage;
+
+/// A chat message along with its parsed text. Derefs to the message so it can be used like one.
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub message: PrivmsgMessage,
+    /// Shared by every copy of the event, the text is parsed once when the message is received.
+    pub parsed: Arc<ParsedMessage>,
+}
+
+impl From<PrivmsgMessage> for ChatMessage {
+    fn from(message: PrivmsgMessage) -> Self {
+        return Self {
+            parsed: Arc::new(ParsedMessage::parse(&message)),
+            message,
+        };
+    }
+}
+
+impl Deref for ChatMessage {
+    type Target = PrivmsgMessage;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.message;
+    }
+}
+
+impl DerefMut for ChatMessage {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return &mut self.message;
+    }
+}
+
+/// The text of a chat message split into its parts, in the order they appear.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedMessage {
+    pub segments: Vec<MessageSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageSegment {
+    Text(String),
+    Link(String),
+    /// `@name`, the login is lowercase and without the `@`.
+    Mention {
+        text: String,
+        login: String,
+    },
+    /// A twitch emote, emotes of other providers are only known to the chat.
+    Emote {
+        id: String,
+        code: String,
+    },
+    /// `Cheer100` and the like in messages with bits.
+    Cheermote {
+        text: String,
+        prefix: String,
+        bits: u64,
+    },
+    /// The `@name` twitch puts in front of replies.
+    ReplyPrefix {
+        text: String,
+        login: String,
+    },
+}
+
+impl MessageSegment {
+    pub fn text(&self) -> &str {
+        return match self {
+            MessageSegment::Text(text) | MessageSegment::Link(text) => text,
+            MessageSegment::Mention { text, .. }
+            | MessageSegment::Cheermote { text, .. }
+            | MessageSegment::ReplyPrefix { text, .. } => text,
+            MessageSegment::Emote { code, .. } => code,
+        };
+    }
+}
+
+impl ParsedMessage {
+    pub fn parse(message: &PrivmsgMessage) -> Self {
+        let text = message.message_text.as_str();
+        let trimmed = text.trim();
+        // emote ranges count characters of the untrimmed text
+        let trimmed_chars = text[..text.len() - text.trim_start().len()].chars().count();
+
+        let char_offsets = trimmed
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(trimmed.len()))
+            .collect::<Vec<_>>();
+
+        let mut parser = Parser {
+            segments: Vec::new(),
+            has_bits: message.bits.is_some_and(|bits| bits > 0),
+        };
+
+        let mut last_end = 0;
+
+        if let Some(login) = reply_parent_login(message)
+            && let Some(prefix) = trimmed.get(..login.len() + 1)
+            && prefix
+                .strip_prefix('@')
+                .is_some_and(|name| name.eq_ignore_ascii_case(login))
+            && trimmed[prefix.len()..].chars().next().is_none_or(char::is_whitespace)
+        {
+            parser.segments.push(MessageSegment::ReplyPrefix {
+                text: prefix.to_owned(),
+                login: login.to_lowercase(),
+            });
+            last_end = prefix.len();
+        }
+
+        let mut emotes = message.emotes.iter().collect::<Vec<_>>();
+        emotes.sort_by_key(|emote| emote.char_range.start);
+
+        for emote in emotes {
+            let (Some(start), Some(end)) = (
+                emote.char_range.start.checked_sub(trimmed_chars),
+                emote.char_range.end.checked_sub(trimmed_chars),
+            ) else {
+                continue;
+            };
+            // twitch sometimes sends ranges past the end of the message
+            let (Some(&start), Some(&end)) = (char_offsets.get(start), char_offsets.get(end)) else {
+                continue;
+            };
+            if start < last_end {
+                continue;
+            }
+
+            parser.push_links(&trimmed[last_end..start]);
+            parser.segments.push(MessageSegment::Emote {
+                id: emote.id.clone(),
+                code: emote.code.clone(),
+            });
+            last_end = end;
+        }
+
+        parser.push_links(&trimmed[last_end..]);
+
+        return Self {
+            segments: parser.segments,
+        };
+    }
+
+    /// The message without the reply prefix.
+    pub fn text(&self) -> String {
+        return self
+            .segments
+            .iter()
+            .filter(|segment| !matches!(segment, MessageSegment::ReplyPrefix { .. }))
+            .map(MessageSegment::text)
+            .collect::<String>()
+            .trim()
+            .to_owned();
+    }
+
+    /// Login of the sender of the message this one replies to.
+    pub fn reply_to(&self) -> Option<&str> {
+        return self.segments.iter().find_map(|segment| match segment {
+            MessageSegment::ReplyPrefix { login, .. } => Some(login.as_str()),
+            _ => None,
+        });
+    }
+
+    /// Logins mentioned with `@`, in order and possibly repeated.
+    pub fn mentions(&self) -> impl Iterator<Item = &str> {
+        return self.segments.iter().filter_map(|segment| match segment {
+            MessageSegment::Mention { login, .. } => Some(login.as_str()),
+            _ => None,
+        });
+    }
+
+    /// Codes of the twitch emotes, in order and possibly repeated.
+    pub fn emotes(&self) -> impl Iterator<Item = &str> {
+        return self.segments.iter().filter_map(|segment| match segment {
+            MessageSegment::Emote { code, .. } => Some(code.as_str()),
+            _ => None,
+        });
+    }
+
+    /// Whether the user is mentioned with or without `@`, or replied to.
+    pub fn mentions_user(&self, login: &str) -> bool {
+        return self.segments.iter().any(|segment| match segment {
+            MessageSegment::Mention { login: mentioned, .. } | MessageSegment::ReplyPrefix { login: mentioned, .. } => {
+                mentioned.eq_ignore_ascii_case(login)
+            }
+            MessageSegment::Text(text) => text
+                .split(|char: char| !is_login_char(char))
+                .any(|word| word.eq_ignore_ascii_case(login)),
+            _ => false,
+        });
+    }
+}
+
+struct Parser {
+    segments: Vec<MessageSegment>,
+    has_bits: bool,
+}
+
+impl Parser {
+    fn push_links(&mut self, text: &str) {
+        let mut last_end = 0;
+
+        for link in LinkFinder::new().links(text) {
+            self.push_words(&text[last_end..link.start()]);
+            self.segments.push(MessageSegment::Link(link.as_str().to_owned()));
+            last_end = link.end();
+        }
+
+        self.push_words(&text[last_end..]);
+    }
+
+    fn push_words(&mut self, text: &str) {
+        for word in text.split_inclusive(char::is_whitespace) {
+            let trimmed = word.trim_end();
+            let whitespace = &word[trimmed.len()..];
+
+            if let Some(segment) = self.mention(trimmed).or_else(|| self.cheermote(trimmed)) {
+                let text_len = segment.text().len();
+                self.segments.push(segment);
+                // punctuation after a mention, e.g. `@name,`
+                self.push_text(&trimmed[text_len..]);
+                self.push_text(whitespace);
+            } else {
+                self.push_text(word);
+            }
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        if let Some(MessageSegment::Text(last)) = self.segments.last_mut() {
+            last.push_str(text);
+        } else {
+            self.segments.push(MessageSegment::Text(text.to_owned()));
+        }
+    }
+
+    fn mention(&self, word: &str) -> Option<MessageSegment> {
+        let name = word.strip_prefix('@')?;
+        let login_len = name.find(|char: char| !is_login_char(char)).unwrap_or(name.len());
+        if login_len == 0 {
+            return None;
+        }
+
+        return Some(MessageSegment::Mention {
+            text: word[..login_len + 1].to_owned(),
+            login: name[..login_len].to_lowercase(),
+        });
+    }
+
+    /// Cheermote prefixes differ between channels, any letters followed by an amount count in messages with bits.
+    fn cheermote(&self, word: &str) -> Option<MessageSegment> {
+        if !self.has_bits {
+            return None;
+        }
+
+        let prefix = word.trim_end_matches(|char: char| char.is_ascii_digit());
+        if prefix.is_empty() || !prefix.chars().all(|char| char.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        let bits = word[prefix.len()..].parse::<u64>().ok().filter(|bits| *bits > 0)?;
+
+        return Some(MessageSegment::Cheermote {
+            text: word.to_owned(),
+            prefix: prefix.to_owned(),
+            bits,
+        });
+    }
+}
+
+fn is_login_char(char: char) -> bool {
+    return char.is_ascii_alphanumeric() || char == '_';
+}
+
+fn reply_parent_login(message: &PrivmsgMessage) -> Option<&str> {
+    return message
+        .source
+        .tags
+        .0
+        .get("reply-parent-user-login")?
+        .as_deref()
+        .filter(|login| !login.is_empty());
+}
@@ -1,2 +1,4 @@
 pub mod api;
+pub mod message;
+pub mod third_party;
 pub mod types;
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, de::DeserializeOwned};
+use strum::{Display, EnumIter, EnumString, IntoStaticStr};
+
+/// BetterTTV has no flag for them, its zero width emotes are a fixed set of global ones.
+const BTTV_ZERO_WIDTH_EMOTES: [&str; 8] = [
+    "SoSnowy",
+    "IceCold",
+    "SantaHat",
+    "TopHat",
+    "ReinDeer",
+    "CandyCane",
+    "cvMask",
+    "cvHazmat",
+];
+/// Set on the emote in an emote set.
+const SEVEN_TV_ZERO_WIDTH_FLAG: u32 = 1;
+/// Set on the emote itself.
+const SEVEN_TV_ZERO_WIDTH_DATA_FLAG: u32 = 1 << 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumIter, EnumString, IntoStaticStr)]
+pub enum EmoteProvider {
+    #[strum(to_string = "7TV")]
+    SevenTv,
+    #[strum(to_string = "BetterTTV")]
+    BetterTtv,
+    #[strum(to_string = "FrankerFaceZ")]
+    FrankerFaceZ,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThirdPartyEmote {
+    pub provider: EmoteProvider,
+    pub id: String,
+    pub code: String,
+    pub image_url: String,
+    /// Drawn on top of the emote before it instead of next to it.
+    pub zero_width: bool,
+}
+
+/// Where the emote providers are reached, only changed to test against a local server.
+#[derive(Debug, Clone)]
+pub struct EmoteApi {
+    pub seven_tv: String,
+    pub bttv: String,
+    pub ffz: String,
+}
+
+impl Default for EmoteApi {
+    fn default() -> Self {
+        return Self {
+            seven_tv: String::from("https://7tv.io/v3"),
+            bttv: String::from("https://api.betterttv.net/3"),
+            ffz: String::from("https://api.frankerfacez.com/v1"),
+        };
+    }
+}
+
+impl EmoteApi {
+    /// The global emotes of `provider` without `channel_id`, otherwise the emotes of that channel. Channels that never
+    /// signed up with the provider have no emotes.
+    pub async fn load_emotes(
+        &self,
+        http: &reqwest::Client,
+        provider: EmoteProvider,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<ThirdPartyEmote>> {
+        return match provider {
+            EmoteProvider::SevenTv => self.load_seven_tv_emotes(http, channel_id).await,
+            EmoteProvider::BetterTtv => self.load_bttv_emotes(http, channel_id).await,
+            EmoteProvider::FrankerFaceZ => self.load_ffz_emotes(http, channel_id).await,
+        };
+    }
+
+    async fn load_seven_tv_emotes(
+        &self,
+        http: &reqwest::Client,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<ThirdPartyEmote>> {
+        let emote_set = match channel_id {
+            Some(channel_id) => {
+                let url = format!("{}/users/twitch/{channel_id}", self.seven_tv);
+                fetch_json::<SevenTvUser>(http, &url)
+                    .await?
+                    .and_then(|user| user.emote_set)
+            }
+            None => fetch_json::<SevenTvEmoteSet>(http, &format!("{}/emote-sets/global", self.seven_tv)).await?,
+        };
+
+        let emotes = emote_set
+            .and_then(|emote_set| emote_set.emotes)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|emote| {
+                let data = emote.data?;
+                // the largest webp, every emote has them
+                let file = data.host.files.iter().rfind(|file| file.format == "WEBP")?;
+
+                return Some(ThirdPartyEmote {
+                    provider: EmoteProvider::SevenTv,
+                    image_url: format!("https:{}/{}", data.host.url, file.name),
+                    id: emote.id,
+                    code: emote.name,
+                    zero_width: emote.flags & SEVEN_TV_ZERO_WIDTH_FLAG != 0
+                        || data.flags & SEVEN_TV_ZERO_WIDTH_DATA_FLAG != 0,
+                });
+            })
+            .collect();
+
+        return Ok(emotes);
+    }
+
+    async fn load_bttv_emotes(&self, http: &reqwest::Client, channel_id: Option<&str>) -> Result<Vec<ThirdPartyEmote>> {
+        let emotes = match channel_id {
+            Some(channel_id) => {
+                let url = format!("{}/cached/users/twitch/{channel_id}", self.bttv);
+                fetch_json::<BttvUser>(http, &url)
+                    .await?
+                    .map(|user| user.channel_emotes.into_iter().chain(user.shared_emotes).collect())
+            }
+            None => fetch_json::<Vec<BttvEmote>>(http, &format!("{}/cached/emotes/global", self.bttv)).await?,
+        };
+
+        let emotes = emotes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|emote| ThirdPartyEmote {
+                provider: EmoteProvider::BetterTtv,
+                image_url: format!("https://cdn.betterttv.net/emote/{}/3x.webp", emote.id),
+                zero_width: BTTV_ZERO_WIDTH_EMOTES.contains(&emote.code.as_str()),
+                id: emote.id,
+                code: emote.code,
+            })
+            .collect();
+
+        return Ok(emotes);
+    }
+
+    async fn load_ffz_emotes(&self, http: &reqwest::Client, channel_id: Option<&str>) -> Result<Vec<ThirdPartyEmote>> {
+        let sets = match channel_id {
+            Some(channel_id) => {
+                let url = format!("{}/room/id/{channel_id}", self.ffz);
+                fetch_json::<FfzRoom>(http, &url)
+                    .await?
+                    .map(|room| room.sets.into_values().collect::<Vec<_>>())
+            }
+            None => fetch_json::<FfzGlobalSets>(http, &format!("{}/set/global", self.ffz))
+                .await?
+                .map(|mut global| {
+                    global
+                        .default_sets
+                        .iter()
+                        .filter_map(|set_id| global.sets.remove(&set_id.to_string()))
+                        .collect()
+                }),
+        };
+
+        let emotes = sets
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|set| set.emoticons)
+            .filter_map(|emote| {
+                // animated ones have a still image as well
+                let image_url = [emote.animated.as_ref(), Some(&emote.urls)]
+                    .into_iter()
+                    .flatten()
+                    .find_map(largest_ffz_image)?;
+
+                return Some(ThirdPartyEmote {
+                    provider: EmoteProvider::FrankerFaceZ,
+                    id: emote.id.to_string(),
+                    code: emote.name,
+                    image_url,
+                    zero_width: emote.modifier,
+                });
+            })
+            .collect();
+
+        return Ok(emotes);
+    }
+}
+
+/// `None` if there is nothing at `url`.
+async fn fetch_json<T: DeserializeOwned>(http: &reqwest::Client, url: &str) -> Result<Option<T>> {
+    let response = http.get(url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let body = response.error_for_status()?.bytes().await?;
+
+    return Ok(Some(serde_json::from_slice(&body)?));
+}
+
+fn largest_ffz_image(urls: &HashMap<String, Option<String>>) -> Option<String> {
+    return ["4", "2", "1"]
+        .into_iter()
+        .find_map(|scale| urls.get(scale).cloned().flatten());
+}
+
+#[derive(Deserialize)]
+struct SevenTvUser {
+    emote_set: Option<SevenTvEmoteSet>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvEmoteSet {
+    emotes: Option<Vec<SevenTvEmote>>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvEmote {
+    id: String,
+    name: String,
+    #[serde(default)]
+    flags: u32,
+    data: Option<SevenTvEmoteData>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvEmoteData {
+    #[serde(default)]
+    flags: u32,
+    host: SevenTvHost,
+}
+
+#[derive(Deserialize)]
+struct SevenTvHost {
+    url: String,
+    files: Vec<SevenTvFile>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvFile {
+    name: String,
+    format: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BttvUser {
+    #[serde(default)]
+    channel_emotes: Vec<BttvEmote>,
+    #[serde(default)]
+    shared_emotes: Vec<BttvEmote>,
+}
+
+#[derive(Deserialize)]
+struct BttvEmote {
+    id: String,
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct FfzGlobalSets {
+    default_sets: Vec<u64>,
+    sets: HashMap<String, FfzSet>,
+}
+
+#[derive(Deserialize)]
+struct FfzRoom {
+    sets: HashMap<String, FfzSet>,
+}
+
+#[derive(Deserialize)]
+struct FfzSet {
+    emoticons: Vec<FfzEmote>,
+}
+
+#[derive(Deserialize)]
+struct FfzEmote {
+    id: u64,
+    name: String,
+    urls: HashMap<String, Option<String>>,
+    animated: Option<HashMap<String, Option<String>>>,
+    #[serde(default)]
+    modifier: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    const SEVEN_TV_GLOBAL: &str = r#"{
+        "id": "global",
+        "emotes": [
+            {
+                "id": "60ae958e229664e8667aea38",
+                "name": "peepoHappy",
+                "flags": 0,
+                "data": {
+                    "flags": 0,
+                    "host": {
+                        "url": "//cdn.7tv.app/emote/60ae958e229664e8667aea38",
+                        "files": [
+                            { "name": "1x.avif", "format": "AVIF" },
+                            { "name": "1x.webp", "format": "WEBP" },
+                            { "name": "4x.avif", "format": "AVIF" },
+                            { "name": "4x.webp", "format": "WEBP" }
+                        ]
+                    }
+                }
+            },
+            {
+                "id": "6128ed55a50c52b1429e09dc",
+                "name": "RainTime",
+                "flags": 0,
+                "data": {
+                    "flags": 256,
+                    "host": {
+                        "url": "//cdn.7tv.app/emote/6128ed55a50c52b1429e09dc",
+                        "files": [{ "name": "1x.webp", "format": "WEBP" }]
+                    }
+                }
+            },
+            { "id": "deleted", "name": "Gone", "flags": 0, "data": null }
+        ]
+    }"#;
+    const SEVEN_TV_USER: &str = r#"{
+        "id": "12345",
+        "emote_set": {
+            "emotes": [
+                {
+                    "id": "63071bb9464de28875c52531",
+                    "name": "Stare",
+                    "flags": 1,
+                    "data": {
+                        "flags": 0,
+                        "host": {
+                            "url": "//cdn.7tv.app/emote/63071bb9464de28875c52531",
+                            "files": [{ "name": "2x.webp", "format": "WEBP" }]
+                        }
+                    }
+                }
+            ]
+        }
+    }"#;
+    const BTTV_GLOBAL: &str = r#"[
+        { "id": "54fa925e01e468494b85b54d", "code": "OhMyGoodness", "imageType": "png", "animated": false },
+        { "id": "5e76d338d6581c3724c0f0b2", "code": "cvHazmat", "imageType": "png", "animated": false }
+    ]"#;
+    const BTTV_USER: &str = r#"{
+        "id": "5a2c0b8e4f0c1e0a1d1b2c3d",
+        "channelEmotes": [{ "id": "566ca04265dbbdab32ec054a", "code": "D:", "imageType": "png" }],
+        "sharedEmotes": [{ "id": "5f1b0186cf6d2144653d2970", "code": "catJAM", "imageType": "gif" }]
+    }"#;
+    const FFZ_GLOBAL: &str = r#"{
+        "default_sets": [3],
+        "sets": {
+            "3": {
+                "id": 3,
+                "emoticons": [
+                    {
+                        "id": 9,
+                        "name": "ZreknarF",
+                        "urls": { "1": "https://cdn.frankerfacez.com/emote/9/1", "2": null }
+                    }
+                ]
+            },
+            "4330": {
+                "id": 4330,
+                "emoticons": [
+                    { "id": 1, "name": "NotDefault", "urls": { "1": "https://cdn.frankerfacez.com/emote/1/1" } }
+                ]
+            }
+        }
+    }"#;
+    const FFZ_ROOM: &str = r#"{
+        "room": { "twitch_id": 12345, "set": 123 },
+        "sets": {
+            "123": {
+                "id": 123,
+                "emoticons": [
+                    {
+                        "id": 720507,
+                        "name": "monkaH",
+                        "urls": {
+                            "1": "https://cdn.frankerfacez.com/emote/720507/1",
+                            "4": "https://cdn.frankerfacez.com/emote/720507/4"
+                        },
+                        "animated": { "1": "https://cdn.frankerfacez.com/emote/720507/animated/1" }
+                    },
+                    {
+                        "id": 720508,
+                        "name": "ffzHyper",
+                        "modifier": true,
+                        "urls": { "1": "https://cdn.frankerfacez.com/emote/720508/1" }
+                    }
+                ]
+            }
+        }
+    }"#;
+
+    /// Answers requests with the body of their path, or a 404 for unknown paths.
+    async fn start_stub_server(routes: HashMap<String, &'static str>) -> EmoteApi {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+                let response = match routes.get(path) {
+                    Some(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+                         close\r\n\r\n{body}",
+                        body.len()
+                    ),
+                    None => String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        return EmoteApi {
+            seven_tv: format!("{base_url}/7tv"),
+            bttv: format!("{base_url}/bttv"),
+            ffz: format!("{base_url}/ffz"),
+        };
+    }
+
+    async fn stub_api() -> EmoteApi {
+        return start_stub_server(HashMap::from([
+            (String::from("/7tv/emote-sets/global"), SEVEN_TV_GLOBAL),
+            (String::from("/7tv/users/twitch/12345"), SEVEN_TV_USER),
+            (String::from("/bttv/cached/emotes/global"), BTTV_GLOBAL),
+            (String::from("/bttv/cached/users/twitch/12345"), BTTV_USER),
+            (String::from("/ffz/set/global"), FFZ_GLOBAL),
+            (String::from("/ffz/room/id/12345"), FFZ_ROOM),
+        ]))
+        .await;
+    }
+
+    fn codes(emotes: &[ThirdPartyEmote]) -> Vec<&str> {
+        return emotes.iter().map(|emote| emote.code.as_str()).collect();
+    }
+
+    #[tokio::test]
+    async fn loads_seven_tv_emotes() {
+        let api = stub_api().await;
+        let http = reqwest::Client::new();
+
+        let global = api.load_emotes(&http, EmoteProvider::SevenTv, None).await.unwrap();
+        assert_eq!(codes(&global), ["peepoHappy", "RainTime"]);
+        assert_eq!(
+            global[0].image_url,
+            "https://cdn.7tv.app/emote/60ae958e229664e8667aea38/4x.webp"
+        );
+        assert!(!global[0].zero_width);
+        assert!(global[1].zero_width);
+
+        let channel = api
+            .load_emotes(&http, EmoteProvider::SevenTv, Some("12345"))
+            .await
+            .unwrap();
+        assert_eq!(codes(&channel), ["Stare"]);
+        assert!(channel[0].zero_width);
+    }
+
+    #[tokio::test]
+    async fn loads_bttv_emotes() {
+        let api = stub_api().await;
+        let http = reqwest::Client::new();
+
+        let global = api.load_emotes(&http, EmoteProvider::BetterTtv, None).await.unwrap();
+        assert_eq!(codes(&global), ["OhMyGoodness", "cvHazmat"]);
+        assert!(!global[0].zero_width);
+        assert!(global[1].zero_width);
+
+        let channel = api
+            .load_emotes(&http, EmoteProvider::BetterTtv, Some("12345"))
+            .await
+            .unwrap();
+        assert_eq!(codes(&channel), ["D:", "catJAM"]);
+        assert_eq!(
+            channel[1].image_url,
+            "https://cdn.betterttv.net/emote/5f1b0186cf6d2144653d2970/3x.webp"
+        );
+    }
+
+    #[tokio::test]
+    async fn loads_ffz_emotes() {
+        let api = stub_api().await;
+        let http = reqwest::Client::new();
+
+        let global = api.load_emotes(&http, EmoteProvider::FrankerFaceZ, None).await.unwrap();
+        assert_eq!(codes(&global), ["ZreknarF"]);
+        assert_eq!(global[0].image_url, "https://cdn.frankerfacez.com/emote/9/1");
+
+        let channel = api
+            .load_emotes(&http, EmoteProvider::FrankerFaceZ, Some("12345"))
+            .await
+            .unwrap();
+        assert_eq!(codes(&channel), ["monkaH", "ffzHyper"]);
+        assert_eq!(
+            channel[0].image_url,
+            "https://cdn.frankerfacez.com/emote/720507/animated/1"
+        );
+        assert!(channel[1].zero_width);
+    }
+
+    #[tokio::test]
+    async fn channels_without_emotes_are_empty() {
+        let api = stub_api().await;
+        let http = reqwest::Client::new();
+
+        for provider in [
+            EmoteProvider::SevenTv,
+            EmoteProvider::BetterTtv,
+            EmoteProvider::FrankerFaceZ,
+        ] {
+            let emotes = api.load_emotes(&http, provider, Some("404")).await.unwrap();
+            assert!(emotes.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_on_malformed_responses() {
+        let api = start_stub_server(HashMap::from([(String::from("/bttv/cached/emotes/global"), "<html>")])).await;
+        let http = reqwest::Client::new();
+
+        assert!(api.load_emotes(&http, EmoteProvider::BetterTtv, None).await.is_err());
+    }
+}
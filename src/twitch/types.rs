@@ -1,17 +1,99 @@
-use twitch_api::HelixClient;
+use anyhow::{Result, bail};
+use twitch_api::{
+    HelixClient,
+    eventsub::{
+        Event, EventSubscription, Message, Payload,
+        channel::{
+            ChannelCheerV1Payload, ChannelFollowV2Payload, ChannelHypeTrainBeginV1Payload,
+            ChannelHypeTrainEndV1Payload, ChannelHypeTrainProgressV1Payload,
+            ChannelPointsCustomRewardRedemptionAddV1Payload, ChannelPollBeginV1Payload, ChannelPollEndV1Payload,
+            ChannelPollProgressV1Payload, ChannelPredictionBeginV1Payload, ChannelPredictionEndV1Payload,
+            ChannelPredictionLockV1Payload, ChannelPredictionProgressV1Payload, ChannelRaidV1Payload,
+            ChannelSubscribeV1Payload, ChannelSubscriptionGiftV1Payload, ChannelSubscriptionMessageV1Payload,
+        },
+        stream::{StreamOfflineV1Payload, StreamOnlineV1Payload},
+    },
+    helix::points::{CustomReward, CustomRewardRedemption},
+};
 use twitch_irc::message::{
-    ClearChatMessage, ClearMsgMessage, GlobalUserStateMessage, JoinMessage, NoticeMessage, PartMessage, PingMessage,
-    PongMessage, PrivmsgMessage, ReconnectMessage, RoomStateMessage, ServerMessage, UserNoticeMessage,
+    ClearChatMessage, ClearMsgMessage, GlobalUserStateMessage, IRCMessage, JoinMessage, NoticeMessage, PartMessage,
+    PingMessage, PongMessage, PrivmsgMessage, ReconnectMessage, RoomStateMessage, ServerMessage, UserNoticeMessage,
     UserStateMessage, WhisperMessage,
 };
 use twitch_oauth2::UserToken;
 
+use crate::twitch::message::ChatMessage;
+
 #[derive(Clone)]
 pub struct TwitchAccount {
     pub client: HelixClient<'static, reqwest::Client>,
     pub token: UserToken,
 }
 
+/// A channel point reward of the connected channel.
+#[derive(Debug, Clone)]
+pub struct ChannelReward {
+    pub id: String,
+    pub title: String,
+    pub cost: i64,
+    /// Only rewards created with our client id can have their redemptions fulfilled or canceled.
+    pub is_manageable: bool,
+}
+
+impl ChannelReward {
+    pub fn from_helix(reward: CustomReward, is_manageable: bool) -> Self {
+        return Self {
+            id: reward.id.to_string(),
+            title: reward.title,
+            cost: reward.cost as i64,
+            is_manageable,
+        };
+    }
+}
+
+/// An unfulfilled channel point redemption, either fetched from helix or received through EventSub.
+#[derive(Debug, Clone)]
+pub struct PendingRedemption {
+    pub id: String,
+    pub reward_id: String,
+    pub reward_title: String,
+    pub cost: i64,
+    pub user_login: String,
+    pub user_name: String,
+    pub user_input: String,
+    pub redeemed_at: String,
+}
+
+impl From<CustomRewardRedemption> for PendingRedemption {
+    fn from(redemption: CustomRewardRedemption) -> Self {
+        return Self {
+            id: redemption.id.to_string(),
+            reward_id: redemption.reward.id.to_string(),
+            reward_title: redemption.reward.title,
+            cost: redemption.reward.cost,
+            user_login: redemption.user_login.to_string(),
+            user_name: redemption.user_name.to_string(),
+            user_input: redemption.user_input,
+            redeemed_at: redemption.redeemed_at.to_string(),
+        };
+    }
+}
+
+impl From<&ChannelPointsCustomRewardRedemptionAddV1Payload> for PendingRedemption {
+    fn from(redemption: &ChannelPointsCustomRewardRedemptionAddV1Payload) -> Self {
+        return Self {
+            id: redemption.id.to_string(),
+            reward_id: redemption.reward.id.to_string(),
+            reward_title: redemption.reward.title.clone(),
+            cost: redemption.reward.cost,
+            user_login: redemption.user_login.to_string(),
+            user_name: redemption.user_name.to_string(),
+            user_input: redemption.user_input.clone(),
+            redeemed_at: redemption.redeemed_at.to_string(),
+        };
+    }
+}
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum TwitchEvent {
@@ -24,13 +106,32 @@ pub enum TwitchEvent {
     Part(PartMessage),
     Ping(PingMessage),
     Pong(PongMessage),
-    Privmsg(PrivmsgMessage),
+    Privmsg(ChatMessage),
     Reconnect(ReconnectMessage),
     RoomState(RoomStateMessage),
     UserNotice(UserNoticeMessage),
     UserState(UserStateMessage),
     Whisper(WhisperMessage),
-    // PUBSUB EVENTS
+    // EVENTSUB EVENTS
+    Follow(ChannelFollowV2Payload),
+    Subscribe(ChannelSubscribeV1Payload),
+    SubscriptionMessage(ChannelSubscriptionMessageV1Payload),
+    SubscriptionGift(ChannelSubscriptionGiftV1Payload),
+    Cheer(ChannelCheerV1Payload),
+    Raid(ChannelRaidV1Payload),
+    Redemption(ChannelPointsCustomRewardRedemptionAddV1Payload),
+    PollBegin(ChannelPollBeginV1Payload),
+    PollProgress(ChannelPollProgressV1Payload),
+    PollEnd(ChannelPollEndV1Payload),
+    PredictionBegin(ChannelPredictionBeginV1Payload),
+    PredictionProgress(ChannelPredictionProgressV1Payload),
+    PredictionLock(ChannelPredictionLockV1Payload),
+    PredictionEnd(ChannelPredictionEndV1Payload),
+    HypeTrainBegin(ChannelHypeTrainBeginV1Payload),
+    HypeTrainProgress(ChannelHypeTrainProgressV1Payload),
+    HypeTrainEnd(ChannelHypeTrainEndV1Payload),
+    StreamOnline(StreamOnlineV1Payload),
+    StreamOffline(StreamOfflineV1Payload),
 }
 
 impl TryFrom<ServerMessage> for TwitchEvent {
@@ -46,7 +147,7 @@ impl TryFrom<ServerMessage> for TwitchEvent {
             ServerMessage::Part(msg) => Ok(TwitchEvent::Part(msg)),
             ServerMessage::Ping(msg) => Ok(TwitchEvent::Ping(msg)),
             ServerMessage::Pong(msg) => Ok(TwitchEvent::Pong(msg)),
-            ServerMessage::Privmsg(msg) => Ok(TwitchEvent::Privmsg(msg)),
+            ServerMessage::Privmsg(msg) => Ok(TwitchEvent::Privmsg(ChatMessage::from(msg))),
             ServerMessage::Reconnect(msg) => Ok(TwitchEvent::Reconnect(msg)),
             ServerMessage::RoomState(msg) => Ok(TwitchEvent::RoomState(msg)),
             ServerMessage::UserNotice(msg) => Ok(TwitchEvent::UserNotice(msg)),
@@ -57,6 +158,79 @@ impl TryFrom<ServerMessage> for TwitchEvent {
     }
 }
 
+impl TryFrom<Event> for TwitchEvent {
+    type Error = ();
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        return match event {
+            Event::ChannelFollowV2(payload) => notification(payload).map(TwitchEvent::Follow),
+            Event::ChannelSubscribeV1(payload) => notification(payload).map(TwitchEvent::Subscribe),
+            Event::ChannelSubscriptionMessageV1(payload) => notification(payload).map(TwitchEvent::SubscriptionMessage),
+            Event::ChannelSubscriptionGiftV1(payload) => notification(payload).map(TwitchEvent::SubscriptionGift),
+            Event::ChannelCheerV1(payload) => notification(payload).map(TwitchEvent::Cheer),
+            Event::ChannelRaidV1(payload) => notification(payload).map(TwitchEvent::Raid),
+            Event::ChannelPointsCustomRewardRedemptionAddV1(payload) => {
+                notification(payload).map(TwitchEvent::Redemption)
+            }
+            Event::ChannelPollBeginV1(payload) => notification(payload).map(TwitchEvent::PollBegin),
+            Event::ChannelPollProgressV1(payload) => notification(payload).map(TwitchEvent::PollProgress),
+            Event::ChannelPollEndV1(payload) => notification(payload).map(TwitchEvent::PollEnd),
+            Event::ChannelPredictionBeginV1(payload) => notification(payload).map(TwitchEvent::PredictionBegin),
+            Event::ChannelPredictionProgressV1(payload) => notification(payload).map(TwitchEvent::PredictionProgress),
+            Event::ChannelPredictionLockV1(payload) => notification(payload).map(TwitchEvent::PredictionLock),
+            Event::ChannelPredictionEndV1(payload) => notification(payload).map(TwitchEvent::PredictionEnd),
+            Event::ChannelHypeTrainBeginV1(payload) => notification(payload).map(TwitchEvent::HypeTrainBegin),
+            Event::ChannelHypeTrainProgressV1(payload) => notification(payload).map(TwitchEvent::HypeTrainProgress),
+            Event::ChannelHypeTrainEndV1(payload) => notification(payload).map(TwitchEvent::HypeTrainEnd),
+            Event::StreamOnlineV1(payload) => notification(payload).map(TwitchEvent::StreamOnline),
+            Event::StreamOfflineV1(payload) => notification(payload).map(TwitchEvent::StreamOffline),
+            _ => Err(()),
+        };
+    }
+}
+
+fn notification<E: EventSubscription + Clone>(payload: Payload<E>) -> Result<E::Payload, ()> {
+    return match payload.message {
+        Message::Notification(notification) => Ok(notification),
+        _ => Err(()),
+    };
+}
+
+/// Builds a chat message that never came from twitch, e.g. to test actions with.
+pub fn synthetic_privmsg(
+    channel_login: &str,
+    sender_login: &str,
+    text: &str,
+    badges: &[&str],
+) -> Result<PrivmsgMessage> {
+    let channel_login = channel_login.trim().to_lowercase();
+    let sender_login = sender_login.trim().to_lowercase();
+
+    // anything else would end up in the raw irc line
+    for login in [&channel_login, &sender_login] {
+        if login.is_empty() || !login.chars().all(|char| char.is_ascii_alphanumeric() || char == '_') {
+            bail!("'{login}' is not a valid twitch login, only letters, digits and underscores are allowed");
+        }
+    }
+
+    let badges = badges
+        .iter()
+        .map(|badge| format!("{badge}/1"))
+        .collect::<Vec<_>>()
+        .join(",");
+    let text = text.replace(['\r', '\n'], " ");
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let raw = format!(
+        "@badge-info=;badges={badges};color=;display-name={sender_login};emotes=;first-msg=0;\
+         id=00000000-0000-0000-0000-000000000000;room-id=0;tmi-sent-ts={now};user-id=0 \
+         :{sender_login}!{sender_login}@{sender_login}.tmi.twitch.tv PRIVMSG #{channel_login} :{text}"
+    );
+
+    let source = IRCMessage::parse(&raw)?;
+    return Ok(PrivmsgMessage::try_from(source)?);
+}
+
 pub trait PrivmsgMessageExt {
     fn is_by_broadcaster(&self) -> bool;
     fn is_by_lead_mod(&self) -> bool;
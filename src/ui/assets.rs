@@ -0,0 +1,95 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Arc, mpsc},
+    time::Instant,
+};
+
+use eframe::egui::{Context, Image, load::Bytes};
+
+use crate::workers::asset::{ASSET_RETRY_DELAY, AssetKey, AssetWorkerMessage, BadgeMap};
+
+/// Past this many loaded images the least recently shown ones are dropped, they are loaded again when shown.
+const MAX_LOADED_ASSETS: usize = 1000;
+
+/// Images loaded by the asset worker, handed to egui as bytes under their `AssetKey::uri`.
+pub struct AssetStore {
+    assets: HashMap<AssetKey, Bytes>,
+    /// When the loaded assets were last shown.
+    used: RefCell<HashMap<AssetKey, Instant>>,
+    /// Assets asked for that did not arrive yet, the worker is asked again after a while in case loading failed.
+    requested: RefCell<HashMap<AssetKey, Instant>>,
+    message_tx: mpsc::Sender<AssetWorkerMessage>,
+    /// By channel id, `None` holds the global badges.
+    badge_titles: HashMap<Option<String>, BadgeMap>,
+}
+
+impl AssetStore {
+    pub fn new(message_tx: mpsc::Sender<AssetWorkerMessage>) -> Self {
+        return Self {
+            assets: HashMap::new(),
+            used: RefCell::new(HashMap::new()),
+            requested: RefCell::new(HashMap::new()),
+            message_tx,
+            badge_titles: HashMap::new(),
+        };
+    }
+
+    pub fn insert(&mut self, key: AssetKey, bytes: Arc<[u8]>) {
+        self.requested.borrow_mut().remove(&key);
+        self.used.borrow_mut().insert(key.clone(), Instant::now());
+        self.assets.insert(key, Bytes::Shared(bytes));
+    }
+
+    /// Drops the least recently shown assets once there are too many, egui keeps its own copy until told to forget it.
+    pub fn evict_unused(&mut self, ctx: &Context) {
+        if self.assets.len() <= MAX_LOADED_ASSETS {
+            return;
+        }
+
+        let mut used = self.used.borrow_mut();
+        let mut by_use = used.iter().map(|(key, at)| (*at, key.clone())).collect::<Vec<_>>();
+        by_use.sort_by_key(|(at, _)| *at);
+
+        // evict a tenth at once so this does not run every frame
+        for (_, key) in by_use.into_iter().take(self.assets.len() - MAX_LOADED_ASSETS * 9 / 10) {
+            used.remove(&key);
+            self.assets.remove(&key);
+            ctx.forget_image(&key.uri());
+        }
+    }
+
+    pub fn insert_badge_titles(&mut self, channel_id: Option<String>, titles: BadgeMap) {
+        self.badge_titles.insert(channel_id, titles);
+    }
+
+    /// Channel badges take precedence over global ones, like for the asset worker.
+    pub fn badge_title(&self, channel_id: &str, set_id: &str, version: &str) -> Option<&str> {
+        let set_version = (set_id.to_owned(), version.to_owned());
+
+        return [Some(channel_id.to_owned()), None]
+            .iter()
+            .filter_map(|channel_id| self.badge_titles.get(channel_id)?.get(&set_version))
+            .map(String::as_str)
+            .next();
+    }
+
+    /// Asks the asset worker for the image if it is not loaded yet.
+    pub fn image(&self, key: &AssetKey) -> Option<Image<'static>> {
+        if let Some(bytes) = self.assets.get(key) {
+            self.used.borrow_mut().insert(key.clone(), Instant::now());
+            return Some(Image::from_bytes(key.uri(), bytes.clone()));
+        }
+
+        let mut requested = self.requested.borrow_mut();
+        if requested
+            .get(key)
+            .is_none_or(|requested_at| requested_at.elapsed() >= ASSET_RETRY_DELAY)
+        {
+            requested.insert(key.clone(), Instant::now());
+            self.message_tx.send(AssetWorkerMessage::Request(key.clone())).unwrap();
+        }
+
+        return None;
+    }
+}
@@ -1,3 +1,4 @@
+pub mod assets;
 pub mod fonts;
 pub mod state;
 pub mod tabs;
@@ -5,7 +6,10 @@ pub mod tabs;
 use eframe::egui::{self, Key};
 use egui_dock::{DockArea, Style};
 
-use crate::{App, ui::tabs::TabViewer};
+use crate::{
+    App,
+    ui::tabs::{TabViewer, chat::profile::render_user_profile},
+};
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
@@ -17,6 +21,7 @@ impl eframe::App for App {
         while let Ok(message) = self.state.channels.ui_diff_rx.try_recv() {
             self.apply_state_diff(message);
         }
+        self.state.assets.evict_unused(ctx);
 
         // handle global shortcuts
         let input = ctx.input(|i| i.clone());
@@ -49,6 +54,8 @@ impl eframe::App for App {
             DockArea::new(&mut self.tree)
                 .style(Style::from_egui(ctx.style().as_ref()))
                 .show(ctx, &mut TabViewer { state: &mut self.state });
+            render_user_profile(ctx, &mut self.state);
+            self.state.file_dialog.update(ctx);
             self.state.toasts.show(ctx);
         } else {
             egui::CentralPanel::default().show(ctx, |ui| {
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use egui_file_dialog::FileDialog;
 use egui_toast::{Toast, Toasts};
@@ -7,28 +9,47 @@ use twitch_irc::message::{IRCMessage, IRCTags, NoticeMessage};
 use twitch_oauth2::UserToken;
 
 use crate::{
+    engine::{ActionTestResult, host::ActionCommand},
     models::SqlitePool,
     twitch::{
         api::{twitch_get_channel_from_login, twitch_link_account},
-        types::{TwitchAccount, TwitchEvent},
+        third_party::{EmoteProvider, ThirdPartyEmote},
+        types::{ChannelReward, PendingRedemption, TwitchAccount, TwitchEvent},
+    },
+    ui::{
+        assets::AssetStore,
+        tabs::{
+            actions::ActionsState,
+            chat::ChatState,
+            database::DatabaseState,
+            docs::DocsState,
+            logs::LogsState,
+            redemptions::RedemptionsState,
+            search::{SearchContext, SearchHit, SearchState},
+            settings::SettingsState,
+            stats::StatsState,
+        },
     },
-    ui::tabs::{
-        actions::ActionsState, chat::ChatState, database::DatabaseState, docs::DocsState, logs::LogsState,
-        settings::SettingsState, stats::StatsState,
+    workers::{
+        MPSCChannels,
+        asset::{AssetKey, AssetWorkerMessage, BadgeMap},
+        twitch::{worker_start_twitch_eventsub, worker_start_twitch_irc},
     },
-    workers::{MPSCChannels, twitch::worker_start_twitch_irc},
 };
 
 pub struct AppState {
     pub connected_to_internet: bool,
+    pub is_live: bool,
     pub db_pool: SqlitePool,
 
     // twitch worker and information to start/restart them
     pub channels: MPSCChannels,
     pub twitch_irc_worker_handle: Option<AbortHandle>,
-    pub twitch_pubsub_worker_handle: Option<AbortHandle>,
+    pub twitch_eventsub_worker_handle: Option<AbortHandle>,
 
     // account and channel
+    /// Joined once the history worker saved the events of the previous channel.
+    pub pending_join: Option<String>,
     pub did_we_try_to_join: bool,
     pub when_did_we_try_to_join: Option<std::time::Instant>,
     pub did_we_join: bool,
@@ -41,17 +62,28 @@ pub struct AppState {
     pub zoom_factor: f32,
     pub file_dialog: FileDialog,
     pub toasts: Toasts,
+    pub assets: AssetStore,
 
     // tabs
     pub chat: ChatState,
+    pub search: SearchState,
     pub stats: StatsState,
     pub actions: ActionsState,
+    pub redemptions: RedemptionsState,
     pub logs: LogsState,
     pub database: DatabaseState,
     pub settings: SettingsState,
     pub docs: DocsState,
 }
 
+/// Stored as user data on the shared file dialog so only the tab that opened it handles the picked path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDialogPurpose {
+    ExportChatLog,
+    ExportActions,
+    ImportActions,
+}
+
 #[derive(Clone)]
 pub enum AppStateDiff {
     InternetConnected,
@@ -65,25 +97,90 @@ pub enum AppStateDiff {
     ChannelInfoUpdated(ChannelInformation),
 
     SetSettingsChannelError(String),
+
+    CheckStreamStatus,
+    StreamStatusUpdated(bool),
+
+    UserProfileLoaded {
+        user_id: String,
+        created_at: Option<String>,
+        followed_at: Option<String>,
+    },
+
+    RedemptionQueueLoaded {
+        rewards: Vec<ChannelReward>,
+        redemptions: Vec<PendingRedemption>,
+    },
+    RedemptionResolved(String),
+    RedemptionActionFinished {
+        redemption: PendingRedemption,
+        success: bool,
+    },
+
+    RunActionCommands(Vec<ActionCommand>),
+    ActionDisabled(i32),
+    ActionTestFinished {
+        generation: u64,
+        result: ActionTestResult,
+    },
+
+    AssetLoaded {
+        key: AssetKey,
+        bytes: Arc<[u8]>,
+    },
+    /// `None` for the global badges.
+    BadgeTitlesLoaded {
+        channel_id: Option<String>,
+        titles: BadgeMap,
+    },
+    /// `None` for the global emotes.
+    ThirdPartyEmotesLoaded {
+        provider: EmoteProvider,
+        channel_id: Option<String>,
+        emotes: Vec<ThirdPartyEmote>,
+    },
+
+    /// `generation` is the search the results belong to.
+    SearchResultsLoaded {
+        generation: u64,
+        hits: Result<Vec<SearchHit>, String>,
+    },
+    SearchContextLoaded {
+        generation: u64,
+        context: SearchContext,
+    },
+    /// Ids the history worker saved live events with by their channel, in the order the events arrived.
+    ChatEventsSaved(Vec<(String, i32)>),
+    /// The history worker saved what it held before switching to `channel`, its history ends at `newest_id`.
+    ChatHistoryFlushed {
+        channel: String,
+        newest_id: Option<i32>,
+    },
 }
 
 impl AppState {
     pub fn new(db_pool: SqlitePool, channels: MPSCChannels, toasts: Toasts) -> Result<Self> {
+        let redemptions = RedemptionsState::new(&db_pool);
+        let assets = AssetStore::new(channels.asset_worker_message_tx.clone());
+
         return Ok(Self {
             connected_to_internet: true,
+            is_live: false,
 
             // global
             db_pool,
             zoom_factor: 1.0,
             file_dialog: FileDialog::new(),
             toasts,
+            assets,
 
             // twitch worker
             twitch_irc_worker_handle: None,
-            twitch_pubsub_worker_handle: None,
+            twitch_eventsub_worker_handle: None,
             channels,
 
             // twitch
+            pending_join: None,
             did_we_try_to_join: false,
             when_did_we_try_to_join: None,
             did_we_join: false,
@@ -93,8 +190,10 @@ impl AppState {
 
             // tabs
             chat: ChatState::default(),
+            search: SearchState::default(),
             stats: StatsState::default(),
             actions: ActionsState::default(),
+            redemptions,
             logs: LogsState::default(),
             database: DatabaseState::default(),
             settings: SettingsState::default(),
@@ -103,7 +202,7 @@ impl AppState {
     }
 
     pub fn show_notice(&mut self, message: String) {
-        self.chat.events.items.push(TwitchEvent::Notice(NoticeMessage {
+        let notice = TwitchEvent::Notice(NoticeMessage {
             channel_login: None,
             message_id: None,
             message_text: message,
@@ -113,7 +212,9 @@ impl AppState {
                 command: String::from("NOTICE"),
                 params: Vec::new(),
             },
-        }));
+        });
+
+        self.chat.push_event(&self.db_pool, notice);
     }
 
     pub fn start_twitch_irc_worker(&mut self) {
@@ -124,21 +225,26 @@ impl AppState {
         // stop existing worker
         if let Some(handle) = &self.twitch_irc_worker_handle {
             handle.abort();
-            self.chat.events.items.clear();
         }
 
+        self.twitch_irc_worker_handle = None;
+
+        self.chat
+            .load_history(&self.channels.history_worker_message_tx, &self.settings.channel_name);
+        self.pending_join = Some(self.settings.channel_name.clone());
+    }
+
+    /// Called once the chat history of the channel is loaded, so it ends where the live events begin.
+    pub fn join_channel(&mut self, channel: String) {
         // log attempt
         self.did_we_try_to_join = true;
         self.when_did_we_try_to_join = Some(std::time::Instant::now());
 
         // start new worker
-        match worker_start_twitch_irc(
-            self.settings.channel_name.clone(),
-            self.channels.twitch_event_txs.clone(),
-        ) {
+        match worker_start_twitch_irc(channel.clone(), self.channels.twitch_event_txs.clone()) {
             Ok(handle) => {
                 self.twitch_irc_worker_handle = Some(handle);
-                self.connected_channel_name = Some(self.settings.channel_name.clone());
+                self.connected_channel_name = Some(channel);
 
                 if self.twitch_account.is_some() {
                     unsafe {
@@ -163,22 +269,53 @@ impl AppState {
             self.show_notice(format!("Left channel {}.", self.settings.channel_name));
         }
 
+        self.pending_join = None;
         self.did_we_try_to_join = false;
         self.when_did_we_try_to_join = None;
         self.did_we_join = false;
 
         self.twitch_irc_worker_handle = None;
         self.connected_channel_name = None;
+
+        self.stop_twitch_eventsub_worker();
     }
 
-    pub fn start_twitch_pubsub_worker(&mut self) {}
+    /// Needs a linked account and the info of the joined channel, restarts the worker if it already runs.
+    pub fn start_twitch_eventsub_worker(&mut self) {
+        self.stop_twitch_eventsub_worker();
 
-    pub fn stop_twitch_pubsub_worker(&mut self) {
-        if let Some(handle) = &self.twitch_pubsub_worker_handle {
+        let (Some(account), Some(channel)) = (&self.twitch_account, &self.connected_channel_info) else {
+            return;
+        };
+
+        self.twitch_eventsub_worker_handle = Some(worker_start_twitch_eventsub(
+            account.clone(),
+            channel.broadcaster_id.clone(),
+            self.channels.twitch_event_txs.clone(),
+        ));
+    }
+
+    pub fn stop_twitch_eventsub_worker(&mut self) {
+        if let Some(handle) = &self.twitch_eventsub_worker_handle {
             handle.abort();
         }
 
-        self.twitch_pubsub_worker_handle = None;
+        self.twitch_eventsub_worker_handle = None;
+    }
+
+    /// Loads the emotes of the enabled providers for the connected channel.
+    pub fn load_third_party_emotes(&self) {
+        let Some(channel) = &self.connected_channel_info else {
+            return;
+        };
+
+        self.channels
+            .asset_worker_message_tx
+            .send(AssetWorkerMessage::LoadThirdPartyEmotes {
+                channel_id: channel.broadcaster_id.to_string(),
+                providers: self.chat.third_party_emotes.enabled.iter().copied().collect(),
+            })
+            .unwrap();
     }
 
     pub fn link_twitch_account(&mut self) {
@@ -186,6 +323,7 @@ impl AppState {
     }
 
     pub fn unlink_twitch_account(&mut self) {
+        self.stop_twitch_eventsub_worker();
         self.twitch_account = None;
     }
 }
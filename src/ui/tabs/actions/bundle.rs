@@ -0,0 +1,157 @@
+use eframe::egui::{Button, Color32, ComboBox, RichText, ScrollArea, Ui};
+use egui_toast::ToastKind;
+use strum::IntoEnumIterator;
+
+use crate::{
+    app::App,
+    engine::bundle::{ActionBundle, ConflictStrategy},
+    ui::{
+        state::{AppState, FileDialogPurpose},
+        tabs::actions::{PendingImport, notify_actions_changed},
+    },
+};
+
+pub fn render_export_controls(ui: &mut Ui, state: &mut AppState) {
+    ui.checkbox(&mut state.actions.export_include_store, "Include storage")
+        .on_hover_text("Also export the values the actions saved in their store.");
+
+    ui.horizontal(|ui| {
+        let count = state.actions.export_selection.len();
+        if ui
+            .add_enabled(count > 0, Button::new(format!("Export {count}")))
+            .clicked()
+        {
+            state.file_dialog.set_user_data(FileDialogPurpose::ExportActions);
+            state.file_dialog.save_file();
+        }
+
+        if ui.button("Cancel").clicked() {
+            state.actions.export_mode = false;
+            state.actions.export_selection.clear();
+        }
+    });
+}
+
+pub fn render_pending_import(ui: &mut Ui, state: &mut AppState) {
+    let Some(pending) = &mut state.actions.pending_import else {
+        return;
+    };
+
+    let mut import_clicked = false;
+    let mut cancel_clicked = false;
+
+    ui.label(RichText::new("Import Actions").strong());
+    ui.separator();
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for bundled in &pending.bundle.actions {
+            let is_conflict = pending.conflicts.contains(&bundled.name);
+
+            ui.horizontal(|ui| {
+                ui.label(&bundled.name);
+
+                if bundled.store.is_some() {
+                    ui.label(RichText::new("with storage").color(Color32::GRAY));
+                }
+
+                if is_conflict {
+                    ui.label(RichText::new("already exists").color(Color32::YELLOW));
+                }
+            });
+        }
+
+        ui.separator();
+
+        if !pending.conflicts.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Existing actions:");
+                ComboBox::from_id_salt("import_conflict_strategy")
+                    .selected_text(pending.strategy.to_string())
+                    .show_ui(ui, |ui| {
+                        for strategy in ConflictStrategy::iter() {
+                            ui.selectable_value(&mut pending.strategy, strategy, strategy.to_string());
+                        }
+                    });
+            });
+        }
+
+        ui.horizontal(|ui| {
+            import_clicked = ui.button("Import").clicked();
+            cancel_clicked = ui.button("Cancel").clicked();
+        });
+    });
+
+    if import_clicked {
+        let pending = state.actions.pending_import.take().unwrap();
+
+        match pending.bundle.import(&state.db_pool, pending.strategy) {
+            Ok(summary) => App::show_toast(
+                &state.channels.ui_diff_tx,
+                ToastKind::Success,
+                &format!(
+                    "Imported {} actions, overwrote {}, renamed {}, skipped {}.",
+                    summary.imported, summary.overwritten, summary.renamed, summary.skipped
+                ),
+            ),
+            Err(err) => App::show_toast(
+                &state.channels.ui_diff_tx,
+                ToastKind::Error,
+                &format!("Failed to import actions: {err}"),
+            ),
+        }
+
+        state.actions.editor = None;
+        notify_actions_changed(state);
+    } else if cancel_clicked {
+        state.actions.pending_import = None;
+    }
+}
+
+pub fn handle_file_dialog(state: &mut AppState) {
+    let purpose = match state.file_dialog.user_data::<FileDialogPurpose>() {
+        Some(purpose @ (FileDialogPurpose::ExportActions | FileDialogPurpose::ImportActions)) => *purpose,
+        _ => return,
+    };
+
+    let Some(path) = state.file_dialog.take_picked() else {
+        return;
+    };
+
+    if purpose == FileDialogPurpose::ExportActions {
+        let ids = state.actions.export_selection.iter().copied().collect::<Vec<_>>();
+        let result = ActionBundle::export(&state.db_pool, &ids, state.actions.export_include_store)
+            .and_then(|bundle| bundle.write(&path).map(|()| bundle.actions.len()));
+
+        match result {
+            Ok(count) => {
+                App::show_toast(
+                    &state.channels.ui_diff_tx,
+                    ToastKind::Success,
+                    &format!("Exported {count} actions."),
+                );
+                state.actions.export_mode = false;
+                state.actions.export_selection.clear();
+            }
+            Err(err) => App::show_toast(
+                &state.channels.ui_diff_tx,
+                ToastKind::Error,
+                &format!("Failed to export actions: {err}"),
+            ),
+        }
+    } else {
+        match ActionBundle::read(&path) {
+            Ok(bundle) => {
+                state.actions.pending_import = Some(PendingImport {
+                    conflicts: bundle.conflicts(&state.actions.actions),
+                    bundle,
+                    strategy: ConflictStrategy::default(),
+                });
+            }
+            Err(err) => App::show_toast(
+                &state.channels.ui_diff_tx,
+                ToastKind::Error,
+                &format!("Failed to read action bundle: {err}"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use eframe::egui::{
+    self, CollapsingHeader, Color32, ComboBox, DragValue, Grid, RichText, ScrollArea, TextEdit, TextStyle, Ui,
+};
+use regex::Regex;
+use strum::IntoEnumIterator;
+use tracing::warn;
+
+use crate::{
+    engine::{
+        guard::{ActionGuards, UserRole},
+        test_action,
+        trigger::{EventKind, Trigger, parse_cron},
+    },
+    models::action::{Action, ActionLimits},
+    twitch::{
+        message::ChatMessage,
+        types::{TwitchEvent, synthetic_privmsg},
+    },
+    ui::{
+        state::{AppState, AppStateDiff},
+        tabs::actions::{highlight::highlight_rhai, notify_actions_changed, save_action},
+    },
+};
+
+pub fn render_action_editor(ui: &mut Ui, state: &mut AppState) {
+    let Some(editor) = &mut state.actions.editor else {
+        ui.centered_and_justified(|ui| {
+            ui.label("Select an action or create a new one.");
+        });
+        return;
+    };
+
+    let mut save_clicked = false;
+    let mut delete_clicked = false;
+    let mut test_clicked = false;
+    let mut reset_failures_clicked = false;
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.add(TextEdit::singleline(&mut editor.name).char_limit(100));
+            ui.checkbox(&mut editor.config.enabled, "Enabled");
+        });
+
+        ui.separator();
+
+        ui.label(RichText::new("Triggers").strong());
+        render_triggers(ui, &mut editor.config.triggers);
+
+        ui.separator();
+
+        ui.label(RichText::new("Permissions & Cooldowns").strong());
+        render_guards(ui, &mut editor.config.guards);
+
+        ui.separator();
+
+        CollapsingHeader::new(RichText::new("Limits").strong())
+            .id_salt("action_limits")
+            .show(ui, |ui| {
+                render_limits(ui, &mut editor.config.limits);
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("Limit violations: {}", editor.failure_count));
+
+                    if editor.id.is_some() && editor.failure_count > 0 {
+                        reset_failures_clicked = ui.button("Reset").clicked();
+                    }
+                });
+            });
+
+        ui.separator();
+
+        ui.label(RichText::new("Script").strong());
+
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let mut layouter = |ui: &Ui, buffer: &dyn egui::TextBuffer, wrap_width: f32| {
+            let mut job = highlight_rhai(buffer.as_str(), font_id.clone());
+            job.wrap.max_width = wrap_width;
+            ui.fonts_mut(|fonts| fonts.layout_job(job))
+        };
+
+        ui.add(
+            TextEdit::multiline(&mut editor.script)
+                .code_editor()
+                .desired_rows(16)
+                .desired_width(f32::INFINITY)
+                .layouter(&mut layouter),
+        );
+
+        if let Some(error) = &editor.error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+
+        ui.horizontal(|ui| {
+            save_clicked = ui.button("Save").clicked();
+
+            if editor.id.is_some() {
+                delete_clicked = ui.button("Delete").clicked();
+            }
+        });
+
+        ui.separator();
+
+        ui.label(RichText::new("Test").strong());
+
+        ui.horizontal(|ui| {
+            ui.label("Sender:");
+            ui.add(
+                TextEdit::singleline(&mut state.actions.test_sender)
+                    .char_limit(25)
+                    .desired_width(120.0),
+            );
+            ui.label("Message:");
+            ui.add(TextEdit::singleline(&mut state.actions.test_message).char_limit(500));
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.actions.test_as_broadcaster, "Broadcaster");
+            ui.checkbox(&mut state.actions.test_as_moderator, "Moderator");
+            ui.checkbox(&mut state.actions.test_as_vip, "VIP");
+            ui.checkbox(&mut state.actions.test_as_subscriber, "Subscriber");
+        });
+
+        ui.horizontal(|ui| {
+            test_clicked = ui
+                .add_enabled(!state.actions.is_testing, egui::Button::new("Test"))
+                .on_hover_text("Runs the script against a fake chat message, nothing is sent to twitch.")
+                .clicked();
+
+            if state.actions.is_testing {
+                ui.spinner();
+                ui.label("Running...");
+            }
+        });
+
+        if let Some(result) = &state.actions.test_result {
+            if !result.triggered {
+                ui.label(RichText::new("No trigger matched, the script ran anyway.").color(Color32::YELLOW));
+            }
+
+            if result.missing_role {
+                ui.label(
+                    RichText::new("The sender doesn't have the required role, the script ran anyway.")
+                        .color(Color32::YELLOW),
+                );
+            }
+
+            for line in &result.run.output {
+                ui.label(RichText::new(line).monospace());
+            }
+
+            for command in &result.run.commands {
+                ui.label(RichText::new(format!("Would run: {command}")).color(Color32::LIGHT_BLUE));
+            }
+
+            match &result.run.error {
+                Some(error) => {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                }
+                None => {
+                    ui.label(RichText::new("Finished without errors.").color(Color32::GREEN));
+                }
+            }
+        }
+    });
+
+    if save_clicked {
+        save_action(state);
+    } else if delete_clicked {
+        if let Some(id) = state.actions.editor.as_ref().and_then(|editor| editor.id) {
+            if let Err(err) = Action::delete(&state.db_pool, id) {
+                warn!("Failed to delete action: {}", err);
+            }
+
+            state.actions.editor = None;
+            state.actions.clear_test();
+            notify_actions_changed(state);
+        }
+    } else if test_clicked {
+        run_test(state);
+    } else if reset_failures_clicked
+        && let Some(editor) = &mut state.actions.editor
+        && let Some(id) = editor.id
+    {
+        match Action::reset_failures(&state.db_pool, id) {
+            Ok(()) => editor.failure_count = 0,
+            Err(err) => warn!("Failed to reset action failures: {}", err),
+        }
+
+        state.actions.needs_refresh = true;
+    }
+}
+
+fn render_guards(ui: &mut Ui, guards: &mut ActionGuards) {
+    Grid::new("action_guards_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Minimum role:");
+        ComboBox::from_id_salt("action_min_role")
+            .selected_text(guards.min_role.to_string())
+            .show_ui(ui, |ui| {
+                for role in UserRole::iter() {
+                    ui.selectable_value(&mut guards.min_role, role, role.to_string());
+                }
+            });
+        ui.end_row();
+
+        ui.label("Global cooldown:");
+        ui.add(
+            DragValue::new(&mut guards.global_cooldown_secs)
+                .range(0..=86400)
+                .suffix(" s"),
+        );
+        ui.end_row();
+
+        ui.label("Per-user cooldown:");
+        ui.add(
+            DragValue::new(&mut guards.user_cooldown_secs)
+                .range(0..=86400)
+                .suffix(" s"),
+        );
+        ui.end_row();
+
+        ui.label("Cooldown reply:")
+            .on_hover_text("Sent once per user while on cooldown, {remaining} is replaced with the seconds left.");
+        ui.add(
+            TextEdit::singleline(&mut guards.cooldown_reply)
+                .hint_text("Wait {remaining}s before using this again.")
+                .char_limit(500),
+        );
+        ui.end_row();
+    });
+}
+
+fn render_limits(ui: &mut Ui, limits: &mut ActionLimits) {
+    Grid::new("action_limits_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Max operations:");
+        ui.add(
+            DragValue::new(&mut limits.max_operations)
+                .range(1_000..=10_000_000)
+                .speed(1_000),
+        );
+        ui.end_row();
+
+        ui.label("Max call depth:");
+        ui.add(DragValue::new(&mut limits.max_call_depth).range(1..=256));
+        ui.end_row();
+
+        ui.label("Max string size:");
+        ui.add(
+            DragValue::new(&mut limits.max_string_size)
+                .range(100..=1_000_000)
+                .speed(100),
+        );
+        ui.end_row();
+
+        ui.label("Max array/map size:");
+        ui.add(DragValue::new(&mut limits.max_array_size).range(10..=100_000).speed(10));
+        ui.end_row();
+
+        ui.label("Timeout:");
+        ui.add(
+            DragValue::new(&mut limits.timeout_ms)
+                .range(10..=60_000)
+                .speed(10)
+                .suffix(" ms"),
+        );
+        ui.end_row();
+
+        ui.label("Disable after:")
+            .on_hover_text("The action gets disabled after this many limit violations.");
+        ui.add(
+            DragValue::new(&mut limits.max_failures)
+                .range(1..=100)
+                .suffix(" violations"),
+        );
+        ui.end_row();
+    });
+}
+
+fn render_triggers(ui: &mut Ui, triggers: &mut Vec<Trigger>) {
+    let mut removed = None;
+
+    for (index, trigger) in triggers.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt(("trigger_type", index))
+                .selected_text(trigger_label(trigger))
+                .show_ui(ui, |ui| {
+                    for option in default_triggers() {
+                        let label = trigger_label(&option);
+                        if ui.selectable_label(trigger_label(trigger) == label, label).clicked() {
+                            *trigger = option;
+                        }
+                    }
+                });
+
+            match trigger {
+                Trigger::Command { name } => {
+                    ui.label("!");
+                    ui.add(TextEdit::singleline(name).hint_text("command").desired_width(150.0));
+                }
+                Trigger::Regex { pattern } => {
+                    let is_valid = Regex::new(pattern).is_ok();
+                    let mut input = TextEdit::singleline(pattern).hint_text("pattern").desired_width(250.0);
+                    if !is_valid {
+                        input = input.text_color(Color32::RED);
+                    }
+                    ui.add(input);
+                }
+                Trigger::Event { kind } => {
+                    ComboBox::from_id_salt(("trigger_event", index))
+                        .selected_text(kind.to_string())
+                        .show_ui(ui, |ui| {
+                            for option in EventKind::iter() {
+                                ui.selectable_value(kind, option, option.to_string());
+                            }
+                        });
+                }
+                Trigger::Interval { seconds } => {
+                    ui.label("every");
+                    ui.add(DragValue::new(seconds).range(1..=86400).suffix(" s"));
+                }
+                Trigger::Cron { expression } => {
+                    let is_valid = parse_cron(expression).is_ok();
+                    let mut input = TextEdit::singleline(expression)
+                        .hint_text("*/15 * * * *")
+                        .desired_width(200.0);
+                    if !is_valid {
+                        input = input.text_color(Color32::RED);
+                    }
+                    ui.add(input);
+                }
+            }
+
+            if ui.button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+
+    if let Some(index) = removed {
+        triggers.remove(index);
+    }
+
+    if ui.button("Add Trigger").clicked() {
+        triggers.push(Trigger::Command { name: String::new() });
+    }
+}
+
+fn default_triggers() -> [Trigger; 5] {
+    return [
+        Trigger::Command { name: String::new() },
+        Trigger::Regex { pattern: String::new() },
+        Trigger::Event {
+            kind: EventKind::Message,
+        },
+        Trigger::Interval { seconds: 300 },
+        Trigger::Cron {
+            expression: String::from("*/15 * * * *"),
+        },
+    ];
+}
+
+fn trigger_label(trigger: &Trigger) -> &'static str {
+    return match trigger {
+        Trigger::Command { .. } => "Command",
+        Trigger::Regex { .. } => "Regex",
+        Trigger::Event { .. } => "Event",
+        Trigger::Interval { .. } => "Interval",
+        Trigger::Cron { .. } => "Cron",
+    };
+}
+
+fn run_test(state: &mut AppState) {
+    let Some(editor) = &mut state.actions.editor else {
+        return;
+    };
+
+    let mut badges = Vec::new();
+    if state.actions.test_as_broadcaster {
+        badges.push("broadcaster");
+    }
+    if state.actions.test_as_moderator {
+        badges.push("moderator");
+    }
+    if state.actions.test_as_vip {
+        badges.push("vip");
+    }
+    if state.actions.test_as_subscriber {
+        badges.push("subscriber");
+    }
+
+    let channel = state
+        .connected_channel_name
+        .clone()
+        .unwrap_or_else(|| String::from("ruey"));
+    let sender = if state.actions.test_sender.trim().is_empty() {
+        "ruey_tester"
+    } else {
+        state.actions.test_sender.trim()
+    };
+
+    let message = match synthetic_privmsg(&channel, sender, &state.actions.test_message, &badges) {
+        Ok(message) => message,
+        Err(err) => {
+            editor.error = Some(format!("Failed to test action: {err}"));
+            return;
+        }
+    };
+    editor.error = None;
+
+    let event = TwitchEvent::Privmsg(ChatMessage::from(message));
+    let action = editor.to_action();
+
+    state.actions.clear_test();
+    state.actions.is_testing = true;
+
+    // scripts may run for as long as their timeout allows
+    let generation = state.actions.test_generation;
+    let db_pool = state.db_pool.clone();
+    let diff_tx = state.channels.ui_diff_tx.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let result = test_action(&db_pool, action, &event);
+
+        diff_tx
+            .send(AppStateDiff::ActionTestFinished { generation, result })
+            .unwrap();
+    });
+}
@@ -0,0 +1,77 @@
+use eframe::egui::{
+    Color32, FontId, TextFormat,
+    text::{LayoutJob, LayoutSection},
+};
+
+const KEYWORDS: &[&str] = &[
+    "let", "const", "if", "else", "switch", "do", "while", "until", "loop", "for", "in", "continue", "break", "return",
+    "throw", "try", "catch", "fn", "private", "import", "export", "as", "this", "global", "true", "false",
+];
+
+const COLOR_DEFAULT: Color32 = Color32::from_rgb(220, 220, 220);
+const COLOR_KEYWORD: Color32 = Color32::from_rgb(198, 120, 221);
+const COLOR_STRING: Color32 = Color32::from_rgb(152, 195, 121);
+const COLOR_NUMBER: Color32 = Color32::from_rgb(209, 154, 102);
+const COLOR_COMMENT: Color32 = Color32::from_rgb(110, 115, 125);
+const COLOR_FUNCTION: Color32 = Color32::from_rgb(97, 175, 239);
+
+pub fn highlight_rhai(code: &str, font_id: FontId) -> LayoutJob {
+    let mut job = LayoutJob {
+        text: code.to_owned(),
+        ..LayoutJob::default()
+    };
+
+    let bytes = code.as_bytes();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let start = index;
+        let byte = bytes[index];
+
+        let color = if code[index..].starts_with("//") {
+            index = code[index..].find('\n').map_or(bytes.len(), |end| index + end);
+            COLOR_COMMENT
+        } else if code[index..].starts_with("/*") {
+            index = code[index + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| index + 2 + end + 2);
+            COLOR_COMMENT
+        } else if byte == b'"' || byte == b'`' || byte == b'\'' {
+            index += 1;
+            while index < bytes.len() && bytes[index] != byte {
+                index += if bytes[index] == b'\\' { 2 } else { 1 };
+            }
+            index = (index + 1).min(bytes.len());
+            COLOR_STRING
+        } else if byte.is_ascii_digit() {
+            while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'.') {
+                index += 1;
+            }
+            COLOR_NUMBER
+        } else if byte.is_ascii_alphabetic() || byte == b'_' {
+            while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_') {
+                index += 1;
+            }
+
+            if KEYWORDS.contains(&&code[start..index]) {
+                COLOR_KEYWORD
+            } else if code[index..].trim_start().starts_with('(') {
+                COLOR_FUNCTION
+            } else {
+                COLOR_DEFAULT
+            }
+        } else {
+            // advance by a whole char so we never split utf-8 sequences
+            index += code[index..].chars().next().map_or(1, char::len_utf8);
+            COLOR_DEFAULT
+        };
+
+        job.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range: start..index,
+            format: TextFormat::simple(font_id.clone(), color),
+        });
+    }
+
+    return job;
+}
@@ -0,0 +1,227 @@
+use eframe::egui::{self, Color32, RichText, ScrollArea, SidePanel, TextEdit, TextStyle, Ui};
+use rhai::Engine;
+use tracing::warn;
+
+use crate::{
+    engine::check_library,
+    models::script_module::ScriptModule,
+    ui::{state::AppState, tabs::actions::highlight::highlight_rhai},
+    workers::action::ActionWorkerMessage,
+};
+
+pub struct LibrariesState {
+    pub modules: Vec<ScriptModule>,
+    pub needs_refresh: bool,
+    pub editor: Option<ModuleEditor>,
+}
+
+pub struct ModuleEditor {
+    pub id: Option<i32>,
+    /// Name the module was loaded with, to notify dependents of the old name after a rename.
+    pub saved_name: Option<String>,
+    pub name: String,
+    pub source: String,
+    pub error: Option<String>,
+}
+
+impl Default for LibrariesState {
+    fn default() -> Self {
+        return Self {
+            modules: Vec::new(),
+            needs_refresh: true,
+            editor: None,
+        };
+    }
+}
+
+pub fn render_libraries(ui: &mut Ui, state: &mut AppState) {
+    if state.actions.libraries.needs_refresh {
+        state.actions.libraries.needs_refresh = false;
+
+        match ScriptModule::load_all(&state.db_pool) {
+            Ok(modules) => state.actions.libraries.modules = modules,
+            Err(err) => warn!("Failed to load script modules: {}", err),
+        }
+    }
+
+    SidePanel::left("libraries_list")
+        .resizable(true)
+        .default_width(160.0)
+        .show_inside(ui, |ui| {
+            render_library_list(ui, state);
+        });
+
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        render_library_editor(ui, state);
+    });
+}
+
+fn render_library_list(ui: &mut Ui, state: &mut AppState) {
+    let libraries = &mut state.actions.libraries;
+
+    if ui.button("New Library").clicked() {
+        libraries.editor = Some(ModuleEditor {
+            id: None,
+            saved_name: None,
+            name: String::from("utils"),
+            source: String::from("fn greet(name) {\n    `Hello ${name}!`\n}\n"),
+            error: None,
+        });
+    }
+
+    ui.separator();
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        if libraries.modules.is_empty() {
+            ui.label("No libraries yet.");
+        }
+
+        for module in &libraries.modules {
+            let is_selected = libraries
+                .editor
+                .as_ref()
+                .is_some_and(|editor| editor.id == Some(module.id));
+
+            if ui.selectable_label(is_selected, &module.name).clicked() {
+                libraries.editor = Some(ModuleEditor {
+                    id: Some(module.id),
+                    saved_name: Some(module.name.clone()),
+                    name: module.name.clone(),
+                    source: module.source_text().unwrap_or_default(),
+                    error: None,
+                });
+            }
+        }
+    });
+}
+
+fn render_library_editor(ui: &mut Ui, state: &mut AppState) {
+    let Some(editor) = &mut state.actions.libraries.editor else {
+        ui.centered_and_justified(|ui| {
+            ui.label("Select a library or create a new one.");
+        });
+        return;
+    };
+
+    let mut save_clicked = false;
+    let mut delete_clicked = false;
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.add(TextEdit::singleline(&mut editor.name).char_limit(100));
+        });
+
+        ui.label(
+            RichText::new(format!(
+                "Use it from an action with: import \"{}\" as {};",
+                editor.name,
+                editor.name.rsplit('/').next().unwrap_or_default()
+            ))
+            .monospace()
+            .color(Color32::GRAY),
+        );
+
+        ui.separator();
+
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let mut layouter = |ui: &Ui, buffer: &dyn egui::TextBuffer, wrap_width: f32| {
+            let mut job = highlight_rhai(buffer.as_str(), font_id.clone());
+            job.wrap.max_width = wrap_width;
+            ui.fonts_mut(|fonts| fonts.layout_job(job))
+        };
+
+        ui.add(
+            TextEdit::multiline(&mut editor.source)
+                .code_editor()
+                .desired_rows(20)
+                .desired_width(f32::INFINITY)
+                .layouter(&mut layouter),
+        );
+
+        if let Some(error) = &editor.error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+
+        ui.horizontal(|ui| {
+            save_clicked = ui.button("Save").clicked();
+
+            if editor.id.is_some() {
+                delete_clicked = ui.button("Delete").clicked();
+            }
+        });
+    });
+
+    if save_clicked {
+        save_library(state);
+    } else if delete_clicked
+        && let Some(editor) = state.actions.libraries.editor.take()
+        && let Some(id) = editor.id
+    {
+        if let Err(err) = ScriptModule::delete(&state.db_pool, id) {
+            warn!("Failed to delete script module: {}", err);
+        }
+
+        notify_module_changed(state, editor.saved_name.unwrap_or(editor.name));
+    }
+}
+
+fn save_library(state: &mut AppState) {
+    let Some(editor) = &mut state.actions.libraries.editor else {
+        return;
+    };
+
+    let name = editor.name.trim().to_owned();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '/'))
+    {
+        editor.error = Some(String::from(
+            "Library names may only contain letters, digits, '_' and '/'.",
+        ));
+        return;
+    }
+
+    if let Err(err) = Engine::new().compile(&editor.source) {
+        editor.error = Some(format!("Syntax error: {err}"));
+        return;
+    }
+
+    if let Err(err) = check_library(&state.db_pool, &name, &editor.source) {
+        editor.error = Some(err.to_string());
+        return;
+    }
+
+    let result = match editor.id {
+        Some(id) => ScriptModule::update(&state.db_pool, id, &name, &editor.source),
+        None => ScriptModule::insert(&state.db_pool, &name, &editor.source).map(|id| {
+            editor.id = Some(id);
+        }),
+    };
+
+    if let Err(err) = result {
+        editor.error = Some(format!("Failed to save library: {err}"));
+        return;
+    }
+
+    editor.error = None;
+    editor.name = name.clone();
+    let previous_name = editor.saved_name.replace(name.clone());
+
+    if let Some(previous_name) = previous_name
+        && previous_name != name
+    {
+        notify_module_changed(state, previous_name);
+    }
+    notify_module_changed(state, name);
+}
+
+fn notify_module_changed(state: &mut AppState, name: String) {
+    state.actions.libraries.needs_refresh = true;
+    state
+        .channels
+        .action_worker_message_tx
+        .send(ActionWorkerMessage::ModuleChanged(name))
+        .unwrap();
+}
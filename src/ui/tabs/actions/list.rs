@@ -0,0 +1,122 @@
+use eframe::egui::{Color32, RichText, ScrollArea, Ui};
+use tracing::warn;
+
+use crate::{
+    models::action::{Action, ActionConfig},
+    ui::{
+        state::{AppState, FileDialogPurpose},
+        tabs::actions::{ActionEditor, bundle::render_export_controls, notify_actions_changed},
+    },
+};
+
+pub fn render_action_list(ui: &mut Ui, state: &mut AppState) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Actions").strong());
+
+        if ui.button("New").clicked() {
+            state.actions.editor = Some(ActionEditor {
+                id: None,
+                name: String::from("New Action"),
+                script: String::from("reply(`Hello ${event.user_name}!`);\n"),
+                config: ActionConfig::default(),
+                failure_count: 0,
+                error: None,
+            });
+            state.actions.clear_test();
+        }
+
+        if ui.button("Import").clicked() {
+            state.file_dialog.set_user_data(FileDialogPurpose::ImportActions);
+            state.file_dialog.pick_file();
+        }
+
+        if ui.button("Export").clicked() {
+            state.actions.export_mode ^= true;
+            state.actions.export_selection.clear();
+        }
+    });
+
+    if state.actions.export_mode {
+        render_export_controls(ui, state);
+    }
+
+    ui.separator();
+
+    let mut toggled = None;
+    let mut selected = None;
+    let mut export_toggled = None;
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        if state.actions.actions.is_empty() {
+            ui.label("No actions yet.");
+        }
+
+        for action in &state.actions.actions {
+            let mut enabled = action.parsed_config().map(|config| config.enabled).unwrap_or(false);
+            let is_selected = state
+                .actions
+                .editor
+                .as_ref()
+                .is_some_and(|editor| editor.id == Some(action.id));
+
+            ui.horizontal(|ui| {
+                if state.actions.export_mode {
+                    let mut is_exported = state.actions.export_selection.contains(&action.id);
+                    if ui.checkbox(&mut is_exported, "").on_hover_text("Export").changed() {
+                        export_toggled = Some((action.id, is_exported));
+                    }
+                } else if ui.checkbox(&mut enabled, "").on_hover_text("Enabled").changed() {
+                    toggled = Some((action.clone(), enabled));
+                }
+
+                let name = if enabled {
+                    RichText::new(&action.name)
+                } else {
+                    RichText::new(&action.name).color(Color32::GRAY)
+                };
+
+                if ui.selectable_label(is_selected, name).clicked() {
+                    selected = Some(action.clone());
+                }
+            });
+        }
+    });
+
+    if let Some((action, enabled)) = toggled {
+        let mut config = action.parsed_config().unwrap_or_default();
+        config.enabled = enabled;
+
+        let script = action.script_source().unwrap_or_default();
+        if let Err(err) = Action::update(&state.db_pool, action.id, &action.name, &script, &config) {
+            warn!("Failed to toggle action: {}", err);
+        }
+
+        if enabled && let Err(err) = Action::reset_failures(&state.db_pool, action.id) {
+            warn!("Failed to reset action failures: {}", err);
+        }
+
+        if let Some(editor) = &mut state.actions.editor
+            && editor.id == Some(action.id)
+        {
+            editor.config.enabled = enabled;
+            if enabled {
+                editor.failure_count = 0;
+            }
+        }
+
+        notify_actions_changed(state);
+    }
+
+    if let Some((id, is_exported)) = export_toggled {
+        if is_exported {
+            state.actions.export_selection.insert(id);
+        } else {
+            state.actions.export_selection.remove(&id);
+        }
+    }
+
+    if let Some(action) = selected {
+        state.actions.editor = Some(ActionEditor::from_action(&action));
+        state.actions.clear_test();
+    }
+}
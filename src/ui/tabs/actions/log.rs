@@ -0,0 +1,163 @@
+use chrono::{Local, TimeZone};
+use eframe::egui::{Color32, ComboBox, RichText, ScrollArea, TextEdit, Ui};
+use strum::IntoEnumIterator;
+use tracing::warn;
+
+use crate::{
+    models::action_log::{ActionLogEntry, ActionRunResult},
+    ui::state::AppState,
+};
+
+const LOG_PAGE_SIZE: i64 = 100;
+
+pub fn render_action_log(ui: &mut Ui, state: &mut AppState) {
+    let log = &mut state.actions.log;
+    let previous_filter = log.filter.clone();
+
+    ui.horizontal(|ui| {
+        let selected_action = log
+            .filter
+            .action_id
+            .and_then(|id| state.actions.actions.iter().find(|action| action.id == id))
+            .map_or("All Actions", |action| action.name.as_str());
+
+        ComboBox::from_id_salt("action_log_action")
+            .selected_text(selected_action)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut log.filter.action_id, None, "All Actions");
+                for action in &state.actions.actions {
+                    ui.selectable_value(&mut log.filter.action_id, Some(action.id), &action.name);
+                }
+            });
+
+        ComboBox::from_id_salt("action_log_result")
+            .selected_text(
+                log.filter
+                    .result
+                    .map_or(String::from("All Results"), |result| result.to_string()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut log.filter.result, None, "All Results");
+                for result in ActionRunResult::iter() {
+                    ui.selectable_value(&mut log.filter.result, Some(result), result.to_string());
+                }
+            });
+
+        ui.add(
+            TextEdit::singleline(&mut log.filter.query)
+                .hint_text("Search events and errors")
+                .char_limit(100)
+                .desired_width(200.0),
+        );
+
+        if ui.button("Refresh").clicked() {
+            log.needs_refresh = true;
+        }
+
+        if ui.button("Clear Log").clicked() {
+            if let Err(err) = ActionLogEntry::clear(&state.db_pool) {
+                warn!("Failed to clear action log: {}", err);
+            }
+            log.needs_refresh = true;
+        }
+    });
+
+    if log.filter != previous_filter {
+        log.needs_refresh = true;
+    }
+
+    if log.needs_refresh {
+        log.needs_refresh = false;
+        log.entries.clear();
+        load_log_page(state);
+    }
+
+    ui.separator();
+
+    let mut load_more = false;
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        let log = &state.actions.log;
+
+        if log.entries.is_empty() {
+            ui.label("No runs recorded.");
+        }
+
+        for entry in &log.entries {
+            render_log_entry(ui, entry);
+        }
+
+        if log.has_more {
+            load_more = ui.button("Load More").clicked();
+        }
+    });
+
+    if load_more {
+        load_log_page(state);
+    }
+}
+
+fn render_log_entry(ui: &mut Ui, entry: &ActionLogEntry) {
+    let started_at = Local
+        .timestamp_millis_opt(entry.started_at)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+
+    let result = entry.run_result();
+    let result_color = match result {
+        Some(ActionRunResult::Success) => Color32::GREEN,
+        Some(ActionRunResult::Error) => Color32::RED,
+        Some(ActionRunResult::LimitExceeded) => Color32::ORANGE,
+        None => Color32::GRAY,
+    };
+
+    let header = RichText::new(format!(
+        "{started_at}  {}  {}  ({:.1} ms)",
+        entry.action_name,
+        entry.event_summary,
+        entry.duration_us as f64 / 1000.0
+    ));
+
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(&entry.result).color(result_color).monospace());
+        ui.collapsing(header, |ui| {
+            ui.label(format!("Action #{}: {}", entry.action_id, entry.action_name));
+            ui.label(format!("Event: {}", entry.event_summary));
+
+            if let Some(error) = &entry.error {
+                let position = match (entry.error_line, entry.error_column) {
+                    (Some(line), Some(column)) => format!(" (line {line}, column {column})"),
+                    (Some(line), None) => format!(" (line {line})"),
+                    _ => String::new(),
+                };
+                ui.label(RichText::new(format!("Error{position}: {error}")).color(Color32::RED));
+            }
+
+            let messages = entry.parsed_messages();
+            if messages.is_empty() {
+                ui.label(RichText::new("Sent nothing.").color(Color32::GRAY));
+            }
+            for message in messages {
+                ui.label(RichText::new(message).color(Color32::LIGHT_BLUE));
+            }
+        })
+        .header_response
+        .on_hover_text(&entry.event_summary);
+    });
+}
+
+fn load_log_page(state: &mut AppState) {
+    let log = &mut state.actions.log;
+
+    match ActionLogEntry::load_page(&state.db_pool, &log.filter, log.entries.len() as i64, LOG_PAGE_SIZE) {
+        Ok(entries) => {
+            log.has_more = entries.len() as i64 == LOG_PAGE_SIZE;
+            log.entries.extend(entries);
+        }
+        Err(err) => {
+            log.has_more = false;
+            warn!("Failed to load action log: {}", err);
+        }
+    }
+}
@@ -1,10 +1,276 @@
-use eframe::egui;
+mod bundle;
+mod editor;
+mod highlight;
+mod library;
+mod list;
+mod log;
+mod timers;
 
-use crate::ui::state::AppState;
+use std::collections::HashSet;
 
-#[derive(Default)]
-pub struct ActionsState {}
+use eframe::egui::{self, SidePanel};
+use tracing::warn;
 
-pub fn show_actions_ui(ui: &mut egui::Ui, _state: &mut AppState) {
-    ui.label("Coming Soon...");
+use crate::{
+    engine::{
+        ActionTestResult,
+        bundle::{ActionBundle, ConflictStrategy},
+    },
+    models::{
+        action::{Action, ActionConfig},
+        action_log::{ActionLogEntry, ActionLogFilter},
+    },
+    ui::{
+        state::AppState,
+        tabs::actions::{
+            bundle::{handle_file_dialog, render_pending_import},
+            editor::render_action_editor,
+            library::{LibrariesState, render_libraries},
+            list::render_action_list,
+            log::render_action_log,
+            timers::{TimersState, render_timers},
+        },
+    },
+    workers::action::ActionWorkerMessage,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ActionsView {
+    #[default]
+    Editor,
+    Log,
+    Libraries,
+    Timers,
+}
+
+pub struct ActionsState {
+    pub actions: Vec<Action>,
+    pub needs_refresh: bool,
+    pub view: ActionsView,
+
+    pub editor: Option<ActionEditor>,
+
+    pub test_message: String,
+    pub test_sender: String,
+    pub test_as_broadcaster: bool,
+    pub test_as_moderator: bool,
+    pub test_as_vip: bool,
+    pub test_as_subscriber: bool,
+    pub test_result: Option<ActionTestResult>,
+    pub is_testing: bool,
+    /// Bumped for every test run, results of older runs are dropped.
+    pub test_generation: u64,
+
+    pub export_mode: bool,
+    pub export_selection: HashSet<i32>,
+    pub export_include_store: bool,
+    pub pending_import: Option<PendingImport>,
+
+    pub log: ActionLogState,
+    pub libraries: LibrariesState,
+    pub timers: TimersState,
+}
+
+pub struct ActionLogState {
+    pub filter: ActionLogFilter,
+    pub entries: Vec<ActionLogEntry>,
+    pub needs_refresh: bool,
+    pub has_more: bool,
+}
+
+pub struct PendingImport {
+    pub bundle: ActionBundle,
+    pub conflicts: Vec<String>,
+    pub strategy: ConflictStrategy,
+}
+
+pub struct ActionEditor {
+    pub id: Option<i32>,
+    pub name: String,
+    pub script: String,
+    pub config: ActionConfig,
+    pub failure_count: i32,
+    pub error: Option<String>,
+}
+
+impl ActionEditor {
+    pub fn from_action(action: &Action) -> Self {
+        let (config, error) = match action.parsed_config() {
+            Ok(config) => (config, None),
+            Err(err) => (ActionConfig::default(), Some(format!("Failed to parse config: {err}"))),
+        };
+
+        return Self {
+            id: Some(action.id),
+            name: action.name.clone(),
+            script: action.script_source().unwrap_or_default(),
+            config,
+            failure_count: action.failure_count,
+            error,
+        };
+    }
+
+    pub fn to_action(&self) -> Action {
+        return Action {
+            id: self.id.unwrap_or(0),
+            name: self.name.clone(),
+            script: self.script.clone().into_bytes(),
+            config: serde_json::to_vec(&self.config).unwrap_or_default(),
+            failure_count: self.failure_count,
+        };
+    }
+}
+
+impl ActionsState {
+    pub fn clear_test(&mut self) {
+        self.test_result = None;
+        self.is_testing = false;
+        self.test_generation += 1;
+    }
+
+    pub fn apply_test_result(&mut self, generation: u64, result: ActionTestResult) {
+        if generation == self.test_generation {
+            self.test_result = Some(result);
+            self.is_testing = false;
+        }
+    }
+}
+
+impl Default for ActionsState {
+    fn default() -> Self {
+        return Self {
+            actions: Vec::new(),
+            needs_refresh: true,
+            view: ActionsView::default(),
+
+            editor: None,
+
+            test_message: String::from("!hello"),
+            test_sender: String::from("ruey_tester"),
+            test_as_broadcaster: false,
+            test_as_moderator: false,
+            test_as_vip: false,
+            test_as_subscriber: false,
+            test_result: None,
+            is_testing: false,
+            test_generation: 0,
+
+            export_mode: false,
+            export_selection: HashSet::new(),
+            export_include_store: false,
+            pending_import: None,
+
+            log: ActionLogState {
+                filter: ActionLogFilter::default(),
+                entries: Vec::new(),
+                needs_refresh: true,
+                has_more: false,
+            },
+            libraries: LibrariesState::default(),
+            timers: TimersState::default(),
+        };
+    }
+}
+
+pub fn show_actions_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    if state.actions.needs_refresh {
+        refresh_actions(state);
+    }
+
+    handle_file_dialog(state);
+
+    SidePanel::left("actions_list")
+        .resizable(true)
+        .default_width(200.0)
+        .show_inside(ui, |ui| {
+            render_action_list(ui, state);
+        });
+
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        if state.actions.pending_import.is_some() {
+            render_pending_import(ui, state);
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut state.actions.view, ActionsView::Editor, "Editor");
+            if ui
+                .selectable_value(&mut state.actions.view, ActionsView::Log, "Log")
+                .clicked()
+            {
+                state.actions.log.needs_refresh = true;
+            }
+            if ui
+                .selectable_value(&mut state.actions.view, ActionsView::Libraries, "Libraries")
+                .clicked()
+            {
+                state.actions.libraries.needs_refresh = true;
+            }
+            if ui
+                .selectable_value(&mut state.actions.view, ActionsView::Timers, "Timers")
+                .clicked()
+            {
+                state.actions.timers.needs_refresh = true;
+            }
+        });
+
+        ui.separator();
+
+        match state.actions.view {
+            ActionsView::Editor => render_action_editor(ui, state),
+            ActionsView::Log => render_action_log(ui, state),
+            ActionsView::Libraries => render_libraries(ui, state),
+            ActionsView::Timers => render_timers(ui, state),
+        }
+    });
+}
+
+fn refresh_actions(state: &mut AppState) {
+    state.actions.needs_refresh = false;
+
+    match Action::load_all(&state.db_pool) {
+        Ok(actions) => state.actions.actions = actions,
+        Err(err) => warn!("Failed to load actions: {}", err),
+    }
+}
+
+pub fn save_action(state: &mut AppState) {
+    let Some(editor) = &mut state.actions.editor else {
+        return;
+    };
+
+    let was_enabled = state
+        .actions
+        .actions
+        .iter()
+        .find(|action| Some(action.id) == editor.id)
+        .and_then(|action| action.parsed_config().ok())
+        .is_some_and(|config| config.enabled);
+
+    let result = match editor.id {
+        Some(id) => Action::update(&state.db_pool, id, &editor.name, &editor.script, &editor.config).and_then(|()| {
+            // like the toggle in the list, re-enabling gives an auto-disabled action a clean slate
+            if editor.config.enabled && !was_enabled {
+                Action::reset_failures(&state.db_pool, id)?;
+                editor.failure_count = 0;
+            }
+            Ok(())
+        }),
+        None => Action::insert(&state.db_pool, &editor.name, &editor.script, &editor.config).map(|id| {
+            editor.id = Some(id);
+        }),
+    };
+
+    editor.error = result.err().map(|err| format!("Failed to save action: {err}"));
+
+    notify_actions_changed(state);
+}
+
+pub fn notify_actions_changed(state: &mut AppState) {
+    state.actions.needs_refresh = true;
+    state
+        .channels
+        .action_worker_message_tx
+        .send(ActionWorkerMessage::Reload)
+        .unwrap();
 }
@@ -0,0 +1,254 @@
+use eframe::egui::{self, Color32, ComboBox, DragValue, Grid, RichText, ScrollArea, SidePanel, TextEdit, Ui};
+use tracing::warn;
+
+use crate::{
+    models::timed_message::{ANNOUNCEMENT_COLORS, NewTimedMessage, TimedMessage},
+    ui::state::AppState,
+    workers::timers::TimersWorkerMessage,
+};
+
+pub struct TimersState {
+    pub timers: Vec<TimedMessage>,
+    pub needs_refresh: bool,
+    pub editor: Option<TimerEditor>,
+}
+
+pub struct TimerEditor {
+    pub id: Option<i32>,
+    pub timer: NewTimedMessage,
+    pub error: Option<String>,
+}
+
+impl Default for TimersState {
+    fn default() -> Self {
+        return Self {
+            timers: Vec::new(),
+            needs_refresh: true,
+            editor: None,
+        };
+    }
+}
+
+pub fn render_timers(ui: &mut Ui, state: &mut AppState) {
+    if state.actions.timers.needs_refresh {
+        state.actions.timers.needs_refresh = false;
+
+        match TimedMessage::load_all(&state.db_pool) {
+            Ok(timers) => state.actions.timers.timers = timers,
+            Err(err) => warn!("Failed to load timed messages: {}", err),
+        }
+    }
+
+    SidePanel::left("timers_list")
+        .resizable(true)
+        .default_width(160.0)
+        .show_inside(ui, |ui| {
+            render_timer_list(ui, state);
+        });
+
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        render_timer_editor(ui, state);
+    });
+}
+
+fn render_timer_list(ui: &mut Ui, state: &mut AppState) {
+    let timers = &mut state.actions.timers;
+
+    if ui.button("New Timer").clicked() {
+        timers.editor = Some(TimerEditor {
+            id: None,
+            timer: NewTimedMessage::default(),
+            error: None,
+        });
+    }
+
+    ui.separator();
+
+    let mut toggled = None;
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        if timers.timers.is_empty() {
+            ui.label("No timers yet.");
+        }
+
+        for timer in &timers.timers {
+            let is_selected = timers.editor.as_ref().is_some_and(|editor| editor.id == Some(timer.id));
+
+            ui.horizontal(|ui| {
+                let mut enabled = timer.enabled;
+                if ui.checkbox(&mut enabled, "").on_hover_text("Enabled").changed() {
+                    toggled = Some((timer.id, enabled));
+                }
+
+                let name = if timer.enabled {
+                    RichText::new(&timer.name)
+                } else {
+                    RichText::new(&timer.name).color(Color32::GRAY)
+                };
+
+                if ui.selectable_label(is_selected, name).clicked() {
+                    timers.editor = Some(TimerEditor {
+                        id: Some(timer.id),
+                        timer: timer.to_new(),
+                        error: None,
+                    });
+                }
+            });
+        }
+    });
+
+    if let Some((id, enabled)) = toggled {
+        if let Err(err) = TimedMessage::set_enabled(&state.db_pool, id, enabled) {
+            warn!("Failed to update timed message: {}", err);
+        }
+
+        if let Some(editor) = &mut state.actions.timers.editor
+            && editor.id == Some(id)
+        {
+            editor.timer.enabled = enabled;
+        }
+
+        notify_timers_changed(state);
+    }
+}
+
+fn render_timer_editor(ui: &mut Ui, state: &mut AppState) {
+    let is_live = state.is_live;
+    let Some(editor) = &mut state.actions.timers.editor else {
+        ui.centered_and_justified(|ui| {
+            ui.label("Select a timer or create a new one.");
+        });
+        return;
+    };
+
+    let mut save_clicked = false;
+    let mut delete_clicked = false;
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        let timer = &mut editor.timer;
+
+        Grid::new("timer_settings").num_columns(2).striped(true).show(ui, |ui| {
+            ui.label("Name");
+            ui.add(TextEdit::singleline(&mut timer.name).char_limit(100));
+            ui.end_row();
+
+            ui.label("Post as");
+            ComboBox::from_id_salt("timer_announcement_color")
+                .selected_text(match &timer.announcement_color {
+                    Some(color) => format!("Announcement ({color})"),
+                    None => String::from("Chat message"),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut timer.announcement_color, None, "Chat message");
+                    for color in ANNOUNCEMENT_COLORS {
+                        ui.selectable_value(
+                            &mut timer.announcement_color,
+                            Some(color.to_owned()),
+                            format!("Announcement ({color})"),
+                        );
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Every")
+                .on_hover_text("Minutes between two posts of this timer.");
+            ui.add(
+                DragValue::new(&mut timer.interval_minutes)
+                    .range(1..=1440)
+                    .suffix(" min"),
+            );
+            ui.end_row();
+
+            ui.label("Min. chat messages").on_hover_text(
+                "Chat messages that have to arrive since the last post, so a quiet chat is not spammed.",
+            );
+            ui.add(DragValue::new(&mut timer.min_chat_messages).range(0..=1000));
+            ui.end_row();
+
+            ui.label("Enabled");
+            ui.checkbox(&mut timer.enabled, "");
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label("Messages, one per line, posted in rotation:");
+        ui.add(
+            TextEdit::multiline(&mut timer.messages)
+                .desired_rows(8)
+                .desired_width(f32::INFINITY),
+        );
+
+        let status = if is_live {
+            RichText::new("The stream is live, timers are running.").color(Color32::GREEN)
+        } else {
+            RichText::new("Timers only post while the stream is live.").color(Color32::GRAY)
+        };
+        ui.label(status);
+
+        if let Some(error) = &editor.error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+
+        ui.horizontal(|ui| {
+            save_clicked = ui.button("Save").clicked();
+
+            if editor.id.is_some() {
+                delete_clicked = ui.button("Delete").clicked();
+            }
+        });
+    });
+
+    if save_clicked {
+        save_timer(state);
+    } else if delete_clicked
+        && let Some(editor) = state.actions.timers.editor.take()
+        && let Some(id) = editor.id
+    {
+        if let Err(err) = TimedMessage::delete(&state.db_pool, id) {
+            warn!("Failed to delete timed message: {}", err);
+        }
+
+        notify_timers_changed(state);
+    }
+}
+
+fn save_timer(state: &mut AppState) {
+    let Some(editor) = &mut state.actions.timers.editor else {
+        return;
+    };
+
+    editor.timer.name = editor.timer.name.trim().to_owned();
+    if editor.timer.name.is_empty() {
+        editor.error = Some(String::from("The timer needs a name."));
+        return;
+    }
+
+    if editor.timer.messages.trim().is_empty() {
+        editor.error = Some(String::from("The timer needs at least one message."));
+        return;
+    }
+
+    let result = match editor.id {
+        Some(id) => TimedMessage::update(&state.db_pool, id, &editor.timer),
+        None => TimedMessage::insert(&state.db_pool, &editor.timer).map(|id| {
+            editor.id = Some(id);
+        }),
+    };
+
+    if let Err(err) = result {
+        editor.error = Some(format!("Failed to save timer: {err}"));
+        return;
+    }
+
+    editor.error = None;
+    notify_timers_changed(state);
+}
+
+fn notify_timers_changed(state: &mut AppState) {
+    state.actions.timers.needs_refresh = true;
+    state
+        .channels
+        .timers_worker_message_tx
+        .send(TimersWorkerMessage::Reload)
+        .unwrap();
+}
@@ -0,0 +1,72 @@
+use std::collections::{HashMap, HashSet};
+
+use strum::IntoEnumIterator;
+
+use crate::twitch::third_party::{EmoteProvider, ThirdPartyEmote};
+
+/// The 7TV, BetterTTV and FrankerFaceZ emotes usable in the joined channel.
+pub struct ThirdPartyEmotes {
+    pub enabled: HashSet<EmoteProvider>,
+    channel_id: Option<String>,
+    /// By provider and channel id, `None` holds the global emotes.
+    sets: HashMap<(EmoteProvider, Option<String>), Vec<ThirdPartyEmote>>,
+    /// The emotes of the enabled providers by their code.
+    by_code: HashMap<String, ThirdPartyEmote>,
+}
+
+impl Default for ThirdPartyEmotes {
+    fn default() -> Self {
+        return Self {
+            enabled: EmoteProvider::iter().collect(),
+            channel_id: None,
+            sets: HashMap::new(),
+            by_code: HashMap::new(),
+        };
+    }
+}
+
+impl ThirdPartyEmotes {
+    pub fn get(&self, code: &str) -> Option<&ThirdPartyEmote> {
+        return self.by_code.get(code);
+    }
+
+    pub fn insert_set(&mut self, provider: EmoteProvider, channel_id: Option<String>, emotes: Vec<ThirdPartyEmote>) {
+        self.sets.insert((provider, channel_id), emotes);
+        self.rebuild();
+    }
+
+    pub fn set_channel(&mut self, channel_id: &str) {
+        self.channel_id = Some(channel_id.to_owned());
+        self.rebuild();
+    }
+
+    pub fn set_enabled(&mut self, provider: EmoteProvider, enabled: bool) {
+        if enabled {
+            self.enabled.insert(provider);
+        } else {
+            self.enabled.remove(&provider);
+        }
+        self.rebuild();
+    }
+
+    /// Channel emotes win over global ones with the same code, 7TV over BetterTTV over FrankerFaceZ.
+    fn rebuild(&mut self) {
+        self.by_code.clear();
+
+        for channel_id in [None, self.channel_id.clone()] {
+            for provider in EmoteProvider::iter().rev() {
+                if !self.enabled.contains(&provider) {
+                    continue;
+                }
+
+                let Some(emotes) = self.sets.get(&(provider, channel_id.clone())) else {
+                    continue;
+                };
+
+                for emote in emotes {
+                    self.by_code.insert(emote.code.clone(), emote.clone());
+                }
+            }
+        }
+    }
+}
@@ -42,9 +42,7 @@ pub fn render_chat_footer(ui: &mut Ui, state: &mut AppState) {
             if state.chat.message_input.trim().starts_with('/') {
                 run_command(&state.channels.ui_diff_tx, account, channel, &state.chat.message_input);
             } else {
-                unsafe {
-                    twitch_send_message(state, &state.chat.message_input);
-                }
+                twitch_send_message(&state.channels.ui_diff_tx, account, channel, &state.chat.message_input);
             }
 
             state.chat.message_input.clear();
@@ -10,7 +10,10 @@ use crate::{
         api::{twitch_delete_all_messages, twitch_patch_chat_settings},
         types::TwitchEvent,
     },
-    ui::{state::AppState, tabs::chat::message::render_event_for_log},
+    ui::{
+        state::{AppState, FileDialogPurpose},
+        tabs::chat::message::render_event_for_log,
+    },
 };
 
 pub fn render_chat_header(ui: &mut Ui, state: &mut AppState) {
@@ -249,6 +252,13 @@ pub fn render_chat_header(ui: &mut Ui, state: &mut AppState) {
                 if ui.selectable_label(state.chat.show_raids, "Raids").clicked() {
                     state.chat.show_raids ^= true;
                 }
+
+                if ui
+                    .selectable_label(state.chat.show_announcements, "Announcements")
+                    .clicked()
+                {
+                    state.chat.show_announcements ^= true;
+                }
             });
         });
 
@@ -278,6 +288,7 @@ pub fn render_chat_header(ui: &mut Ui, state: &mut AppState) {
         });
 
         if flex.add(item(), Button::new("Export Chat Log")).clicked() {
+            state.file_dialog.set_user_data(FileDialogPurpose::ExportChatLog);
             state.file_dialog.save_file();
         }
     });
@@ -285,7 +296,9 @@ pub fn render_chat_header(ui: &mut Ui, state: &mut AppState) {
     // BUG: this seems to break chat flow after being used
 
     // chat log saving, i failed twice moving this to a worker thread already
-    if let Some(path) = state.file_dialog.update(ui.ctx()).picked() {
+    if state.file_dialog.user_data::<FileDialogPurpose>() == Some(&FileDialogPurpose::ExportChatLog)
+        && let Some(path) = state.file_dialog.take_picked()
+    {
         let mut buffer = String::new();
         buffer.reserve(state.chat.events.items.len() * size_of::<TwitchEvent>());
 
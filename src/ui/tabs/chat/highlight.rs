@@ -0,0 +1,298 @@
+use chrono::{DateTime, Local, Utc};
+use eframe::egui::{Color32, Frame, RichText, Ui};
+use twitch_irc::message::UserNoticeEvent;
+
+use crate::twitch::types::TwitchEvent;
+
+/// Chat events that get their own styled row instead of being shown like a regular message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Follow,
+    Sub,
+    Resub,
+    SubGift,
+    Raid,
+    Bits,
+    BitsBadgeTier,
+    Announcement,
+}
+
+impl HighlightKind {
+    fn label(self) -> &'static str {
+        return match self {
+            HighlightKind::Follow => "FOLLOW ",
+            HighlightKind::Sub => "SUB ",
+            HighlightKind::Resub => "RESUB ",
+            HighlightKind::SubGift => "GIFT ",
+            HighlightKind::Raid => "RAID ",
+            HighlightKind::Bits => "BITS ",
+            HighlightKind::BitsBadgeTier => "BITS BADGE ",
+            HighlightKind::Announcement => "ANNOUNCEMENT ",
+        };
+    }
+
+    fn color(self) -> Color32 {
+        return match self {
+            HighlightKind::Follow => Color32::LIGHT_BLUE,
+            HighlightKind::Sub | HighlightKind::Resub | HighlightKind::SubGift => Color32::from_rgb(145, 70, 255),
+            HighlightKind::Raid => Color32::ORANGE,
+            HighlightKind::Bits | HighlightKind::BitsBadgeTier => Color32::GOLD,
+            HighlightKind::Announcement => Color32::LIGHT_GREEN,
+        };
+    }
+}
+
+/// The Show toggles of the chat header that apply to highlights.
+#[derive(Debug, Clone, Copy)]
+pub struct HighlightFilter {
+    pub follows: bool,
+    pub subscriptions: bool,
+    pub bits: bool,
+    pub raids: bool,
+    pub announcements: bool,
+}
+
+impl HighlightFilter {
+    pub fn shows(&self, kind: HighlightKind) -> bool {
+        return match kind {
+            HighlightKind::Follow => self.follows,
+            HighlightKind::Sub | HighlightKind::Resub | HighlightKind::SubGift => self.subscriptions,
+            HighlightKind::Bits | HighlightKind::BitsBadgeTier => self.bits,
+            HighlightKind::Raid => self.raids,
+            HighlightKind::Announcement => self.announcements,
+        };
+    }
+}
+
+pub struct ChatHighlight {
+    pub kind: HighlightKind,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    /// What the user wrote along with it, if anything.
+    pub message: Option<String>,
+}
+
+impl ChatHighlight {
+    /// The kind and user of the highlight `from_event` would build, without building its text.
+    pub fn classify(event: &TwitchEvent) -> Option<(HighlightKind, &str)> {
+        return match event {
+            TwitchEvent::Privmsg(msg) => {
+                msg.bits.filter(|bits| *bits > 0)?;
+                Some((HighlightKind::Bits, msg.sender.name.as_str()))
+            }
+            TwitchEvent::UserNotice(notice) => {
+                let kind = match &notice.event {
+                    UserNoticeEvent::SubOrResub { is_resub: false, .. } => HighlightKind::Sub,
+                    UserNoticeEvent::SubOrResub { is_resub: true, .. } => HighlightKind::Resub,
+                    UserNoticeEvent::SubGift { .. }
+                    | UserNoticeEvent::SubMysteryGift { .. }
+                    | UserNoticeEvent::AnonSubMysteryGift { .. } => HighlightKind::SubGift,
+                    UserNoticeEvent::Raid { .. } => HighlightKind::Raid,
+                    UserNoticeEvent::BitsBadgeTier { .. } => HighlightKind::BitsBadgeTier,
+                    _ if notice.event_id == "announcement" => HighlightKind::Announcement,
+                    _ => return None,
+                };
+
+                Some((kind, notice.sender.name.as_str()))
+            }
+            TwitchEvent::Follow(follow) => Some((HighlightKind::Follow, follow.user_name.as_str())),
+            _ => None,
+        };
+    }
+
+    pub fn from_event(event: &TwitchEvent) -> Option<Self> {
+        return match event {
+            TwitchEvent::Privmsg(msg) => {
+                let bits = msg.bits.filter(|bits| *bits > 0)?;
+
+                Some(Self {
+                    kind: HighlightKind::Bits,
+                    timestamp: msg.server_timestamp,
+                    text: format!("{} cheered {} bits!", msg.sender.name, bits),
+                    // the message itself is rendered as a regular chat message
+                    message: None,
+                })
+            }
+            TwitchEvent::UserNotice(notice) => {
+                let name = &notice.sender.name;
+
+                let (kind, text) = match &notice.event {
+                    UserNoticeEvent::SubOrResub {
+                        is_resub: false,
+                        sub_plan,
+                        ..
+                    } => (
+                        HighlightKind::Sub,
+                        format!("{name} subscribed with {}.", sub_plan_name(sub_plan)),
+                    ),
+                    UserNoticeEvent::SubOrResub {
+                        is_resub: true,
+                        cumulative_months,
+                        streak_months,
+                        sub_plan,
+                        ..
+                    } => {
+                        let streak = streak_months
+                            .map(|months| format!(", {months} months in a row"))
+                            .unwrap_or_default();
+
+                        (
+                            HighlightKind::Resub,
+                            format!(
+                                "{name} resubscribed with {} for {cumulative_months} months{streak}.",
+                                sub_plan_name(sub_plan)
+                            ),
+                        )
+                    }
+                    UserNoticeEvent::SubGift {
+                        is_sender_anonymous,
+                        recipient,
+                        sub_plan,
+                        num_gifted_months,
+                        ..
+                    } => {
+                        let gifter = if *is_sender_anonymous {
+                            "An anonymous user"
+                        } else {
+                            name
+                        };
+                        let months = if *num_gifted_months > 1 {
+                            format!("{num_gifted_months} months of ")
+                        } else {
+                            String::new()
+                        };
+
+                        (
+                            HighlightKind::SubGift,
+                            format!(
+                                "{gifter} gifted {months}a {} sub to {}.",
+                                sub_plan_name(sub_plan),
+                                recipient.name
+                            ),
+                        )
+                    }
+                    UserNoticeEvent::SubMysteryGift {
+                        mass_gift_count,
+                        sender_total_gifts,
+                        sub_plan,
+                    } => (
+                        HighlightKind::SubGift,
+                        format!(
+                            "{name} is gifting {mass_gift_count} {} subs to the community, {sender_total_gifts} in \
+                             total.",
+                            sub_plan_name(sub_plan)
+                        ),
+                    ),
+                    UserNoticeEvent::AnonSubMysteryGift {
+                        mass_gift_count,
+                        sub_plan,
+                    } => (
+                        HighlightKind::SubGift,
+                        format!(
+                            "An anonymous user is gifting {mass_gift_count} {} subs to the community.",
+                            sub_plan_name(sub_plan)
+                        ),
+                    ),
+                    UserNoticeEvent::Raid { viewer_count, .. } => (
+                        HighlightKind::Raid,
+                        format!("{name} is raiding with {viewer_count} viewers!"),
+                    ),
+                    UserNoticeEvent::BitsBadgeTier { threshold } => (
+                        HighlightKind::BitsBadgeTier,
+                        format!("{name} unlocked the {threshold} bits badge!"),
+                    ),
+                    _ if notice.event_id == "announcement" => {
+                        (HighlightKind::Announcement, format!("{name} announced:"))
+                    }
+                    _ => return None,
+                };
+
+                Some(Self {
+                    kind,
+                    timestamp: notice.server_timestamp,
+                    text,
+                    message: notice
+                        .message_text
+                        .as_ref()
+                        .map(|text| text.trim().to_owned())
+                        .filter(|text| !text.is_empty()),
+                })
+            }
+            TwitchEvent::Follow(follow) => Some(Self {
+                kind: HighlightKind::Follow,
+                timestamp: DateTime::parse_from_rfc3339(follow.followed_at.as_str())
+                    .map(|time| time.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                text: format!("{} followed!", follow.user_name),
+                message: None,
+            }),
+            _ => None,
+        };
+    }
+
+    pub fn to_log_line(&self) -> String {
+        let mut line = format!(
+            "{} {}{}",
+            self.timestamp.format("%H:%M:%S"),
+            self.kind.label(),
+            self.text
+        );
+
+        if let Some(message) = &self.message {
+            line.push(' ');
+            line.push_str(message);
+        }
+
+        return line;
+    }
+}
+
+fn sub_plan_name(sub_plan: &str) -> &str {
+    return match sub_plan {
+        "Prime" => "Prime",
+        "1000" => "Tier 1",
+        "2000" => "Tier 2",
+        "3000" => "Tier 3",
+        other => other,
+    };
+}
+
+/// Renders the highlight row, `add_contents` is shown inside the same frame below it.
+pub fn render_chat_highlight(
+    ui: &mut Ui,
+    highlight: &ChatHighlight,
+    show_timestamps: bool,
+    add_contents: impl FnOnce(&mut Ui),
+) {
+    let color = highlight.kind.color();
+
+    Frame::new()
+        .fill(color.gamma_multiply(0.08))
+        .inner_margin(4.0)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+
+            ui.horizontal_wrapped(|ui| {
+                ui.style_mut().spacing.item_spacing.x = 0.0;
+
+                if show_timestamps {
+                    ui.label(RichText::new(
+                        highlight
+                            .timestamp
+                            .with_timezone(&Local)
+                            .format("%H:%M:%S ")
+                            .to_string(),
+                    ));
+                }
+
+                ui.label(RichText::new(highlight.kind.label()).color(color).strong());
+                ui.label(RichText::new(&highlight.text).color(color));
+            });
+
+            if let Some(message) = &highlight.message {
+                ui.label(RichText::new(message).color(Color32::WHITE));
+            }
+
+            add_contents(ui);
+        });
+}
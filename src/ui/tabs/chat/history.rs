@@ -2,7 +2,14 @@ use eframe::egui::{self, Align2, Area, ScrollArea, Ui, scroll_area::ScrollSource
 
 use crate::{
     twitch::types::{PrivmsgMessageExt, TwitchEvent},
-    ui::{state::AppState, tabs::chat::message::render_chat_message},
+    ui::{
+        state::AppState,
+        tabs::chat::{
+            highlight::{ChatHighlight, HighlightFilter, render_chat_highlight},
+            message::{ChatMessageActions, ChatMessageContext, render_chat_message},
+            profile::UserProfile,
+        },
+    },
 };
 
 pub fn render_chat_history(ui: &mut Ui, state: &mut AppState) {
@@ -48,7 +55,9 @@ pub fn render_chat_history(ui: &mut Ui, state: &mut AppState) {
             }
         });
 
-    ScrollArea::vertical()
+    let mut actions = ChatMessageActions::default();
+
+    let output = ScrollArea::vertical()
         .max_height(ui.available_height() - 35.0)
         .max_width(ui.available_width() - 5.0)
         .auto_shrink([false, false])
@@ -59,36 +68,107 @@ pub fn render_chat_history(ui: &mut Ui, state: &mut AppState) {
             scroll_bar: true,
         })
         .show(ui, |ui| {
-            state.chat.events.ui(ui, 50, |ui, _, event| match event {
-                TwitchEvent::Join(join) => {
-                    ui.label(format!("Joined channel {}.", join.channel_login));
-                }
-                TwitchEvent::Notice(notice) => {
-                    ui.label(notice.message_text.trim());
-                }
-                TwitchEvent::Privmsg(msg) => {
-                    let logged_in_user_name = state
-                        .twitch_account
-                        .as_ref()
-                        .map(|account| account.token.login.clone().to_string());
-
-                    render_chat_message(
-                        ui,
-                        msg,
-                        &state.channels.ui_diff_tx,
-                        &state.twitch_account,
-                        &state.connected_channel_info,
-                        &mut state.chat.user_query,
-                        logged_in_user_name,
-                        state.chat.show_timestamps,
-                    );
-                }
-                _ => {}
+            let context = ChatMessageContext {
+                diff_tx: &state.channels.ui_diff_tx,
+                account: &state.twitch_account,
+                channel: &state.connected_channel_info,
+                logged_in_user_name: state
+                    .twitch_account
+                    .as_ref()
+                    .map(|account| account.token.login.clone().to_string()),
+                show_timestamps: state.chat.show_timestamps,
+                emote_size: state.chat.emote_size,
+                assets: &state.assets,
+                third_party_emotes: &state.chat.third_party_emotes,
+            };
+
+            state.chat.events.ui(ui, 50, |ui, _, event| {
+                render_chat_event(ui, event, &context, &mut actions);
             });
         });
+
+    state.chat.is_at_bottom =
+        output.state.offset.y + output.inner_rect.height() >= output.content_size.y - ui.spacing().item_spacing.y;
+
+    if let Some(user_query) = actions.user_query {
+        state.chat.user_query = user_query;
+    }
+
+    if let Some(sender) = actions.open_profile {
+        state.chat.profile = Some(UserProfile::open(state, &sender));
+    }
+}
+
+pub fn render_chat_event(
+    ui: &mut Ui,
+    event: &TwitchEvent,
+    context: &ChatMessageContext,
+    actions: &mut ChatMessageActions,
+) {
+    match event {
+        TwitchEvent::Join(join) => {
+            ui.label(format!("Joined channel {}.", join.channel_login));
+        }
+        TwitchEvent::Notice(notice) => {
+            ui.label(notice.message_text.trim());
+        }
+        TwitchEvent::Privmsg(msg) => match ChatHighlight::from_event(event) {
+            Some(highlight) => {
+                render_chat_highlight(ui, &highlight, context.show_timestamps, |ui| {
+                    render_chat_message(ui, msg, context, actions);
+                });
+            }
+            None => render_chat_message(ui, msg, context, actions),
+        },
+        event => {
+            if let Some(highlight) = ChatHighlight::from_event(event) {
+                render_chat_highlight(ui, &highlight, context.show_timestamps, |_| {});
+            }
+        }
+    }
+}
+
+/// Everything the chat filter depends on, the filter is only rebuilt when this changes.
+#[derive(Clone, PartialEq)]
+pub struct ChatFilterKey {
+    show_notices: bool,
+    show_messages: bool,
+    show_messages_by_broadcaster: bool,
+    show_messages_by_moderator: bool,
+    show_messages_by_vip: bool,
+    show_messages_by_subscriber: bool,
+    show_messages_by_regular_viewer: bool,
+    show_follows: bool,
+    show_subscriptions: bool,
+    show_bits: bool,
+    show_raids: bool,
+    show_announcements: bool,
+    user_query: String,
+    message_query: String,
 }
 
 fn set_event_filter(state: &mut AppState) {
+    let key = ChatFilterKey {
+        show_notices: state.chat.show_notices,
+        show_messages: state.chat.show_messages,
+        show_messages_by_broadcaster: state.chat.show_messages_by_broadcaster,
+        show_messages_by_moderator: state.chat.show_messages_by_moderator,
+        show_messages_by_vip: state.chat.show_messages_by_vip,
+        show_messages_by_subscriber: state.chat.show_messages_by_subscriber,
+        show_messages_by_regular_viewer: state.chat.show_messages_by_regular_viewer,
+        show_follows: state.chat.show_follows,
+        show_subscriptions: state.chat.show_subscriptions,
+        show_bits: state.chat.show_bits,
+        show_raids: state.chat.show_raids,
+        show_announcements: state.chat.show_announcements,
+        user_query: state.chat.user_query_last.clone(),
+        message_query: state.chat.message_query_last.clone(),
+    };
+    if state.chat.filter_key.as_ref() == Some(&key) {
+        return;
+    }
+    state.chat.filter_key = Some(key);
+
     let local_chat_show_notices = state.chat.show_notices;
     let local_chat_show_messages = state.chat.show_messages;
     let local_chat_show_messages_by_broadcaster = state.chat.show_messages_by_broadcaster;
@@ -98,29 +178,43 @@ fn set_event_filter(state: &mut AppState) {
     let local_chat_show_messages_by_regular_viewer = state.chat.show_messages_by_regular_viewer;
     let local_chat_user_query_regex = state.chat.user_query_regex.clone();
     let local_chat_message_regex = state.chat.message_regex.clone();
+    let local_chat_highlight_filter = HighlightFilter {
+        follows: state.chat.show_follows,
+        subscriptions: state.chat.show_subscriptions,
+        bits: state.chat.show_bits,
+        raids: state.chat.show_raids,
+        announcements: state.chat.show_announcements,
+    };
 
     state.chat.events.set_filter(move |event| match event {
         TwitchEvent::Join(_) => true,
         TwitchEvent::Notice(_) => local_chat_show_notices,
         TwitchEvent::Privmsg(msg) => {
-            if !local_chat_show_messages {
-                return false;
-            }
+            // cheers are shown with the bits, regardless of who sent them
+            if let Some((kind, _)) = ChatHighlight::classify(event) {
+                if !local_chat_highlight_filter.shows(kind) {
+                    return false;
+                }
+            } else {
+                if !local_chat_show_messages {
+                    return false;
+                }
 
-            if !local_chat_show_messages_by_broadcaster && msg.is_by_broadcaster() {
-                return false;
-            }
-            if !local_chat_show_messages_by_moderator && msg.is_by_mod() {
-                return false;
-            }
-            if !local_chat_show_messages_by_vip && msg.is_by_vip() {
-                return false;
-            }
-            if !local_chat_show_messages_by_subscriber && msg.is_by_subscriber() {
-                return false;
-            }
-            if !local_chat_show_messages_by_regular_viewer && msg.is_by_regular_viewer() {
-                return false;
+                if !local_chat_show_messages_by_broadcaster && msg.is_by_broadcaster() {
+                    return false;
+                }
+                if !local_chat_show_messages_by_moderator && msg.is_by_mod() {
+                    return false;
+                }
+                if !local_chat_show_messages_by_vip && msg.is_by_vip() {
+                    return false;
+                }
+                if !local_chat_show_messages_by_subscriber && msg.is_by_subscriber() {
+                    return false;
+                }
+                if !local_chat_show_messages_by_regular_viewer && msg.is_by_regular_viewer() {
+                    return false;
+                }
             }
 
             if let Some(ref re) = local_chat_user_query_regex
@@ -137,6 +231,11 @@ fn set_event_filter(state: &mut AppState) {
 
             return true;
         }
-        _ => false,
+        event => ChatHighlight::classify(event).is_some_and(|(kind, user_name)| {
+            local_chat_highlight_filter.shows(kind)
+                && local_chat_user_query_regex
+                    .as_ref()
+                    .is_none_or(|re| re.is_match(user_name))
+        }),
     });
 }
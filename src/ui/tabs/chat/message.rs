@@ -1,10 +1,9 @@
 use std::{sync::mpsc, time::Duration};
 
 use chrono::Local;
-use eframe::egui::{self, Color32, Popup, RichText, Ui};
-use linkify::LinkFinder;
+use eframe::egui::{self, Color32, Popup, Rect, RichText, Sense, Ui, Vec2};
 use twitch_api::helix::channels::ChannelInformation;
-use twitch_irc::message::PrivmsgMessage;
+use twitch_irc::message::{Badge, PrivmsgMessage, TwitchUserBasics};
 
 use crate::{
     twitch::{
@@ -12,29 +11,66 @@ use crate::{
             twitch_ban_user, twitch_delete_message, twitch_mod_user, twitch_shoutout_user, twitch_timeout_user,
             twitch_unban_user, twitch_unmod_user, twitch_vip_user,
         },
+        message::{ChatMessage, MessageSegment, ParsedMessage},
+        third_party::ThirdPartyEmote,
         types::{PrivmsgMessageExt, TwitchAccount, TwitchEvent},
     },
-    ui::state::AppStateDiff,
+    ui::{
+        assets::AssetStore,
+        state::AppStateDiff,
+        tabs::chat::{emotes::ThirdPartyEmotes, highlight::ChatHighlight},
+    },
+    workers::asset::AssetKey,
 };
 
+const BADGE_SIZE: f32 = 18.0;
+/// Wider emotes are scaled down to fit.
+const MAX_EMOTE_ASPECT_RATIO: f32 = 4.0;
+const BADGE_SPACING: f32 = 3.0;
+/// Set on messages by us to mark them as deleted, timed out or banned.
+const PSEUDO_BADGES: [&str; 3] = ["deleted", "timeouted", "banned"];
+
+/// What rendering a chat message needs besides the message itself.
+pub struct ChatMessageContext<'a> {
+    pub diff_tx: &'a mpsc::Sender<AppStateDiff>,
+    pub account: &'a Option<TwitchAccount>,
+    pub channel: &'a Option<ChannelInformation>,
+    pub logged_in_user_name: Option<String>,
+    pub show_timestamps: bool,
+    pub emote_size: f32,
+    pub assets: &'a AssetStore,
+    pub third_party_emotes: &'a ThirdPartyEmotes,
+}
+
+/// What was picked from the sender menu, applied once the chat is rendered.
+#[derive(Default)]
+pub struct ChatMessageActions {
+    pub user_query: Option<String>,
+    pub open_profile: Option<TwitchUserBasics>,
+}
+
 pub fn render_chat_message(
     ui: &mut Ui,
-    message: &PrivmsgMessage,
-    diff_tx: &mpsc::Sender<AppStateDiff>,
-    account: &Option<TwitchAccount>,
-    channel: &Option<ChannelInformation>,
-    chat_user_query: &mut String,
-    logged_in_user_name: Option<String>,
-    show_timestamps: bool,
+    message: &ChatMessage,
+    context: &ChatMessageContext,
+    actions: &mut ChatMessageActions,
 ) {
+    let ChatMessageContext {
+        diff_tx,
+        account,
+        channel,
+        logged_in_user_name,
+        show_timestamps,
+        emote_size,
+        assets,
+        third_party_emotes,
+    } = context;
+
     ui.horizontal_wrapped(|ui| {
         ui.style_mut().spacing.item_spacing.x = 0.0;
 
-        let mut message = message.to_owned();
-        message.message_text = message.message_text.trim().to_owned();
-
         // timestamp
-        if show_timestamps {
+        if *show_timestamps {
             ui.label(RichText::new(
                 message
                     .server_timestamp
@@ -45,8 +81,8 @@ pub fn render_chat_message(
         }
 
         // ping?
-        if let Some(logged_in_user_name) = &logged_in_user_name
-            && message.message_text.contains(&logged_in_user_name.to_lowercase())
+        if let Some(logged_in_user_name) = logged_in_user_name
+            && message.parsed.mentions_user(logged_in_user_name)
         {
             ui.label(RichText::new("PING ").color(Color32::PURPLE));
         }
@@ -64,16 +100,8 @@ pub fn render_chat_message(
         if message.is_first_message() {
             ui.label(RichText::new("FIRST ").color(Color32::YELLOW));
         }
-        if message.is_by_broadcaster() {
-            ui.label(RichText::new("CAST ").color(Color32::RED));
-        } else if message.is_by_lead_mod() {
-            ui.label(RichText::new("LMOD ").color(Color32::DARK_GREEN));
-        } else if message.is_by_mod() {
-            ui.label(RichText::new("MOD ").color(Color32::GREEN));
-        } else if message.is_by_vip() {
-            ui.label(RichText::new("VIP ").color(Color32::MAGENTA));
-        } else if message.is_by_subscriber() {
-            ui.label(RichText::new("SUB ").color(Color32::CYAN));
+        for badge in &message.badges {
+            render_badge(ui, assets, message, badge);
         }
 
         // sender
@@ -94,8 +122,13 @@ pub fn render_chat_message(
             ui.colored_label(egui::Color32::WHITE, format!("User: {}", message.sender.name));
             ui.separator();
 
+            if ui.button("Profile").clicked() {
+                actions.open_profile = Some(message.sender.clone());
+                ui.close();
+            }
+
             if ui.button("History").clicked() {
-                *chat_user_query = message.sender.name.clone();
+                actions.user_query = Some(message.sender.name.clone());
                 ui.close();
             }
 
@@ -123,9 +156,7 @@ pub fn render_chat_message(
                 && !message.is_banned()
                 && ui.button("Delete Message").clicked()
             {
-                unsafe {
-                    // twitch_delete_message(diff_tx, account, channel, &message.message_id);
-                }
+                twitch_delete_message(diff_tx, account, channel, &message.message_id);
                 ui.close();
             }
 
@@ -371,39 +402,232 @@ pub fn render_chat_message(
             }
         });
 
-        // NOTE: if this has to be extended in anyway, parse the message before into [Text(String),
-        // Link(String), Emote(Img), Text(Text)] etc and then match on the kind to display.
-
         // message text
-        let links: Vec<_> = LinkFinder::new().links(&message.message_text).collect();
-
-        if links.is_empty() {
-            ui.label(RichText::new(&message.message_text).color(egui::Color32::WHITE));
-        } else {
-            let mut last_end = 0;
-            for link in &links {
-                let start = link.start();
-                let end = link.end();
-
-                // text before link
-                if start > last_end {
-                    ui.label(RichText::new(&message.message_text[last_end..start]).color(egui::Color32::WHITE));
+        for segment in render_segments(&message.parsed, third_party_emotes) {
+            match segment {
+                RenderSegment::Text(text) => {
+                    ui.label(RichText::new(text).color(Color32::WHITE));
+                }
+                RenderSegment::Emote { emote, overlays } => {
+                    render_emote(ui, assets, emote, &overlays, *emote_size);
+                }
+                RenderSegment::Parsed(MessageSegment::Link(link)) => {
+                    ui.hyperlink(link);
+                }
+                RenderSegment::Parsed(MessageSegment::Mention { text, login }) => {
+                    let is_me = logged_in_user_name
+                        .as_ref()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(login));
+                    ui.label(
+                        RichText::new(text)
+                            .strong()
+                            .color(if is_me { Color32::PURPLE } else { Color32::WHITE }),
+                    );
+                }
+                RenderSegment::Parsed(MessageSegment::Cheermote { text, .. }) => {
+                    ui.label(RichText::new(text).strong().color(Color32::GOLD));
+                }
+                RenderSegment::Parsed(MessageSegment::ReplyPrefix { text, .. }) => {
+                    ui.label(RichText::new(text).weak());
+                }
+                RenderSegment::Parsed(segment) => {
+                    ui.label(RichText::new(segment.text()).color(Color32::WHITE));
                 }
+            }
+        }
+    });
+}
 
-                // link
-                ui.hyperlink(link.as_str());
+/// A piece of a chat message as it is rendered, text is split further into third party emotes.
+enum RenderSegment<'a> {
+    Text(&'a str),
+    Parsed(&'a MessageSegment),
+    Emote {
+        emote: ChatEmote<'a>,
+        /// Zero width emotes drawn on top of it.
+        overlays: Vec<&'a ThirdPartyEmote>,
+    },
+}
 
-                last_end = end;
-            }
+#[derive(Clone, Copy)]
+enum ChatEmote<'a> {
+    Twitch { id: &'a str, code: &'a str },
+    ThirdParty(&'a ThirdPartyEmote),
+}
+
+impl ChatEmote<'_> {
+    fn key(&self) -> AssetKey {
+        return match self {
+            ChatEmote::Twitch { id, .. } => AssetKey::Emote((*id).to_owned()),
+            ChatEmote::ThirdParty(emote) => emote_key(emote),
+        };
+    }
 
-            // text after last link
-            if last_end < message.message_text.len() {
-                ui.label(RichText::new(&message.message_text[last_end..]).color(egui::Color32::WHITE));
+    fn code(&self) -> &str {
+        return match self {
+            ChatEmote::Twitch { code, .. } => code,
+            ChatEmote::ThirdParty(emote) => &emote.code,
+        };
+    }
+}
+
+fn emote_key(emote: &ThirdPartyEmote) -> AssetKey {
+    return AssetKey::ThirdPartyEmote {
+        provider: emote.provider,
+        id: emote.id.clone(),
+    };
+}
+
+fn render_segments<'a>(parsed: &'a ParsedMessage, third_party_emotes: &'a ThirdPartyEmotes) -> Vec<RenderSegment<'a>> {
+    let mut segments = Vec::new();
+
+    for segment in &parsed.segments {
+        match segment {
+            MessageSegment::Text(text) => push_words(&mut segments, text, third_party_emotes),
+            MessageSegment::Emote { id, code } => push_emote(&mut segments, ChatEmote::Twitch { id, code }),
+            segment => segments.push(RenderSegment::Parsed(segment)),
+        }
+    }
+
+    return segments;
+}
+
+/// Third party emotes are whole words matching their code.
+fn push_words<'a>(segments: &mut Vec<RenderSegment<'a>>, text: &'a str, third_party_emotes: &'a ThirdPartyEmotes) {
+    let mut last_end = 0;
+    let mut word_start = 0;
+
+    for word in text.split_inclusive(char::is_whitespace) {
+        let start = word_start;
+        word_start += word.len();
+
+        let Some(emote) = third_party_emotes.get(word.trim_end()) else {
+            continue;
+        };
+
+        if start > last_end {
+            segments.push(RenderSegment::Text(&text[last_end..start]));
+        }
+
+        push_emote(segments, ChatEmote::ThirdParty(emote));
+        last_end = start + word.trim_end().len();
+    }
+
+    if last_end < text.len() {
+        segments.push(RenderSegment::Text(&text[last_end..]));
+    }
+}
+
+/// Zero width emotes go on top of the emote before them when only whitespace is in between.
+fn push_emote<'a>(segments: &mut Vec<RenderSegment<'a>>, emote: ChatEmote<'a>) {
+    if let ChatEmote::ThirdParty(overlay) = emote
+        && overlay.zero_width
+    {
+        let has_separator = matches!(segments.last(), Some(RenderSegment::Text(text)) if text.trim().is_empty());
+        let base_index = segments.len().checked_sub(1 + has_separator as usize);
+
+        if let Some(RenderSegment::Emote { overlays, .. }) = base_index.and_then(|index| segments.get_mut(index)) {
+            overlays.push(overlay);
+            if has_separator {
+                segments.pop();
             }
+            return;
         }
+    }
+
+    segments.push(RenderSegment::Emote {
+        emote,
+        overlays: Vec::new(),
     });
 }
 
+fn render_badge(ui: &mut Ui, assets: &AssetStore, message: &PrivmsgMessage, badge: &Badge) {
+    if PSEUDO_BADGES.contains(&badge.name.as_str()) {
+        return;
+    }
+
+    let key = AssetKey::Badge {
+        channel_id: message.channel_id.clone(),
+        set_id: badge.name.clone(),
+        version: badge.version.clone(),
+    };
+
+    let response = match assets.image(&key) {
+        Some(image) => {
+            let response = ui.add(image.fit_to_exact_size(Vec2::splat(BADGE_SIZE)));
+            ui.add_space(BADGE_SPACING);
+            response
+        }
+        // until the image arrives, or for good without a linked account
+        None => {
+            let (text, color) = match badge.name.as_str() {
+                "broadcaster" => ("CAST ", Color32::RED),
+                "lead_moderator" => ("LMOD ", Color32::DARK_GREEN),
+                "moderator" => ("MOD ", Color32::GREEN),
+                "vip" => ("VIP ", Color32::MAGENTA),
+                "subscriber" => ("SUB ", Color32::CYAN),
+                _ => return,
+            };
+            ui.label(RichText::new(text).color(color))
+        }
+    };
+
+    response.on_hover_text(badge_tooltip(assets, message, badge));
+}
+
+fn badge_tooltip(assets: &AssetStore, message: &PrivmsgMessage, badge: &Badge) -> String {
+    let title = assets
+        .badge_title(&message.channel_id, &badge.name, &badge.version)
+        .unwrap_or(&badge.name);
+
+    // badge info holds the exact months, the badge version only the tier of the badge
+    let months = message
+        .badge_info
+        .iter()
+        .find(|info| info.name == badge.name && matches!(badge.name.as_str(), "subscriber" | "founder"))
+        .map(|info| info.version.as_str());
+
+    return match months {
+        Some("1") => format!("{title}\n1 month"),
+        Some(months) => format!("{title}\n{months} months"),
+        None => title.to_owned(),
+    };
+}
+
+fn render_emote(ui: &mut Ui, assets: &AssetStore, emote: ChatEmote, overlays: &[&ThirdPartyEmote], size: f32) {
+    // emotes keep their aspect ratio, only the height is fixed
+    let max_size = Vec2::new(size * MAX_EMOTE_ASPECT_RATIO, size);
+
+    let response = match assets.image(&emote.key()) {
+        Some(image) => ui.add(image.fit_to_exact_size(max_size)),
+        None => ui.allocate_exact_size(Vec2::splat(size), Sense::hover()).1,
+    };
+
+    for overlay in overlays {
+        let Some(image) = assets
+            .image(&emote_key(overlay))
+            .map(|image| image.fit_to_exact_size(max_size))
+        else {
+            continue;
+        };
+        let Some(image_size) = image
+            .load_for_size(ui.ctx(), max_size)
+            .ok()
+            .and_then(|texture| texture.size())
+        else {
+            continue;
+        };
+
+        let rect = Rect::from_center_size(response.rect.center(), image.calc_size(max_size, Some(image_size)));
+        image.paint_at(ui, rect);
+    }
+
+    let codes = std::iter::once(emote.code())
+        .chain(overlays.iter().map(|overlay| overlay.code.as_str()))
+        .collect::<Vec<_>>();
+    response.on_hover_text(codes.join(" "));
+}
+
 pub fn render_event_for_log(buffer: &mut String, event: &TwitchEvent) {
     match event {
         TwitchEvent::Join(join) => {
@@ -413,6 +637,10 @@ pub fn render_event_for_log(buffer: &mut String, event: &TwitchEvent) {
             buffer.push_str(&format!("{}\n", notice.message_text.trim()));
         }
         TwitchEvent::Privmsg(msg) => {
+            if let Some(highlight) = ChatHighlight::from_event(event) {
+                buffer.push_str(&format!("{}\n", highlight.to_log_line()));
+            }
+
             let badge1 = if msg.is_banned() {
                 "[BANNED] "
             } else if msg.is_timeouted() {
@@ -439,13 +667,22 @@ pub fn render_event_for_log(buffer: &mut String, event: &TwitchEvent) {
                 ""
             };
 
+            let reply = match msg.parsed.reply_to() {
+                Some(login) => format!(" (reply to {login})"),
+                None => String::new(),
+            };
+
             buffer.push_str(&format!(
-                "{} {badge1}{badge2}{}: {}\n",
+                "{} {badge1}{badge2}{}{reply}: {}\n",
                 msg.server_timestamp.format("%H:%M:%S"),
                 msg.sender.name,
-                msg.message_text
+                msg.parsed.text()
             ));
         }
-        _ => {}
+        event => {
+            if let Some(highlight) = ChatHighlight::from_event(event) {
+                buffer.push_str(&format!("{}\n", highlight.to_log_line()));
+            }
+        }
     }
 }
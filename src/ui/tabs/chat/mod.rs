@@ -1,24 +1,69 @@
+pub mod emotes;
 mod footer;
 mod header;
-mod history;
+mod highlight;
+pub mod history;
 pub mod message;
+pub mod profile;
 
-use std::time::Duration;
+use std::{
+    collections::VecDeque,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
 use eframe::egui;
 use egui_infinite_scroll::InfiniteScroll;
 use regex::Regex;
+use tracing::warn;
 
 use crate::{
+    models::{
+        SqlitePool,
+        chat_event::{ChatEvent, NewChatEvent},
+    },
     twitch::types::TwitchEvent,
     ui::{
         state::AppState,
-        tabs::chat::{footer::render_chat_footer, header::render_chat_header, history::render_chat_history},
+        tabs::chat::{
+            emotes::ThirdPartyEmotes,
+            footer::render_chat_footer,
+            header::render_chat_header,
+            history::{ChatFilterKey, render_chat_history},
+            profile::UserProfile,
+        },
     },
+    workers::history::HistoryWorkerMessage,
 };
 
+const HISTORY_PAGE_SIZE: i64 = 100;
+const DEFAULT_MAX_EVENTS: usize = 5_000;
+/// While scrolled up live events are kept until there are this many times `max_events`.
+const SCROLLED_UP_EVENTS_FACTOR: usize = 2;
+const DEFAULT_EMOTE_SIZE: f32 = 28.0;
+
 pub struct ChatState {
-    pub events: InfiniteScroll<TwitchEvent, usize>,
+    /// Live events of the joined channel, older ones are paged in from the chat history, keyed by their id.
+    pub events: InfiniteScroll<TwitchEvent, i32>,
+    /// How many events are kept in memory while following the chat.
+    pub max_events: usize,
+    history_channel: Option<String>,
+    /// Whether the history worker saved the events of before the switch to `history_channel`, the ids it reports
+    /// from then on belong to the live events.
+    history_started: bool,
+    /// The history shown above the live events starts before this id.
+    history_before: Option<i32>,
+    /// History ids of the live events at the end of `events`, unset for events that are not saved or not yet.
+    live_ids: VecDeque<Option<i32>>,
+    /// How many live events were dropped from the front of `live_ids`.
+    live_start: usize,
+    /// Positions of live events the history worker did not report the id of yet, oldest first.
+    unsaved: VecDeque<usize>,
+    /// The filter `events` was last filtered with.
+    pub(super) filter_key: Option<ChatFilterKey>,
+    pub is_at_bottom: bool,
+    /// Smoothed time it takes to filter and draw the chat each frame.
+    pub render_time: Duration,
 
     pub is_slow_mode: Option<Duration>,
     pub is_emote_only: bool,
@@ -26,6 +71,9 @@ pub struct ChatState {
     pub is_subscriber_only: bool,
 
     pub show_timestamps: bool,
+    /// Height of inline emotes in points.
+    pub emote_size: f32,
+    pub third_party_emotes: ThirdPartyEmotes,
 
     pub show_messages_by_broadcaster: bool,
     pub show_messages_by_moderator: bool,
@@ -39,6 +87,7 @@ pub struct ChatState {
     pub show_subscriptions: bool,
     pub show_bits: bool,
     pub show_raids: bool,
+    pub show_announcements: bool,
 
     pub user_query: String,
     pub user_query_regex: Option<Regex>,
@@ -51,19 +100,24 @@ pub struct ChatState {
     pub message_query_last: String,
 
     pub message_input: String,
+
+    pub profile: Option<UserProfile>,
 }
 
 impl Default for ChatState {
     fn default() -> Self {
-        let events = InfiniteScroll::new().start_loader(|cursor, callback| {
-            let page = cursor.unwrap_or(0);
-            let items: Vec<TwitchEvent> = vec![];
-
-            callback(Ok((items, Some(page + 1))));
-        });
-
         return Self {
-            events,
+            events: InfiniteScroll::new(),
+            max_events: DEFAULT_MAX_EVENTS,
+            history_channel: None,
+            history_started: false,
+            history_before: None,
+            live_ids: VecDeque::new(),
+            live_start: 0,
+            unsaved: VecDeque::new(),
+            filter_key: None,
+            is_at_bottom: true,
+            render_time: Duration::ZERO,
 
             is_slow_mode: None,
             is_emote_only: false,
@@ -71,6 +125,8 @@ impl Default for ChatState {
             is_subscriber_only: false,
 
             show_timestamps: true,
+            emote_size: DEFAULT_EMOTE_SIZE,
+            third_party_emotes: ThirdPartyEmotes::default(),
 
             show_messages_by_broadcaster: true,
             show_messages_by_moderator: true,
@@ -84,6 +140,7 @@ impl Default for ChatState {
             show_subscriptions: true,
             show_bits: true,
             show_raids: true,
+            show_announcements: true,
 
             user_query: String::new(),
             user_query_regex: None,
@@ -96,16 +153,181 @@ impl Default for ChatState {
             message_query_last: String::new(),
 
             message_input: String::new(),
+
+            profile: None,
         };
     }
 }
 
+impl ChatState {
+    /// Clears the chat and asks the history worker to save what it holds, `start_history` follows once it did. Call
+    /// it before joining, events the history worker still holds would end up in neither the history nor the live
+    /// events.
+    pub fn load_history(&mut self, history_worker_message_tx: &mpsc::Sender<HistoryWorkerMessage>, channel: &str) {
+        history_worker_message_tx
+            .send(HistoryWorkerMessage::Flush(channel.to_owned()))
+            .unwrap();
+
+        self.history_channel = Some(channel.to_owned());
+        self.history_started = false;
+        self.history_before = None;
+        self.live_ids.clear();
+        self.unsaved.clear();
+        self.replace_events(InfiniteScroll::new());
+    }
+
+    /// Pages the history of `channel` older than `newest_id` in when scrolling up.
+    pub fn start_history(&mut self, db_pool: &SqlitePool, channel: &str, newest_id: Option<i32>) {
+        // switched channels again in the meantime
+        if self.history_channel.as_deref() != Some(channel) {
+            return;
+        }
+
+        self.history_started = true;
+        self.history_before = newest_id.map(|id| id + 1);
+
+        // notices shown while waiting
+        let mut events = history_scroll(db_pool, channel, self.history_before);
+        events.items = std::mem::take(&mut self.events.items);
+        self.replace_events(events);
+    }
+
+    /// Hands the ids the history worker saved the live events with to them, the events arrive in the same order
+    /// there.
+    pub fn apply_saved_ids(&mut self, ids: Vec<(String, i32)>) {
+        let Some(channel) = self.history_channel.as_deref().filter(|_| self.history_started) else {
+            return;
+        };
+
+        for (_, id) in ids.into_iter().filter(|(event_channel, _)| event_channel == channel) {
+            let Some(position) = self.unsaved.pop_front() else {
+                break;
+            };
+
+            match position.checked_sub(self.live_start) {
+                Some(index) => self.live_ids[index] = Some(id),
+                // dropped before it was saved, the history can continue after it at least
+                None => self.history_before = Some(id + 1),
+            }
+        }
+    }
+
+    /// Appends a live event, dropping the oldest events once there are more than `max_events`. They stay reachable
+    /// through the chat history.
+    pub fn push_event(&mut self, db_pool: &SqlitePool, event: TwitchEvent) {
+        let is_saved = self.history_started
+            && NewChatEvent::channel_of(&event).is_some_and(|channel| self.history_channel.as_deref() == Some(channel));
+        if is_saved {
+            self.unsaved.push_back(self.live_start + self.live_ids.len());
+        }
+
+        self.live_ids.push_back(None);
+        self.events.items.push(event);
+
+        self.drop_old_events(db_pool);
+    }
+
+    fn drop_old_events(&mut self, db_pool: &SqlitePool) {
+        let len = self.events.items.len();
+        let live_len = self.live_ids.len();
+        // pages of the history loaded above the live events
+        let history_len = len.saturating_sub(live_len);
+
+        // while scrolled up the oldest events may be the ones being read
+        let is_over_limit = live_len > self.max_events * SCROLLED_UP_EVENTS_FACTOR;
+        if !is_over_limit && (!self.is_at_bottom || len <= self.max_events) {
+            return;
+        }
+
+        // dropping a tenth at once keeps the scroll from being rebuilt for every single event
+        let mut live_excess = live_len.saturating_sub(self.max_events - self.max_events / 10);
+        if !is_over_limit {
+            // the history continues after the newest dropped event, so it has to be saved
+            if let Some(position) = self.unsaved.front() {
+                live_excess = live_excess.min(position.saturating_sub(self.live_start));
+            }
+
+            if history_len + live_excess < self.max_events / 10 {
+                return;
+            }
+        }
+
+        for _ in 0..live_excess {
+            if let Some(Some(id)) = self.live_ids.pop_front() {
+                self.history_before = Some(id + 1);
+            }
+        }
+        self.live_start += live_excess;
+
+        if self.unsaved.front().is_some_and(|position| *position < self.live_start) {
+            warn!("Dropped chat events the chat history did not save yet");
+        }
+
+        let kept = self.events.items.split_off(history_len + live_excess);
+
+        let Some(channel) = &self.history_channel else {
+            self.events.items = kept;
+            self.events.reset_virtual_list();
+            return;
+        };
+
+        let mut events = history_scroll(db_pool, channel, self.history_before);
+        events.items = kept;
+        self.replace_events(events);
+    }
+
+    fn replace_events(&mut self, events: InfiniteScroll<TwitchEvent, i32>) {
+        self.events = events;
+        // the new scroll has no filter yet
+        self.filter_key = None;
+    }
+}
+
+/// Pages the events of `channel` older than `before_id` in when scrolling up.
+fn history_scroll(db_pool: &SqlitePool, channel: &str, before_id: Option<i32>) -> InfiniteScroll<TwitchEvent, i32> {
+    let db_pool = db_pool.clone();
+    let channel = channel.to_owned();
+
+    return InfiniteScroll::new().start_loader(move |cursor, callback| {
+        let Some(before_id) = cursor.or(before_id) else {
+            callback(Ok((Vec::new(), None)));
+            return;
+        };
+
+        let db_pool = db_pool.clone();
+        let channel = channel.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let page = match ChatEvent::load_page(&db_pool, &channel, before_id, HISTORY_PAGE_SIZE) {
+                Ok(page) => page,
+                Err(err) => {
+                    callback(Err(err.to_string()));
+                    return;
+                }
+            };
+
+            // a short page means we reached the beginning
+            let cursor = page
+                .first()
+                .map(|event| event.id)
+                .filter(|_| page.len() as i64 == HISTORY_PAGE_SIZE);
+            let events = page.iter().filter_map(ChatEvent::to_event).collect();
+
+            callback(Ok((events, cursor)));
+        });
+    });
+}
+
 pub fn show_chat_ui(ui: &mut egui::Ui, state: &mut AppState) {
     update_regex_cache(state);
 
     render_chat_header(ui, state);
     ui.separator();
+
+    let started_at = Instant::now();
     render_chat_history(ui, state);
+    state.chat.render_time = state.chat.render_time.mul_f64(0.95) + started_at.elapsed().mul_f64(0.05);
+
     ui.separator();
     render_chat_footer(ui, state);
 }
@@ -0,0 +1,253 @@
+use chrono::{DateTime, Local, TimeZone, Utc};
+use eframe::egui::{self, Color32, Grid, RichText, ScrollArea, TextEdit, Vec2, Window};
+use tracing::warn;
+use twitch_irc::message::TwitchUserBasics;
+
+use crate::{
+    models::{
+        SqlitePool,
+        chat_user::{ChatUser, ChatUserName, UserNote},
+        mod_log::{ModAction, ModLogEntry},
+    },
+    twitch::api::twitch_get_user_profile,
+    ui::state::AppState,
+    workers::asset::AssetKey,
+};
+
+const AVATAR_SIZE: f32 = 64.0;
+
+/// The user card opened from the sender menu of a chat message.
+pub struct UserProfile {
+    pub user_id: String,
+    pub login: String,
+    pub name: String,
+    /// Unset if we never saw the user chatting before.
+    pub user: Option<ChatUser>,
+    pub prior_names: Vec<ChatUserName>,
+    pub mod_log: Vec<ModLogEntry>,
+    pub notes: String,
+    pub saved_notes: String,
+    /// Unset until helix answered.
+    pub twitch: Option<TwitchProfile>,
+}
+
+pub struct TwitchProfile {
+    pub created_at: Option<String>,
+    pub followed_at: Option<String>,
+}
+
+impl UserProfile {
+    pub fn open(state: &AppState, sender: &TwitchUserBasics) -> Self {
+        let mut profile = Self {
+            user_id: sender.id.clone(),
+            login: sender.login.clone(),
+            name: sender.name.clone(),
+            user: None,
+            prior_names: Vec::new(),
+            mod_log: Vec::new(),
+            notes: String::new(),
+            saved_notes: String::new(),
+            twitch: None,
+        };
+
+        if let Err(err) = profile.load(&state.db_pool) {
+            warn!("Failed to load user profile: {}", err);
+        }
+
+        match (&state.twitch_account, &state.connected_channel_info) {
+            (Some(account), Some(channel)) => {
+                twitch_get_user_profile(&state.channels.ui_diff_tx, account, channel, &profile.user_id);
+            }
+            _ => {
+                profile.twitch = Some(TwitchProfile {
+                    created_at: None,
+                    followed_at: None,
+                });
+            }
+        }
+
+        return profile;
+    }
+
+    fn load(&mut self, db_pool: &SqlitePool) -> anyhow::Result<()> {
+        self.user = ChatUser::load(db_pool, &self.user_id)?;
+        self.prior_names = ChatUserName::load_for_user(db_pool, &self.user_id)?;
+
+        let mut logins = vec![self.login.clone()];
+        logins.extend(self.prior_names.iter().map(|name| name.login.clone()));
+        self.mod_log = ModLogEntry::load_for_user(db_pool, &self.user_id, &logins)?;
+
+        self.notes = UserNote::load(db_pool, &self.user_id)?;
+        self.saved_notes = self.notes.clone();
+
+        return Ok(());
+    }
+}
+
+pub fn render_user_profile(ctx: &egui::Context, state: &mut AppState) {
+    let Some(profile) = &mut state.chat.profile else {
+        return;
+    };
+
+    let mut open = true;
+    let mut save_notes = false;
+    let avatar = state.assets.image(&AssetKey::Avatar(profile.user_id.clone()));
+
+    Window::new(format!("User: {}", profile.name))
+        .id(egui::Id::new("chat_user_profile"))
+        .open(&mut open)
+        .resizable(true)
+        .default_width(360.0)
+        .show(ctx, |ui| {
+            ui.horizontal_top(|ui| {
+                if let Some(avatar) = avatar {
+                    ui.add(avatar.fit_to_exact_size(Vec2::splat(AVATAR_SIZE)).corner_radius(4.0));
+                }
+
+                Grid::new("user_profile_info").num_columns(2).show(ui, |ui| {
+                    ui.label("Login");
+                    ui.label(&profile.login);
+                    ui.end_row();
+
+                    if !profile.prior_names.is_empty() {
+                        ui.label("Previously");
+                        ui.label(
+                            profile
+                                .prior_names
+                                .iter()
+                                .map(|name| name.name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        );
+                        ui.end_row();
+                    }
+
+                    match &profile.user {
+                        Some(user) => {
+                            ui.label("First seen");
+                            ui.label(format_timestamp(user.first_seen));
+                            ui.end_row();
+
+                            ui.label("Last seen");
+                            ui.label(format_timestamp(user.last_seen));
+                            ui.end_row();
+
+                            ui.label("Messages");
+                            ui.label(user.messages.to_string());
+                            ui.end_row();
+                        }
+                        None => {
+                            ui.label("First seen");
+                            ui.label("Just now");
+                            ui.end_row();
+                        }
+                    }
+
+                    match &profile.twitch {
+                        Some(twitch) => {
+                            ui.label("Account created");
+                            ui.label(twitch.created_at.as_deref().map_or(String::from("Unknown"), format_age));
+                            ui.end_row();
+
+                            ui.label("Following");
+                            ui.label(
+                                twitch
+                                    .followed_at
+                                    .as_deref()
+                                    .map_or(String::from("Not following"), format_age),
+                            );
+                            ui.end_row();
+                        }
+                        None => {
+                            ui.label("Account");
+                            ui.spinner();
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+
+            ui.separator();
+            ui.label(RichText::new("Mod log").strong());
+
+            if profile.mod_log.is_empty() {
+                ui.label("No timeouts, bans or deleted messages.");
+            } else {
+                ScrollArea::vertical()
+                    .id_salt("user_profile_mod_log")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for entry in &profile.mod_log {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label(format_timestamp(entry.created_at));
+                                ui.label(RichText::new(describe_mod_action(entry)).color(Color32::LIGHT_RED));
+                                if let Some(message) = &entry.message {
+                                    ui.label(message);
+                                }
+                            });
+                        }
+                    });
+            }
+
+            ui.separator();
+            ui.label(RichText::new("Notes").strong());
+            ui.add(
+                TextEdit::multiline(&mut profile.notes)
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY),
+            );
+
+            ui.add_enabled_ui(profile.notes != profile.saved_notes, |ui| {
+                save_notes = ui.button("Save Notes").clicked();
+            });
+        });
+
+    if save_notes {
+        match UserNote::save(&state.db_pool, &profile.user_id, &profile.notes) {
+            Ok(()) => profile.saved_notes = profile.notes.clone(),
+            Err(err) => warn!("Failed to save user notes: {}", err),
+        }
+    }
+
+    if !open {
+        state.chat.profile = None;
+    }
+}
+
+fn describe_mod_action(entry: &ModLogEntry) -> String {
+    return match entry.action() {
+        Some(ModAction::Timeout) => format!(
+            "Timed out for {}s in {}",
+            entry.duration_secs.unwrap_or(0),
+            entry.channel
+        ),
+        Some(ModAction::Ban) => format!("Banned in {}", entry.channel),
+        Some(ModAction::DeleteMessage) => format!("Message deleted in {}:", entry.channel),
+        None => entry.action.clone(),
+    };
+}
+
+fn format_timestamp(timestamp_millis: i64) -> String {
+    return match Local.timestamp_millis_opt(timestamp_millis).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => String::new(),
+    };
+}
+
+/// Formats an RFC 3339 timestamp as the date plus how long ago it was.
+fn format_age(timestamp: &str) -> String {
+    let Ok(time) = DateTime::parse_from_rfc3339(timestamp) else {
+        return timestamp.to_owned();
+    };
+
+    let days = (Utc::now() - time.with_timezone(&Utc)).num_days();
+    let age = if days >= 365 {
+        format!("{} years, {} months", days / 365, days % 365 / 30)
+    } else if days >= 30 {
+        format!("{} months", days / 30)
+    } else {
+        format!("{days} days")
+    };
+
+    return format!("{} ({age})", time.with_timezone(&Local).format("%Y-%m-%d"));
+}
@@ -3,6 +3,8 @@ pub mod chat;
 pub mod database;
 pub mod docs;
 pub mod logs;
+pub mod redemptions;
+pub mod search;
 pub mod settings;
 pub mod stats;
 
@@ -15,15 +17,18 @@ use crate::ui::{
     state::AppState,
     tabs::{
         actions::show_actions_ui, chat::show_chat_ui, database::show_database_ui, docs::show_docs_ui,
-        logs::show_logs_ui, settings::show_settings_ui, stats::show_stats_ui,
+        logs::show_logs_ui, redemptions::show_redemptions_ui, search::show_search_ui, settings::show_settings_ui,
+        stats::show_stats_ui,
     },
 };
 
-#[derive(Clone, Serialize, Deserialize, Display, EnumIter, EnumString)]
+#[derive(Clone, PartialEq, Serialize, Deserialize, Display, EnumIter, EnumString)]
 pub enum Tabs {
     Chat,
+    Search,
     Stats,
     Actions,
+    Redemptions,
     Database,
     Logs,
     Settings,
@@ -44,8 +49,10 @@ impl egui_dock::TabViewer for TabViewer<'_> {
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
         match tab {
             Tabs::Chat => show_chat_ui(ui, self.state),
+            Tabs::Search => show_search_ui(ui, self.state),
             Tabs::Stats => show_stats_ui(ui, self.state),
             Tabs::Actions => show_actions_ui(ui, self.state),
+            Tabs::Redemptions => show_redemptions_ui(ui, self.state),
             Tabs::Database => show_database_ui(ui, self.state),
             Tabs::Logs => show_logs_ui(ui, self.state),
             Tabs::Settings => show_settings_ui(ui, self.state),
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use eframe::egui::{self, Color32, ComboBox, Grid, RichText, ScrollArea};
+use strum::IntoEnumIterator;
+use tracing::warn;
+use twitch_api::helix::points::CustomRewardRedemptionStatus;
+
+use crate::{
+    models::{
+        SqlitePool,
+        action::Action,
+        reward_rule::{AutoFulfill, RewardRule},
+    },
+    twitch::{
+        api::{twitch_get_redemption_queue, twitch_update_redemption_status},
+        types::{ChannelReward, PendingRedemption},
+    },
+    ui::state::AppState,
+    workers::action::ActionWorkerMessage,
+};
+
+pub struct RedemptionsState {
+    pub rewards: Vec<ChannelReward>,
+    pub redemptions: Vec<PendingRedemption>,
+    /// Rules by reward id, rewards without a rule are never auto-fulfilled.
+    pub rules: HashMap<String, RewardRule>,
+    pub actions: Vec<Action>,
+    pub needs_refresh: bool,
+    pub is_loading: bool,
+}
+
+impl RedemptionsState {
+    pub fn new(db_pool: &SqlitePool) -> Self {
+        let rules = match RewardRule::load_all(db_pool) {
+            Ok(rules) => rules.into_iter().map(|rule| (rule.reward_id.clone(), rule)).collect(),
+            Err(err) => {
+                warn!("Failed to load reward rules: {}", err);
+                HashMap::new()
+            }
+        };
+
+        return Self {
+            rewards: Vec::new(),
+            redemptions: Vec::new(),
+            rules,
+            actions: Vec::new(),
+            needs_refresh: true,
+            is_loading: false,
+        };
+    }
+
+    pub fn auto_fulfill(&self, reward_id: &str) -> AutoFulfill {
+        return self
+            .rules
+            .get(reward_id)
+            .map(RewardRule::auto_fulfill)
+            .unwrap_or_default();
+    }
+
+    /// Clears everything belonging to the previous channel.
+    pub fn reset(&mut self) {
+        self.rewards.clear();
+        self.redemptions.clear();
+        self.needs_refresh = true;
+        self.is_loading = false;
+    }
+}
+
+/// Marks a redemption as fulfilled or canceled, it leaves the queue once twitch confirmed it.
+pub fn resolve_redemption(state: &AppState, redemption: &PendingRedemption, status: CustomRewardRedemptionStatus) {
+    let (Some(account), Some(channel)) = (&state.twitch_account, &state.connected_channel_info) else {
+        return;
+    };
+
+    twitch_update_redemption_status(&state.channels.ui_diff_tx, account, channel, redemption, status);
+}
+
+pub fn show_redemptions_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    let (Some(account), Some(channel)) = (&state.twitch_account, &state.connected_channel_info) else {
+        ui.label("Link your account and join your channel to manage redemptions.");
+        return;
+    };
+
+    if state.redemptions.needs_refresh {
+        state.redemptions.needs_refresh = false;
+        state.redemptions.is_loading = true;
+
+        twitch_get_redemption_queue(&state.channels.ui_diff_tx, account, channel);
+
+        match Action::load_all(&state.db_pool) {
+            Ok(actions) => state.redemptions.actions = actions,
+            Err(err) => warn!("Failed to load actions: {}", err),
+        }
+    }
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(!state.redemptions.is_loading, egui::Button::new("Refresh"))
+            .clicked()
+        {
+            state.redemptions.needs_refresh = true;
+        }
+
+        if state.redemptions.is_loading {
+            ui.spinner();
+        }
+    });
+
+    ui.separator();
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        egui::CollapsingHeader::new(format!("Pending ({})", state.redemptions.redemptions.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                render_queue(ui, state);
+            });
+
+        egui::CollapsingHeader::new("Rewards")
+            .default_open(true)
+            .show(ui, |ui| {
+                render_rules(ui, state);
+            });
+    });
+}
+
+fn render_queue(ui: &mut egui::Ui, state: &mut AppState) {
+    let redemptions = &state.redemptions;
+
+    if redemptions.redemptions.is_empty() {
+        ui.label("No pending redemptions.");
+        return;
+    }
+
+    let mut resolved = None;
+
+    Grid::new("redemption_queue")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            for redemption in &redemptions.redemptions {
+                let is_manageable = redemptions
+                    .rewards
+                    .iter()
+                    .find(|reward| reward.id == redemption.reward_id)
+                    .is_none_or(|reward| reward.is_manageable);
+
+                ui.label(&redemption.redeemed_at);
+                ui.label(RichText::new(&redemption.user_name).strong());
+                ui.label(format!("{} ({})", redemption.reward_title, redemption.cost));
+                ui.label(&redemption.user_input);
+
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(is_manageable, |ui| {
+                        if ui.button("Fulfill").clicked() {
+                            resolved = Some((redemption.clone(), CustomRewardRedemptionStatus::Fulfilled));
+                        }
+
+                        if ui.button("Cancel").on_hover_text("Refunds the points.").clicked() {
+                            resolved = Some((redemption.clone(), CustomRewardRedemptionStatus::Canceled));
+                        }
+                    })
+                    .response
+                    .on_disabled_hover_text("Only rewards created by this app can be managed here.");
+                });
+                ui.end_row();
+            }
+        });
+
+    if let Some((redemption, status)) = resolved {
+        resolve_redemption(state, &redemption, status);
+    }
+}
+
+fn render_rules(ui: &mut egui::Ui, state: &mut AppState) {
+    let redemptions = &mut state.redemptions;
+
+    if redemptions.rewards.is_empty() {
+        ui.label("No rewards found.");
+        return;
+    }
+
+    let mut changed = None;
+
+    Grid::new("reward_rules").num_columns(3).striped(true).show(ui, |ui| {
+        ui.label(RichText::new("Reward").strong());
+        ui.label(RichText::new("Auto-fulfill").strong());
+        ui.label(RichText::new("Action").strong());
+        ui.end_row();
+
+        for reward in &redemptions.rewards {
+            let mut rule = redemptions
+                .rules
+                .get(&reward.id)
+                .cloned()
+                .unwrap_or_else(|| RewardRule {
+                    reward_id: reward.id.clone(),
+                    auto_fulfill: AutoFulfill::default().to_string(),
+                    action_id: None,
+                });
+            let mut auto_fulfill = rule.auto_fulfill();
+
+            let title = format!("{} ({})", reward.title, reward.cost);
+            if reward.is_manageable {
+                ui.label(title);
+            } else {
+                ui.label(RichText::new(title).color(Color32::GRAY))
+                    .on_hover_text("Created outside of this app, redemptions can not be fulfilled from here.");
+            }
+
+            ui.add_enabled_ui(reward.is_manageable, |ui| {
+                ComboBox::from_id_salt(("reward_auto_fulfill", &reward.id))
+                    .selected_text(auto_fulfill_label(auto_fulfill))
+                    .show_ui(ui, |ui| {
+                        for option in AutoFulfill::iter() {
+                            ui.selectable_value(&mut auto_fulfill, option, auto_fulfill_label(option));
+                        }
+                    });
+            });
+
+            let selected_action = rule
+                .action_id
+                .and_then(|id| redemptions.actions.iter().find(|action| action.id == id))
+                .map_or("None", |action| action.name.as_str());
+
+            let mut action_id = rule.action_id;
+            ComboBox::from_id_salt(("reward_action", &reward.id))
+                .selected_text(selected_action)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut action_id, None, "None");
+                    for action in &redemptions.actions {
+                        ui.selectable_value(&mut action_id, Some(action.id), &action.name);
+                    }
+                });
+            ui.end_row();
+
+            if auto_fulfill != rule.auto_fulfill() || action_id != rule.action_id {
+                rule.auto_fulfill = auto_fulfill.to_string();
+                rule.action_id = action_id;
+                changed = Some(rule);
+            }
+        }
+    });
+
+    if let Some(rule) = changed {
+        if let Err(err) = RewardRule::upsert(&state.db_pool, &rule) {
+            warn!("Failed to save reward rule: {}", err);
+            return;
+        }
+
+        state.redemptions.rules.insert(rule.reward_id.clone(), rule);
+        state
+            .channels
+            .action_worker_message_tx
+            .send(ActionWorkerMessage::ReloadRewardRules)
+            .unwrap();
+    }
+}
+
+fn auto_fulfill_label(auto_fulfill: AutoFulfill) -> &'static str {
+    return match auto_fulfill {
+        AutoFulfill::Never => "Never",
+        AutoFulfill::Always => "Always",
+        AutoFulfill::OnActionSuccess => "When the action succeeds",
+    };
+}
@@ -0,0 +1,497 @@
+use std::sync::mpsc;
+
+use chrono::{Local, NaiveDate, TimeZone};
+use eframe::egui::{self, Color32, ComboBox, Frame, Key, RichText, ScrollArea, Stroke, TextEdit, Ui};
+use strum::IntoEnumIterator;
+use tracing::warn;
+
+use crate::{
+    models::{
+        SqlitePool,
+        chat_event::{ChatEvent, ChatEventStatus, ChatRole, ChatSearch, ChatSearchStatus},
+    },
+    twitch::types::TwitchEvent,
+    ui::{
+        state::{AppState, AppStateDiff},
+        tabs::chat::{
+            history::render_chat_event,
+            message::{ChatMessageActions, ChatMessageContext},
+            profile::UserProfile,
+        },
+    },
+};
+
+const PAGE_SIZE: i64 = 100;
+/// How many events before and after a hit are shown around it.
+const CONTEXT_RADIUS: i64 = 25;
+
+#[derive(Clone)]
+pub struct SearchHit {
+    pub row: ChatEvent,
+    /// Unset if the saved event could not be parsed anymore.
+    pub event: Option<TwitchEvent>,
+}
+
+impl From<ChatEvent> for SearchHit {
+    fn from(row: ChatEvent) -> Self {
+        return Self {
+            event: row.to_event(),
+            row,
+        };
+    }
+}
+
+/// The chat around a search hit.
+#[derive(Clone)]
+pub struct SearchContext {
+    pub hit_id: i32,
+    pub channel: String,
+    pub events: Vec<SearchHit>,
+    pub scroll_to_hit: bool,
+}
+
+pub struct SearchState {
+    pub query: ChatSearch,
+    /// `YYYY-MM-DD`, the whole day is included.
+    pub since: String,
+    pub until: String,
+    pub channels: Vec<String>,
+    pub needs_refresh: bool,
+
+    pub results: Vec<SearchHit>,
+    pub has_more: bool,
+    pub has_searched: bool,
+    pub is_searching: bool,
+    /// Counts the searches, so results of outdated ones are dropped.
+    pub generation: u64,
+    pub error: Option<String>,
+    /// Shown instead of the results while set.
+    pub context: Option<SearchContext>,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        return Self {
+            query: ChatSearch::default(),
+            since: String::new(),
+            until: String::new(),
+            channels: Vec::new(),
+            needs_refresh: true,
+            results: Vec::new(),
+            has_more: false,
+            has_searched: false,
+            is_searching: false,
+            generation: 0,
+            error: None,
+            context: None,
+        };
+    }
+}
+
+impl SearchState {
+    fn refresh(&mut self, db_pool: &SqlitePool) {
+        self.needs_refresh = false;
+
+        match ChatEvent::channels(db_pool) {
+            Ok(channels) => self.channels = channels,
+            Err(err) => warn!("Failed to load chat channels: {}", err),
+        }
+    }
+
+    /// Runs the query, or loads the next page of it with `more`. The results arrive as an `AppStateDiff`.
+    fn search(&mut self, db_pool: &SqlitePool, diff_tx: &mpsc::Sender<AppStateDiff>, more: bool) {
+        let (Ok(since), Ok(until)) = (parse_day(&self.since, 0), parse_day(&self.until, 1)) else {
+            self.error = Some(String::from("Dates have to look like 2025-01-31."));
+            return;
+        };
+
+        self.query.since = since;
+        self.query.until = until;
+        self.error = None;
+        self.context = None;
+        self.has_searched = true;
+        self.is_searching = true;
+        self.generation += 1;
+
+        if !more {
+            self.results.clear();
+            self.has_more = false;
+        }
+
+        let before_id = self.results.last().map(|hit| hit.row.id);
+        let generation = self.generation;
+        let query = self.query.clone();
+        let db_pool = db_pool.clone();
+        let diff_tx = diff_tx.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let hits = ChatEvent::search(&db_pool, &query, before_id, PAGE_SIZE)
+                .map(|rows| rows.into_iter().map(SearchHit::from).collect())
+                .map_err(|err| {
+                    warn!("Failed to search chat history: {}", err);
+                    err.to_string()
+                });
+
+            diff_tx
+                .send(AppStateDiff::SearchResultsLoaded { generation, hits })
+                .unwrap();
+        });
+    }
+
+    pub fn apply_results(&mut self, generation: u64, hits: Result<Vec<SearchHit>, String>) {
+        if generation != self.generation {
+            return;
+        }
+
+        self.is_searching = false;
+
+        match hits {
+            Ok(hits) => {
+                self.has_more = hits.len() as i64 == PAGE_SIZE;
+                self.results.extend(hits);
+            }
+            Err(err) => {
+                self.error = Some(err);
+                self.has_more = false;
+            }
+        }
+    }
+
+    fn open_context(&mut self, db_pool: &SqlitePool, diff_tx: &mpsc::Sender<AppStateDiff>, hit_index: usize) {
+        let Some(hit) = self.results.get(hit_index) else {
+            return;
+        };
+
+        // dropped if another search started in the meantime
+        let row = hit.row.clone();
+        let generation = self.generation;
+        let db_pool = db_pool.clone();
+        let diff_tx = diff_tx.clone();
+
+        tokio::task::spawn_blocking(move || match ChatEvent::load_context(&db_pool, &row, CONTEXT_RADIUS) {
+            Ok(rows) => {
+                let context = SearchContext {
+                    hit_id: row.id,
+                    channel: row.channel.clone(),
+                    events: rows.into_iter().map(SearchHit::from).collect(),
+                    scroll_to_hit: true,
+                };
+
+                diff_tx
+                    .send(AppStateDiff::SearchContextLoaded { generation, context })
+                    .unwrap();
+            }
+            Err(err) => warn!("Failed to load chat history: {}", err),
+        });
+    }
+
+    pub fn apply_context(&mut self, generation: u64, context: SearchContext) {
+        if generation == self.generation {
+            self.context = Some(context);
+        }
+    }
+}
+
+pub fn show_search_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    if state.search.needs_refresh {
+        state.search.refresh(&state.db_pool);
+    }
+
+    let mut run_search = false;
+
+    ui.horizontal(|ui| {
+        let search = &mut state.search;
+
+        let text = ui.add(
+            TextEdit::singleline(&mut search.query.text)
+                .hint_text("Words")
+                .desired_width(250.0),
+        );
+        let user = ui.add(
+            TextEdit::singleline(&mut search.query.user)
+                .hint_text("User")
+                .desired_width(150.0),
+        );
+        let entered = ui.input(|input| input.key_pressed(Key::Enter));
+        run_search |= (text.lost_focus() || user.lost_focus()) && entered;
+        run_search |= ui.button("Search").clicked();
+    });
+
+    ui.horizontal(|ui| {
+        let search = &mut state.search;
+
+        ComboBox::from_id_salt("search_channel")
+            .selected_text(search.query.channel.as_deref().unwrap_or("All channels"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut search.query.channel, None, "All channels");
+                for channel in &search.channels {
+                    ui.selectable_value(&mut search.query.channel, Some(channel.clone()), channel);
+                }
+            });
+
+        ComboBox::from_id_salt("search_role")
+            .selected_text(
+                search
+                    .query
+                    .role
+                    .map_or(String::from("Anyone"), |role| role.to_string()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut search.query.role, None, "Anyone");
+                for role in ChatRole::iter() {
+                    ui.selectable_value(&mut search.query.role, Some(role), role.to_string());
+                }
+            });
+
+        ComboBox::from_id_salt("search_status")
+            .selected_text(search.query.status.to_string())
+            .show_ui(ui, |ui| {
+                for status in ChatSearchStatus::iter() {
+                    ui.selectable_value(&mut search.query.status, status, status.to_string());
+                }
+            });
+
+        ui.label("From");
+        date_edit(ui, &mut search.since);
+        ui.label("To");
+        date_edit(ui, &mut search.until);
+
+        if ui.button("Refresh Channels").clicked() {
+            search.needs_refresh = true;
+        }
+    });
+
+    if run_search {
+        state.search.search(&state.db_pool, &state.channels.ui_diff_tx, false);
+    }
+
+    if let Some(error) = &state.search.error {
+        ui.colored_label(Color32::RED, error);
+    }
+
+    ui.separator();
+
+    if state.search.context.is_some() {
+        render_context(ui, state);
+    } else {
+        render_results(ui, state);
+    }
+}
+
+fn render_results(ui: &mut Ui, state: &mut AppState) {
+    let mut open_context = None;
+    let mut load_more = false;
+    let mut actions = ChatMessageActions::default();
+
+    let search = &state.search;
+
+    if search.results.is_empty() {
+        ui.centered_and_justified(|ui| {
+            if search.is_searching {
+                ui.spinner();
+                return;
+            }
+
+            ui.label(if search.has_searched {
+                "Nothing found."
+            } else {
+                "Search everything that was said in chat."
+            });
+        });
+        return;
+    }
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for (index, hit) in search.results.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format_timestamp(hit.row.created_at)).weak());
+                ui.label(RichText::new(format!("#{}", hit.row.channel)).weak());
+
+                if ui.small_button("Context").clicked() {
+                    open_context = Some(index);
+                }
+            });
+
+            let channel = state
+                .connected_channel_info
+                .clone()
+                .filter(|info| info.broadcaster_login.as_str() == hit.row.channel);
+            let context = ChatMessageContext {
+                diff_tx: &state.channels.ui_diff_tx,
+                account: &state.twitch_account,
+                channel: &channel,
+                logged_in_user_name: None,
+                show_timestamps: false,
+                emote_size: state.chat.emote_size,
+                assets: &state.assets,
+                third_party_emotes: &state.chat.third_party_emotes,
+            };
+
+            match &hit.event {
+                Some(event) => render_chat_event(ui, event, &context, &mut actions),
+                None => render_row(ui, &hit.row),
+            }
+
+            ui.separator();
+        }
+
+        if search.is_searching {
+            ui.spinner();
+        } else if search.has_more && ui.button("Load More").clicked() {
+            load_more = true;
+        }
+    });
+
+    if let Some(index) = open_context {
+        state
+            .search
+            .open_context(&state.db_pool, &state.channels.ui_diff_tx, index);
+    }
+
+    if load_more {
+        state.search.search(&state.db_pool, &state.channels.ui_diff_tx, true);
+    }
+
+    apply_actions(state, actions);
+}
+
+fn render_context(ui: &mut Ui, state: &mut AppState) {
+    let Some(search_context) = &mut state.search.context else {
+        return;
+    };
+
+    let mut close = false;
+    let mut actions = ChatMessageActions::default();
+
+    let hit = search_context
+        .events
+        .iter()
+        .find(|event| event.row.id == search_context.hit_id);
+
+    ui.horizontal(|ui| {
+        close = ui.button("Back to Results").clicked();
+
+        if let Some(hit) = hit {
+            ui.label(format!(
+                "Around a message in #{} on {}",
+                search_context.channel,
+                format_timestamp(hit.row.created_at)
+            ));
+        }
+    });
+
+    ui.separator();
+
+    let channel = state
+        .connected_channel_info
+        .clone()
+        .filter(|info| info.broadcaster_login.as_str() == search_context.channel);
+    let context = ChatMessageContext {
+        diff_tx: &state.channels.ui_diff_tx,
+        account: &state.twitch_account,
+        channel: &channel,
+        logged_in_user_name: None,
+        show_timestamps: true,
+        emote_size: state.chat.emote_size,
+        assets: &state.assets,
+        third_party_emotes: &state.chat.third_party_emotes,
+    };
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for hit in &search_context.events {
+            let is_hit = hit.row.id == search_context.hit_id;
+
+            let response = Frame::new()
+                .stroke(if is_hit {
+                    Stroke::new(1.0_f32, Color32::GOLD)
+                } else {
+                    Stroke::NONE
+                })
+                .inner_margin(2.0)
+                .show(ui, |ui| match &hit.event {
+                    Some(event) => render_chat_event(ui, event, &context, &mut actions),
+                    None => render_row(ui, &hit.row),
+                })
+                .response;
+
+            if is_hit && search_context.scroll_to_hit {
+                response.scroll_to_me(Some(egui::Align::Center));
+            }
+        }
+    });
+    search_context.scroll_to_hit = false;
+
+    if close {
+        state.search.context = None;
+    }
+
+    apply_actions(state, actions);
+}
+
+/// Fallback for saved events that can no longer be parsed.
+fn render_row(ui: &mut Ui, row: &ChatEvent) {
+    ui.horizontal_wrapped(|ui| {
+        if let Some(status) = row.status() {
+            ui.colored_label(
+                Color32::RED,
+                match status {
+                    ChatEventStatus::Deleted => "[DELETED]",
+                    ChatEventStatus::TimedOut => "[TIMED OUT]",
+                    ChatEventStatus::Banned => "[BANNED]",
+                },
+            );
+        }
+
+        if let Some(user_name) = &row.user_name {
+            ui.label(RichText::new(format!("{user_name}:")).strong());
+        }
+
+        ui.label(row.text.as_deref().unwrap_or(&row.kind));
+    });
+}
+
+fn apply_actions(state: &mut AppState, actions: ChatMessageActions) {
+    if let Some(user_query) = actions.user_query {
+        state.search.query.user = user_query;
+        state.search.search(&state.db_pool, &state.channels.ui_diff_tx, false);
+    }
+
+    if let Some(sender) = actions.open_profile {
+        state.chat.profile = Some(UserProfile::open(state, &sender));
+    }
+}
+
+fn date_edit(ui: &mut Ui, date: &mut String) {
+    let is_valid = parse_day(date, 0).is_ok();
+
+    let mut edit = TextEdit::singleline(date).hint_text("YYYY-MM-DD").desired_width(90.0);
+    if !is_valid {
+        edit = edit.text_color(Color32::RED);
+    }
+
+    ui.add(edit);
+}
+
+/// Local midnight of the day `offset_days` after `date`, unset for an empty `date`.
+fn parse_day(date: &str, offset_days: u64) -> Result<Option<i64>, ()> {
+    let date = date.trim();
+    if date.is_empty() {
+        return Ok(None);
+    }
+
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ())?;
+    let date = date.checked_add_days(chrono::Days::new(offset_days)).ok_or(())?;
+    let midnight = Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).ok_or(())?)
+        .earliest()
+        .ok_or(())?;
+
+    return Ok(Some(midnight.timestamp_millis()));
+}
+
+fn format_timestamp(timestamp_millis: i64) -> String {
+    return match Local.timestamp_millis_opt(timestamp_millis).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => String::new(),
+    };
+}
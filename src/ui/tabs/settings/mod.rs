@@ -1,6 +1,12 @@
-use eframe::egui::{self, Color32, RichText, TextEdit};
+use eframe::egui::{self, Color32, DragValue, RichText, TextEdit};
+use strum::IntoEnumIterator;
 
-use crate::ui::state::{AppState, AppStateDiff};
+use crate::{
+    models::action_log::ActionLogRetention,
+    twitch::third_party::EmoteProvider,
+    ui::state::{AppState, AppStateDiff},
+    workers::action::ActionWorkerMessage,
+};
 
 const GIT_COMMIT_HASH: &str = include_str!("../../../../.git/refs/heads/master");
 
@@ -8,6 +14,7 @@ const GIT_COMMIT_HASH: &str = include_str!("../../../../.git/refs/heads/master")
 pub struct SettingsState {
     pub channel_name: String,
     pub channel_name_error: Option<String>,
+    pub action_log_retention: ActionLogRetention,
 }
 
 pub fn show_settings_ui(ui: &mut egui::Ui, state: &mut AppState) {
@@ -84,6 +91,81 @@ pub fn show_settings_ui(ui: &mut egui::Ui, state: &mut AppState) {
 
     ui.separator();
 
+    ui.label(RichText::new("Chat").strong());
+
+    ui.horizontal(|ui| {
+        ui.label("Keep the newest");
+        ui.add(
+            DragValue::new(&mut state.chat.max_events)
+                .range(500..=100_000)
+                .speed(100),
+        );
+        ui.label("chat events in memory, older ones are loaded from the history when scrolling up");
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Emote size");
+        ui.add(
+            DragValue::new(&mut state.chat.emote_size)
+                .range(16.0..=112.0)
+                .speed(1.0),
+        );
+        ui.label("points");
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Emotes from");
+        for provider in EmoteProvider::iter() {
+            let mut enabled = state.chat.third_party_emotes.enabled.contains(&provider);
+            if ui.checkbox(&mut enabled, provider.to_string()).changed() {
+                state.chat.third_party_emotes.set_enabled(provider, enabled);
+                if enabled {
+                    state.load_third_party_emotes();
+                }
+            }
+        }
+    });
+
+    ui.label(
+        RichText::new(format!(
+            "{} events in memory, {:.2} ms per frame to filter and draw them",
+            state.chat.events.items.len(),
+            state.chat.render_time.as_secs_f64() * 1000.0
+        ))
+        .weak(),
+    );
+
+    ui.separator();
+
+    ui.label(RichText::new("Actions").strong());
+
+    let retention = &mut state.settings.action_log_retention;
+    let mut retention_changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Keep the newest");
+        retention_changed |= ui
+            .add(
+                DragValue::new(&mut retention.max_entries)
+                    .range(100..=1_000_000)
+                    .speed(100),
+            )
+            .changed();
+        ui.label("action log entries, for at most");
+        retention_changed |= ui
+            .add(DragValue::new(&mut retention.max_days).range(1..=3650).suffix(" days"))
+            .changed();
+    });
+
+    if retention_changed {
+        state
+            .channels
+            .action_worker_message_tx
+            .send(ActionWorkerMessage::SetLogRetention(*retention))
+            .unwrap();
+    }
+
+    ui.separator();
+
     ui.label(RichText::new("Storage").strong());
 
     ui.horizontal(|ui| {
@@ -1,10 +1,400 @@
-use eframe::egui;
+use chrono::{Local, TimeZone, Utc};
+use eframe::egui::{self, Color32, ComboBox, Grid, RichText, ScrollArea, SidePanel, Ui};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tracing::warn;
 
-use crate::ui::state::AppState;
+use crate::{
+    models::{
+        SqlitePool,
+        stats::{LeaderboardEntry, StatsChatter, StatsEmote, StatsMinute, StreamSession},
+    },
+    ui::state::AppState,
+};
 
-#[derive(Default)]
-pub struct StatsState {}
+const LEADERBOARD_SIZE: i64 = 10;
 
-pub fn show_stats_ui(ui: &mut egui::Ui, _state: &mut AppState) {
-    ui.label("Coming Soon...");
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum StatsRange {
+    #[strum(to_string = "Last 24 hours")]
+    Day,
+    #[default]
+    #[strum(to_string = "Last 7 days")]
+    Week,
+    #[strum(to_string = "Last 30 days")]
+    Month,
+    #[strum(to_string = "All time")]
+    All,
+}
+
+impl StatsRange {
+    fn since(self) -> Option<i64> {
+        let days = match self {
+            StatsRange::Day => 1,
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+            StatsRange::All => return None,
+        };
+
+        return Some((Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+    }
+}
+
+/// Totals of the selected sessions.
+#[derive(Debug, Default, Clone)]
+pub struct StatsSummary {
+    pub messages: i64,
+    pub unique_chatters: i64,
+    pub first_time_chatters: i64,
+    pub peak_messages_per_minute: i64,
+    pub emotes: i64,
+    pub subs: i64,
+    pub bits: i64,
+    pub mod_actions: i64,
+}
+
+pub struct SessionListEntry {
+    pub session: StreamSession,
+    pub messages: i64,
+}
+
+pub struct StatsState {
+    pub channel: Option<String>,
+    pub range: StatsRange,
+    /// Only this session is shown, all sessions in the range otherwise.
+    pub selected_session: Option<i32>,
+    pub needs_refresh: bool,
+
+    pub channels: Vec<String>,
+    pub sessions: Vec<SessionListEntry>,
+    pub minutes: Vec<StatsMinute>,
+    pub summary: StatsSummary,
+    pub top_chatters: Vec<LeaderboardEntry>,
+    pub top_emotes: Vec<LeaderboardEntry>,
+    pub top_cheerers: Vec<LeaderboardEntry>,
+}
+
+impl Default for StatsState {
+    fn default() -> Self {
+        return Self {
+            channel: None,
+            range: StatsRange::default(),
+            selected_session: None,
+            needs_refresh: true,
+            channels: Vec::new(),
+            sessions: Vec::new(),
+            minutes: Vec::new(),
+            summary: StatsSummary::default(),
+            top_chatters: Vec::new(),
+            top_emotes: Vec::new(),
+            top_cheerers: Vec::new(),
+        };
+    }
+}
+
+impl StatsState {
+    fn refresh(&mut self, db_pool: &SqlitePool) {
+        self.needs_refresh = false;
+
+        match StreamSession::channels(db_pool) {
+            Ok(channels) => self.channels = channels,
+            Err(err) => warn!("Failed to load stats channels: {}", err),
+        }
+
+        let sessions = match StreamSession::load_since(db_pool, self.channel.as_deref(), self.range.since()) {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                warn!("Failed to load stream sessions: {}", err);
+                Vec::new()
+            }
+        };
+
+        if self
+            .selected_session
+            .is_some_and(|id| !sessions.iter().any(|session| session.id == id))
+        {
+            self.selected_session = None;
+        }
+
+        let session_ids = sessions.iter().map(|session| session.id).collect::<Vec<_>>();
+        let minutes = StatsMinute::load_for_sessions(db_pool, &session_ids).unwrap_or_else(|err| {
+            warn!("Failed to load stats: {}", err);
+            Vec::new()
+        });
+
+        self.sessions = sessions
+            .into_iter()
+            .map(|session| SessionListEntry {
+                messages: minutes
+                    .iter()
+                    .filter(|minute| minute.session_id == session.id)
+                    .map(|minute| i64::from(minute.messages))
+                    .sum(),
+                session,
+            })
+            .collect();
+
+        let selected_ids = match self.selected_session {
+            Some(id) => vec![id],
+            None => session_ids,
+        };
+
+        self.minutes = minutes
+            .into_iter()
+            .filter(|minute| selected_ids.contains(&minute.session_id))
+            .collect();
+
+        if let Err(err) = self.load_totals(db_pool, &selected_ids) {
+            warn!("Failed to load stats: {}", err);
+        }
+    }
+
+    fn load_totals(&mut self, db_pool: &SqlitePool, session_ids: &[i32]) -> anyhow::Result<()> {
+        let (unique_chatters, first_time_chatters) = StatsChatter::count_unique(db_pool, session_ids)?;
+
+        let mut summary = StatsSummary {
+            unique_chatters,
+            first_time_chatters,
+            ..StatsSummary::default()
+        };
+
+        for minute in &self.minutes {
+            summary.messages += i64::from(minute.messages);
+            summary.peak_messages_per_minute = summary.peak_messages_per_minute.max(i64::from(minute.messages));
+            summary.emotes += i64::from(minute.emotes);
+            summary.subs += i64::from(minute.subs);
+            summary.bits += i64::from(minute.bits);
+            summary.mod_actions += i64::from(minute.mod_actions);
+        }
+
+        self.summary = summary;
+        self.top_chatters = StatsChatter::top_by_messages(db_pool, session_ids, LEADERBOARD_SIZE)?;
+        self.top_cheerers = StatsChatter::top_by_bits(db_pool, session_ids, LEADERBOARD_SIZE)?;
+        self.top_emotes = StatsEmote::top_by_uses(db_pool, session_ids, LEADERBOARD_SIZE)?;
+
+        return Ok(());
+    }
+}
+
+pub fn show_stats_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    if state.stats.needs_refresh {
+        state.stats.refresh(&state.db_pool);
+    }
+
+    let stats = &mut state.stats;
+
+    ui.horizontal(|ui| {
+        ComboBox::from_id_salt("stats_channel")
+            .selected_text(stats.channel.as_deref().unwrap_or("All channels"))
+            .show_ui(ui, |ui| {
+                stats.needs_refresh |= ui.selectable_value(&mut stats.channel, None, "All channels").clicked();
+                for channel in &stats.channels {
+                    stats.needs_refresh |= ui
+                        .selectable_value(&mut stats.channel, Some(channel.clone()), channel)
+                        .clicked();
+                }
+            });
+
+        ComboBox::from_id_salt("stats_range")
+            .selected_text(stats.range.to_string())
+            .show_ui(ui, |ui| {
+                for range in StatsRange::iter() {
+                    stats.needs_refresh |= ui
+                        .selectable_value(&mut stats.range, range, range.to_string())
+                        .clicked();
+                }
+            });
+
+        if ui.button("Refresh").clicked() {
+            stats.needs_refresh = true;
+        }
+    });
+
+    ui.separator();
+
+    SidePanel::left("stats_sessions")
+        .resizable(true)
+        .default_width(220.0)
+        .show_inside(ui, |ui| {
+            render_session_list(ui, stats);
+        });
+
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        if stats.sessions.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label("No chat activity recorded in this range yet.");
+            });
+            return;
+        }
+
+        ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            render_summary(ui, &stats.summary);
+            ui.separator();
+            render_charts(ui, &stats.minutes);
+            ui.separator();
+            render_leaderboards(ui, stats);
+        });
+    });
+}
+
+fn render_session_list(ui: &mut Ui, stats: &mut StatsState) {
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        stats.needs_refresh |= ui
+            .selectable_value(&mut stats.selected_session, None, "All sessions")
+            .clicked();
+
+        for entry in &stats.sessions {
+            let session = &entry.session;
+            let started_at = format_timestamp(session.started_at, "%Y-%m-%d %H:%M");
+            let duration = match session.ended_at {
+                Some(ended_at) => format_duration(ended_at - session.started_at),
+                None => String::from("running"),
+            };
+
+            let mut text = RichText::new(format!(
+                "{started_at}  {}\n{duration}, {} messages",
+                session.channel, entry.messages
+            ));
+            if !session.live {
+                text = text.color(Color32::GRAY);
+            }
+
+            stats.needs_refresh |= ui
+                .selectable_value(&mut stats.selected_session, Some(session.id), text)
+                .on_hover_text(if session.live {
+                    "Live stream"
+                } else {
+                    "Chat while offline"
+                })
+                .clicked();
+        }
+    });
+}
+
+fn render_summary(ui: &mut Ui, summary: &StatsSummary) {
+    Grid::new("stats_summary")
+        .num_columns(4)
+        .spacing([24.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Messages");
+            ui.label(RichText::new(summary.messages.to_string()).strong());
+            ui.label("Peak messages/min");
+            ui.label(RichText::new(summary.peak_messages_per_minute.to_string()).strong());
+            ui.end_row();
+
+            ui.label("Unique chatters");
+            ui.label(RichText::new(summary.unique_chatters.to_string()).strong());
+            ui.label("First-time chatters");
+            ui.label(RichText::new(summary.first_time_chatters.to_string()).strong());
+            ui.end_row();
+
+            ui.label("Subs");
+            ui.label(RichText::new(summary.subs.to_string()).strong());
+            ui.label("Bits");
+            ui.label(RichText::new(summary.bits.to_string()).strong());
+            ui.end_row();
+
+            ui.label("Emotes");
+            ui.label(RichText::new(summary.emotes.to_string()).strong());
+            ui.label("Mod actions");
+            ui.label(RichText::new(summary.mod_actions.to_string()).strong());
+            ui.end_row();
+        });
+}
+
+fn render_charts(ui: &mut Ui, minutes: &[StatsMinute]) {
+    ui.heading("Chat velocity");
+    render_chart(
+        ui,
+        "stats_velocity",
+        minutes,
+        &[
+            ("Messages/min", Color32::LIGHT_BLUE, |minute| minute.messages),
+            ("Active chatters", Color32::LIGHT_GREEN, |minute| minute.chatters),
+        ],
+    );
+
+    ui.heading("Events");
+    render_chart(
+        ui,
+        "stats_events",
+        minutes,
+        &[
+            ("First-time chatters", Color32::GOLD, |minute| {
+                minute.first_time_chatters
+            }),
+            ("Subs", Color32::from_rgb(145, 70, 255), |minute| minute.subs),
+            ("Mod actions", Color32::LIGHT_RED, |minute| minute.mod_actions),
+        ],
+    );
+}
+
+type ChartSeries = (&'static str, Color32, fn(&StatsMinute) -> i32);
+
+/// One line per series and session, so the gaps between sessions are not bridged.
+fn render_chart(ui: &mut Ui, id: &str, minutes: &[StatsMinute], series: &[ChartSeries]) {
+    Plot::new(id)
+        .height(180.0)
+        .legend(Legend::default())
+        .link_axis("stats_charts", [true, false])
+        .allow_scroll(false)
+        .include_y(0.0)
+        .x_axis_formatter(|mark, _| format_timestamp(mark.value as i64 * 60_000, "%m-%d %H:%M"))
+        .label_formatter(|name, point| {
+            format!(
+                "{name}\n{}: {}",
+                format_timestamp(point.x as i64 * 60_000, "%Y-%m-%d %H:%M"),
+                point.y
+            )
+        })
+        .show(ui, |plot_ui| {
+            for (name, color, value) in series {
+                for session in minutes.chunk_by(|a, b| a.session_id == b.session_id) {
+                    let points = session
+                        .iter()
+                        .map(|minute| [(minute.minute / 60_000) as f64, f64::from(value(minute))])
+                        .collect::<PlotPoints>();
+
+                    plot_ui.line(Line::new(*name, points).color(*color));
+                }
+            }
+        });
+}
+
+fn render_leaderboards(ui: &mut Ui, stats: &StatsState) {
+    ui.columns(3, |columns| {
+        render_leaderboard(&mut columns[0], "Top chatters", "messages", &stats.top_chatters);
+        render_leaderboard(&mut columns[1], "Top emotes", "uses", &stats.top_emotes);
+        render_leaderboard(&mut columns[2], "Top cheerers", "bits", &stats.top_cheerers);
+    });
+}
+
+fn render_leaderboard(ui: &mut Ui, title: &str, unit: &str, entries: &[LeaderboardEntry]) {
+    ui.heading(title);
+
+    if entries.is_empty() {
+        ui.label("Nothing yet.");
+        return;
+    }
+
+    Grid::new(title).num_columns(3).striped(true).show(ui, |ui| {
+        for (rank, entry) in entries.iter().enumerate() {
+            ui.label(format!("{}.", rank + 1));
+            ui.label(&entry.name);
+            ui.label(format!("{} {unit}", entry.value));
+            ui.end_row();
+        }
+    });
+}
+
+fn format_timestamp(timestamp_millis: i64, format: &str) -> String {
+    return match Local.timestamp_millis_opt(timestamp_millis).single() {
+        Some(time) => time.format(format).to_string(),
+        None => String::new(),
+    };
+}
+
+fn format_duration(millis: i64) -> String {
+    let minutes = millis.max(0) / 60_000;
+    return format!("{}h {:02}m", minutes / 60, minutes % 60);
 }
@@ -1,11 +1,55 @@
-use std::sync::mpsc;
+use std::{sync::mpsc, time::Duration};
 
-use crate::{twitch::types::TwitchEvent, ui::state::AppStateDiff};
+use tracing::warn;
 
-pub fn worker_start_action(event_rx: mpsc::Receiver<TwitchEvent>, _state_diff_tx: mpsc::Sender<AppStateDiff>) {
+use crate::{
+    engine::ActionEngine,
+    models::{SqlitePool, action_log::ActionLogRetention},
+    twitch::types::TwitchEvent,
+    ui::state::AppStateDiff,
+};
+
+#[derive(Debug, Clone)]
+pub enum ActionWorkerMessage {
+    Tick,
+    Reload,
+    ModuleChanged(String),
+    ReloadRewardRules,
+    SetLogRetention(ActionLogRetention),
+}
+
+pub fn worker_start_action(
+    db_pool: SqlitePool,
+    event_rx: mpsc::Receiver<TwitchEvent>,
+    message_rx: mpsc::Receiver<ActionWorkerMessage>,
+    state_diff_tx: mpsc::Sender<AppStateDiff>,
+) {
     tokio::task::spawn_blocking(move || {
-        while let Ok(_event) = event_rx.recv() {
-            //
+        let mut engine = ActionEngine::new(db_pool, state_diff_tx);
+        if let Err(err) = engine.reload() {
+            warn!("Failed to load actions: {}", err);
+        }
+
+        loop {
+            match event_rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(event) => engine.handle_event(&event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            while let Ok(message) = message_rx.try_recv() {
+                match message {
+                    ActionWorkerMessage::Tick => engine.handle_tick(),
+                    ActionWorkerMessage::Reload => {
+                        if let Err(err) = engine.reload() {
+                            warn!("Failed to reload actions: {}", err);
+                        }
+                    }
+                    ActionWorkerMessage::ModuleChanged(name) => engine.recompile_dependents(&name),
+                    ActionWorkerMessage::ReloadRewardRules => engine.reload_reward_rules(),
+                    ActionWorkerMessage::SetLogRetention(retention) => engine.set_log_retention(retention),
+                }
+            }
         }
     });
 }
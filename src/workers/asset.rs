@@ -1,11 +1,707 @@
-use std::sync::mpsc;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::{Arc, mpsc},
+    time::{Duration, Instant},
+};
 
-use crate::{twitch::types::TwitchEvent, ui::state::AppStateDiff};
+use anyhow::Result;
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use sha2::{Digest, Sha256};
+use tokio::{runtime::Handle, sync::Semaphore};
+use tracing::{info, warn};
+use twitch_api::{
+    helix::chat::{BadgeSet, ChatBadge, GetChannelChatBadgesRequest, GetGlobalChatBadgesRequest},
+    types::UserId,
+};
+use twitch_irc::message::{Badge, Emote};
 
-pub fn worker_start_assets(event_rx: mpsc::Receiver<TwitchEvent>, _state_diff_tx: mpsc::Sender<AppStateDiff>) {
+use crate::{
+    models::{SqlitePool, asset_cache::CachedAsset},
+    twitch::{
+        third_party::{EmoteApi, EmoteProvider, ThirdPartyEmote},
+        types::{TwitchAccount, TwitchEvent},
+    },
+    ui::state::AppStateDiff,
+};
+
+const CACHE_DIR: &str = "ruey-cache";
+/// Cached assets are fetched again after this long, avatars and channel badges change every now and then.
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+/// Helix looks up at most this many users at once.
+const MAX_USERS_PER_LOOKUP: usize = 100;
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+/// Assets that failed to load are tried again when they come up after this long.
+pub const ASSET_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Image urls or titles of badges by set id and version.
+pub type BadgeMap = HashMap<(String, String), String>;
+
+/// An image shown in the UI, loaded by the asset worker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AssetKey {
+    Emote(String),
+    /// Channel badges take precedence over global ones with the same set and version, like subscriber badges.
+    Badge {
+        channel_id: String,
+        set_id: String,
+        version: String,
+    },
+    /// The profile image of a user id.
+    Avatar(String),
+    ThirdPartyEmote {
+        provider: EmoteProvider,
+        id: String,
+    },
+}
+
+impl AssetKey {
+    /// Names the asset in the cache and in the texture cache of egui.
+    pub fn uri(&self) -> String {
+        return match self {
+            AssetKey::Emote(emote_id) => format!("asset://emote/{emote_id}"),
+            AssetKey::Badge {
+                channel_id,
+                set_id,
+                version,
+            } => format!("asset://badge/{channel_id}/{set_id}/{version}"),
+            AssetKey::Avatar(user_id) => format!("asset://avatar/{user_id}"),
+            AssetKey::ThirdPartyEmote { provider, id } => format!("asset://emote/{provider}/{id}"),
+        };
+    }
+}
+
+#[derive(Clone)]
+pub enum AssetWorkerMessage {
+    SetAccount(TwitchAccount),
+    /// Loads an asset that did not come up in the events, like the emotes of the chat history, or one the UI dropped.
+    Request(AssetKey),
+    /// Loads the global and channel emotes of the providers, once per session.
+    LoadThirdPartyEmotes {
+        channel_id: String,
+        providers: Vec<EmoteProvider>,
+    },
+}
+
+/// What the tasks started by the worker send back to it.
+enum TaskResult {
+    /// Unset if loading them failed.
+    BadgeSetsLoaded {
+        channel_id: Option<String>,
+        urls: Option<BadgeMap>,
+    },
+    /// Profile image urls of the looked up users, unset for users without one.
+    AvatarsLookedUp(Vec<(String, Option<String>)>),
+    AvatarLookupFailed(Vec<String>),
+    Downloaded {
+        key: AssetKey,
+        bytes: Vec<u8>,
+    },
+    DownloadFailed(AssetKey),
+    ThirdPartyEmotesLoaded {
+        provider: EmoteProvider,
+        channel_id: Option<String>,
+        emotes: Vec<ThirdPartyEmote>,
+    },
+}
+
+enum AssetUrl {
+    Found(String),
+    /// Waiting for helix to tell.
+    Unknown,
+    Missing,
+}
+
+/// Finds the emotes, badges and avatars the chat needs, loads them from the disk cache or downloads them and hands
+/// them to the UI.
+struct AssetLoader {
+    db_pool: SqlitePool,
+    state_diff_tx: mpsc::Sender<AppStateDiff>,
+    runtime: Handle,
+    http: reqwest::Client,
+    downloads: Arc<Semaphore>,
+    result_tx: mpsc::Sender<TaskResult>,
+    result_rx: mpsc::Receiver<TaskResult>,
+    account: Option<TwitchAccount>,
+
+    /// Every asset that was loaded, is being loaded or has no image.
+    seen: HashSet<AssetKey>,
+    /// Assets being downloaded or waiting for their image url.
+    loading: HashSet<AssetKey>,
+    /// When assets failed to load, they are not tried again before [`ASSET_RETRY_DELAY`] passed.
+    failed: HashMap<AssetKey, Instant>,
+    /// Badges and avatars waiting for their image url.
+    unresolved: Vec<AssetKey>,
+    /// Badge image urls by channel id, `None` holds the global badges. Unset while loading.
+    badge_urls: HashMap<Option<String>, Option<BadgeMap>>,
+    /// Profile image urls by user id. Unset while looking the user up.
+    avatar_urls: HashMap<String, Option<Option<String>>>,
+    emote_api: EmoteApi,
+    /// Emote sets loaded or being loaded, `None` for the global ones.
+    third_party_sets: HashSet<(EmoteProvider, Option<String>)>,
+    third_party_urls: HashMap<(EmoteProvider, String), String>,
+}
+
+impl AssetLoader {
+    fn new(db_pool: SqlitePool, state_diff_tx: mpsc::Sender<AppStateDiff>) -> Self {
+        prune_cache(&db_pool);
+
+        let (result_tx, result_rx) = mpsc::channel();
+
+        return Self {
+            db_pool,
+            state_diff_tx,
+            runtime: Handle::current(),
+            http: reqwest::Client::new(),
+            downloads: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+            result_tx,
+            result_rx,
+            account: None,
+            seen: HashSet::new(),
+            loading: HashSet::new(),
+            failed: HashMap::new(),
+            unresolved: Vec::new(),
+            badge_urls: HashMap::new(),
+            avatar_urls: HashMap::new(),
+            emote_api: EmoteApi::default(),
+            third_party_sets: HashSet::new(),
+            third_party_urls: HashMap::new(),
+        };
+    }
+
+    fn handle_event(&mut self, event: &TwitchEvent) {
+        match event {
+            TwitchEvent::Privmsg(msg) => {
+                self.discover(&msg.channel_id, &msg.emotes, &msg.badges);
+            }
+            TwitchEvent::UserNotice(notice) => {
+                self.discover(&notice.channel_id, &notice.emotes, &notice.badges);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_message(&mut self, message: AssetWorkerMessage) {
+        match message {
+            AssetWorkerMessage::SetAccount(account) => self.account = Some(account),
+            AssetWorkerMessage::Request(key) => {
+                // the UI asks again for assets it dropped or that did not arrive, the latter may still be on the way
+                if self.loading.contains(&key) {
+                    return;
+                }
+
+                self.seen.remove(&key);
+                self.want(key);
+            }
+            AssetWorkerMessage::LoadThirdPartyEmotes { channel_id, providers } => {
+                for provider in providers {
+                    for channel_id in [None, Some(channel_id.clone())] {
+                        if self.third_party_sets.insert((provider, channel_id.clone())) {
+                            self.load_third_party_emotes(provider, channel_id);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Avatars are only loaded when the UI asks for them, most chatters never get their profile opened.
+    fn discover(&mut self, channel_id: &str, emotes: &[Emote], badges: &[Badge]) {
+        for emote in emotes {
+            self.want(AssetKey::Emote(emote.id.clone()));
+        }
+
+        for badge in badges {
+            self.want(AssetKey::Badge {
+                channel_id: channel_id.to_owned(),
+                set_id: badge.name.clone(),
+                version: badge.version.clone(),
+            });
+        }
+    }
+
+    fn want(&mut self, key: AssetKey) {
+        if self
+            .failed
+            .get(&key)
+            .is_some_and(|failed_at| failed_at.elapsed() < ASSET_RETRY_DELAY)
+        {
+            return;
+        }
+
+        if !self.seen.insert(key.clone()) {
+            return;
+        }
+        self.failed.remove(&key);
+
+        if let Some(bytes) = self.load_cached(&key) {
+            self.state_diff_tx
+                .send(AppStateDiff::AssetLoaded {
+                    key,
+                    bytes: Arc::from(bytes),
+                })
+                .unwrap();
+            return;
+        }
+
+        match self.url_for(&key) {
+            AssetUrl::Found(url) => {
+                self.loading.insert(key.clone());
+                self.download(key, url);
+            }
+            AssetUrl::Unknown => {
+                self.loading.insert(key.clone());
+                self.unresolved.push(key);
+            }
+            AssetUrl::Missing => {}
+        }
+    }
+
+    fn url_for(&self, key: &AssetKey) -> AssetUrl {
+        match key {
+            AssetKey::Emote(emote_id) => {
+                // the largest scale, the emote size can be changed in the settings
+                return AssetUrl::Found(format!(
+                    "https://static-cdn.jtvnw.net/emoticons/v2/{emote_id}/default/dark/3.0"
+                ));
+            }
+            AssetKey::Badge {
+                channel_id,
+                set_id,
+                version,
+            } => {
+                let set_version = (set_id.clone(), version.clone());
+
+                let channel_urls = self.badge_urls.get(&Some(channel_id.clone()));
+                if let Some(Some(urls)) = channel_urls
+                    && let Some(url) = urls.get(&set_version)
+                {
+                    return AssetUrl::Found(url.clone());
+                }
+
+                return match (channel_urls, self.badge_urls.get(&None)) {
+                    (Some(Some(_)), Some(Some(urls))) => urls
+                        .get(&set_version)
+                        .map_or(AssetUrl::Missing, |url| AssetUrl::Found(url.clone())),
+                    _ => AssetUrl::Unknown,
+                };
+            }
+            AssetKey::Avatar(user_id) => {
+                return match self.avatar_urls.get(user_id) {
+                    Some(Some(Some(url))) => AssetUrl::Found(url.clone()),
+                    Some(Some(None)) => AssetUrl::Missing,
+                    _ => AssetUrl::Unknown,
+                };
+            }
+            AssetKey::ThirdPartyEmote { provider, id } => {
+                return match self.third_party_urls.get(&(*provider, id.clone())) {
+                    Some(url) => AssetUrl::Found(url.clone()),
+                    None => AssetUrl::Unknown,
+                };
+            }
+        }
+    }
+
+    fn handle_result(&mut self, result: TaskResult) {
+        match result {
+            TaskResult::BadgeSetsLoaded {
+                channel_id,
+                urls: Some(urls),
+            } => {
+                self.badge_urls.insert(channel_id, Some(urls));
+                self.resolve();
+            }
+            TaskResult::BadgeSetsLoaded { channel_id, urls: None } => {
+                self.badge_urls.remove(&channel_id);
+                // channel badges fall back to the global ones, so those are stuck if the global ones failed
+                self.fail_unresolved(|key| match key {
+                    AssetKey::Badge {
+                        channel_id: badge_channel_id,
+                        ..
+                    } => channel_id
+                        .as_ref()
+                        .is_none_or(|channel_id| channel_id == badge_channel_id),
+                    _ => false,
+                });
+            }
+            TaskResult::AvatarsLookedUp(urls) => {
+                for (user_id, url) in urls {
+                    self.avatar_urls.insert(user_id, Some(url));
+                }
+                self.resolve();
+            }
+            TaskResult::AvatarLookupFailed(user_ids) => {
+                for user_id in &user_ids {
+                    self.avatar_urls.remove(user_id);
+                }
+                self.fail_unresolved(|key| matches!(key, AssetKey::Avatar(user_id) if user_ids.contains(user_id)));
+            }
+            TaskResult::DownloadFailed(key) => self.fail(key),
+            TaskResult::Downloaded { key, bytes } => {
+                self.loading.remove(&key);
+
+                if let Err(err) = self.store(&key, &bytes) {
+                    warn!("Failed to cache asset: {}", err);
+                }
+
+                self.state_diff_tx
+                    .send(AppStateDiff::AssetLoaded {
+                        key,
+                        bytes: Arc::from(bytes),
+                    })
+                    .unwrap();
+            }
+            TaskResult::ThirdPartyEmotesLoaded {
+                provider,
+                channel_id,
+                emotes,
+            } => {
+                for emote in &emotes {
+                    self.third_party_urls
+                        .insert((provider, emote.id.clone()), emote.image_url.clone());
+                }
+                self.resolve();
+
+                self.state_diff_tx
+                    .send(AppStateDiff::ThirdPartyEmotesLoaded {
+                        provider,
+                        channel_id,
+                        emotes,
+                    })
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Downloads the unresolved assets helix told us about by now.
+    fn resolve(&mut self) {
+        for key in std::mem::take(&mut self.unresolved) {
+            match self.url_for(&key) {
+                AssetUrl::Found(url) => self.download(key, url),
+                AssetUrl::Unknown => self.unresolved.push(key),
+                AssetUrl::Missing => {
+                    self.loading.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn fail(&mut self, key: AssetKey) {
+        self.loading.remove(&key);
+        self.seen.remove(&key);
+        self.failed.insert(key, Instant::now());
+    }
+
+    fn fail_unresolved(&mut self, is_failed: impl Fn(&AssetKey) -> bool) {
+        let (failed, unresolved) = std::mem::take(&mut self.unresolved).into_iter().partition(is_failed);
+        self.unresolved = unresolved;
+
+        for key in failed {
+            self.fail(key);
+        }
+    }
+
+    /// Asks helix for the image urls of the unresolved badges and avatars.
+    fn handle_tick(&mut self) {
+        let Some(account) = self.account.clone() else {
+            return;
+        };
+
+        let mut badge_channels = HashSet::new();
+        let mut user_ids = Vec::new();
+        for key in &self.unresolved {
+            match key {
+                AssetKey::Badge { channel_id, .. } => {
+                    badge_channels.insert(None);
+                    badge_channels.insert(Some(channel_id.clone()));
+                }
+                AssetKey::Avatar(user_id) if !self.avatar_urls.contains_key(user_id) => {
+                    user_ids.push(user_id.clone());
+                }
+                _ => {}
+            }
+        }
+
+        for channel_id in badge_channels {
+            if self.badge_urls.contains_key(&channel_id) {
+                continue;
+            }
+
+            self.badge_urls.insert(channel_id.clone(), None);
+            self.load_badge_sets(&account, channel_id);
+        }
+
+        for user_ids in user_ids.chunks(MAX_USERS_PER_LOOKUP) {
+            for user_id in user_ids {
+                self.avatar_urls.insert(user_id.clone(), None);
+            }
+
+            self.look_up_avatars(&account, user_ids.to_vec());
+        }
+    }
+
+    fn load_badge_sets(&self, account: &TwitchAccount, channel_id: Option<String>) {
+        let client = account.client.clone();
+        let token = account.token.clone();
+        let result_tx = self.result_tx.clone();
+        let state_diff_tx = self.state_diff_tx.clone();
+
+        self.runtime.spawn(async move {
+            let badge_sets = match &channel_id {
+                Some(channel_id) => client
+                    .req_get(GetChannelChatBadgesRequest::broadcaster_id(channel_id.as_str()), &token)
+                    .await
+                    .map(|response| response.data),
+                None => client
+                    .req_get(GetGlobalChatBadgesRequest::new(), &token)
+                    .await
+                    .map(|response| response.data),
+            };
+
+            let urls = match badge_sets {
+                Ok(badge_sets) => {
+                    state_diff_tx
+                        .send(AppStateDiff::BadgeTitlesLoaded {
+                            channel_id: channel_id.clone(),
+                            titles: by_badge(&badge_sets, |badge| badge.title.clone()),
+                        })
+                        .unwrap();
+
+                    Some(by_badge(&badge_sets, |badge| badge.image_url_4x.clone()))
+                }
+                Err(err) => {
+                    warn!("Failed to load chat badges: {}", err);
+                    None
+                }
+            };
+
+            result_tx
+                .send(TaskResult::BadgeSetsLoaded { channel_id, urls })
+                .unwrap();
+        });
+    }
+
+    fn look_up_avatars(&self, account: &TwitchAccount, user_ids: Vec<String>) {
+        let client = account.client.clone();
+        let token = account.token.clone();
+        let result_tx = self.result_tx.clone();
+
+        self.runtime.spawn(async move {
+            let ids = user_ids
+                .iter()
+                .map(|user_id| UserId::from(user_id.as_str()))
+                .collect::<Vec<_>>();
+            let ids = ids.as_slice().into();
+
+            let mut urls = match client.get_users_from_ids(&ids, &token).try_collect::<Vec<_>>().await {
+                Ok(users) => users
+                    .into_iter()
+                    .map(|user| {
+                        (
+                            user.id.to_string(),
+                            user.profile_image_url.map(|url| small_avatar_url(&url)),
+                        )
+                    })
+                    .collect::<HashMap<_, _>>(),
+                Err(err) => {
+                    warn!("Failed to look up users: {}", err);
+                    result_tx.send(TaskResult::AvatarLookupFailed(user_ids)).unwrap();
+                    return;
+                }
+            };
+
+            let urls = user_ids
+                .into_iter()
+                .map(|user_id| {
+                    let url = urls.remove(&user_id).flatten();
+                    return (user_id, url);
+                })
+                .collect();
+
+            result_tx.send(TaskResult::AvatarsLookedUp(urls)).unwrap();
+        });
+    }
+
+    fn load_third_party_emotes(&self, provider: EmoteProvider, channel_id: Option<String>) {
+        let http = self.http.clone();
+        let emote_api = self.emote_api.clone();
+        let result_tx = self.result_tx.clone();
+
+        self.runtime.spawn(async move {
+            match emote_api.load_emotes(&http, provider, channel_id.as_deref()).await {
+                Ok(emotes) => {
+                    result_tx
+                        .send(TaskResult::ThirdPartyEmotesLoaded {
+                            provider,
+                            channel_id,
+                            emotes,
+                        })
+                        .unwrap();
+                }
+                Err(err) => warn!("Failed to load {} emotes: {}", provider, err),
+            }
+        });
+    }
+
+    fn download(&self, key: AssetKey, url: String) {
+        let http = self.http.clone();
+        let downloads = self.downloads.clone();
+        let result_tx = self.result_tx.clone();
+
+        self.runtime.spawn(async move {
+            let Ok(_permit) = downloads.acquire_owned().await else {
+                return;
+            };
+
+            match fetch(&http, &url).await {
+                Ok(bytes) => result_tx.send(TaskResult::Downloaded { key, bytes }).unwrap(),
+                Err(err) => {
+                    warn!("Failed to download {}: {}", url, err);
+                    result_tx.send(TaskResult::DownloadFailed(key)).unwrap();
+                }
+            }
+        });
+    }
+
+    fn load_cached(&self, key: &AssetKey) -> Option<Vec<u8>> {
+        let asset = match CachedAsset::load(&self.db_pool, &key.uri()) {
+            Ok(asset) => asset?,
+            Err(err) => {
+                warn!("Failed to load cached asset: {}", err);
+                return None;
+            }
+        };
+
+        if asset.fetched_at < expired_before() {
+            return None;
+        }
+
+        return fs::read(blob_path(&asset.hash)).ok();
+    }
+
+    fn store(&self, key: &AssetKey, bytes: &[u8]) -> Result<()> {
+        let hash = format!("{:x}", Sha256::digest(bytes));
+
+        let path = blob_path(&hash);
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+
+            // never leave half written files behind under the final name
+            let partial_path = path.with_extension("partial");
+            fs::write(&partial_path, bytes)?;
+            fs::rename(partial_path, &path)?;
+        }
+
+        CachedAsset::save(
+            &self.db_pool,
+            &CachedAsset {
+                key: key.uri(),
+                hash,
+                fetched_at: Utc::now().timestamp_millis(),
+            },
+        )?;
+
+        return Ok(());
+    }
+}
+
+async fn fetch(http: &reqwest::Client, url: &str) -> reqwest::Result<Vec<u8>> {
+    let response = http.get(url).send().await?.error_for_status()?;
+
+    return Ok(response.bytes().await?.to_vec());
+}
+
+fn by_badge(badge_sets: &[BadgeSet], value: impl Fn(&ChatBadge) -> String) -> BadgeMap {
+    return badge_sets
+        .iter()
+        .flat_map(|set| {
+            set.versions
+                .iter()
+                .map(|badge| ((set.set_id.to_string(), badge.id.to_string()), value(badge)))
+        })
+        .collect();
+}
+
+/// Helix only hands out the 300x300 version, the cdn has a 70x70 one under the same name.
+fn small_avatar_url(url: &str) -> String {
+    return url.replace("-300x300.", "-70x70.");
+}
+
+/// Files are stored by the hash of their content, so assets with the same image share a file.
+fn blob_path(hash: &str) -> PathBuf {
+    return PathBuf::from(CACHE_DIR).join(&hash[..2]).join(hash);
+}
+
+fn expired_before() -> i64 {
+    return Utc::now().timestamp_millis() - MAX_AGE.as_millis() as i64;
+}
+
+/// Forgets expired assets and deletes the files no asset points to anymore.
+fn prune_cache(db_pool: &SqlitePool) {
+    let hashes = match CachedAsset::expire(db_pool, expired_before()) {
+        Ok(hashes) => hashes.into_iter().collect::<HashSet<_>>(),
+        Err(err) => {
+            warn!("Failed to expire cached assets: {}", err);
+            return;
+        }
+    };
+
+    let Ok(dirs) = fs::read_dir(CACHE_DIR) else {
+        return;
+    };
+
+    let mut removed = 0;
+    for file in dirs
+        .flatten()
+        .filter_map(|dir| fs::read_dir(dir.path()).ok())
+        .flatten()
+        .flatten()
+    {
+        if hashes.contains(file.file_name().to_string_lossy().as_ref()) {
+            continue;
+        }
+
+        match fs::remove_file(file.path()) {
+            Ok(()) => removed += 1,
+            Err(err) => warn!("Failed to remove cached asset: {}", err),
+        }
+    }
+
+    if removed > 0 {
+        info!("Removed {} expired assets from the cache.", removed);
+    }
+}
+
+pub fn worker_start_assets(
+    db_pool: SqlitePool,
+    event_rx: mpsc::Receiver<TwitchEvent>,
+    message_rx: mpsc::Receiver<AssetWorkerMessage>,
+    state_diff_tx: mpsc::Sender<AppStateDiff>,
+) {
     tokio::task::spawn_blocking(move || {
-        while let Ok(_event) = event_rx.recv() {
-            //
+        let mut loader = AssetLoader::new(db_pool, state_diff_tx);
+
+        loop {
+            match event_rx.recv_timeout(TICK_INTERVAL) {
+                Ok(event) => loader.handle_event(&event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            while let Ok(message) = message_rx.try_recv() {
+                loader.handle_message(message);
+            }
+
+            while let Ok(result) = loader.result_rx.try_recv() {
+                loader.handle_result(result);
+            }
+
+            loader.handle_tick();
         }
     });
 }
@@ -0,0 +1,170 @@
+use std::{
+    collections::HashMap,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use tracing::warn;
+use twitch_irc::message::ClearChatAction;
+
+use crate::{
+    models::{
+        SqlitePool,
+        chat_event::{ChatEvent, ChatEventStatus, NewChatEvent},
+    },
+    twitch::types::TwitchEvent,
+    ui::state::AppStateDiff,
+};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often messages are checked for while no events arrive.
+const MESSAGE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub enum HistoryWorkerMessage {
+    /// Saves everything received so far and tells the UI where the history of the channel ends.
+    Flush(String),
+}
+
+/// Writes the chat history in batches and applies deletions, timeouts and bans to it.
+struct HistoryRecorder {
+    db_pool: SqlitePool,
+    state_diff_tx: mpsc::Sender<AppStateDiff>,
+    pending: Vec<NewChatEvent>,
+    /// When we first joined each channel, moderation only applies to messages seen since then, like in the chat.
+    joined_at: HashMap<String, i64>,
+    last_flush: Instant,
+}
+
+impl HistoryRecorder {
+    fn new(db_pool: SqlitePool, state_diff_tx: mpsc::Sender<AppStateDiff>) -> Self {
+        return Self {
+            db_pool,
+            state_diff_tx,
+            pending: Vec::new(),
+            joined_at: HashMap::new(),
+            last_flush: Instant::now(),
+        };
+    }
+
+    fn handle_event(&mut self, event: &TwitchEvent) {
+        match event {
+            TwitchEvent::Join(join) => {
+                self.joined_at
+                    .entry(join.channel_login.clone())
+                    .or_insert_with(|| Utc::now().timestamp_millis());
+            }
+            TwitchEvent::ClearMsg(clear_msg) => {
+                self.flush();
+
+                if let Err(err) =
+                    ChatEvent::set_status_by_message_id(&self.db_pool, &clear_msg.message_id, ChatEventStatus::Deleted)
+                {
+                    warn!("Failed to update chat history: {}", err);
+                }
+            }
+            TwitchEvent::ClearChat(clear_chat) => {
+                let (user_id, status) = match &clear_chat.action {
+                    ClearChatAction::ChatCleared => return,
+                    // low duration timeouts are used to clear messages usually
+                    ClearChatAction::UserTimedOut {
+                        user_id,
+                        timeout_length,
+                        ..
+                    } if timeout_length.lt(&Duration::from_secs(5)) => (user_id, ChatEventStatus::Deleted),
+                    ClearChatAction::UserTimedOut { user_id, .. } => (user_id, ChatEventStatus::TimedOut),
+                    ClearChatAction::UserBanned { user_id, .. } => (user_id, ChatEventStatus::Banned),
+                };
+
+                self.flush();
+
+                let since = self.joined_at.get(&clear_chat.channel_login).copied().unwrap_or(0);
+                if let Err(err) =
+                    ChatEvent::set_status_for_user(&self.db_pool, &clear_chat.channel_login, user_id, since, status)
+                {
+                    warn!("Failed to update chat history: {}", err);
+                }
+            }
+            event => {
+                if let Some(event) = NewChatEvent::from_event(event) {
+                    self.pending.push(event);
+                }
+            }
+        }
+    }
+
+    fn handle_tick(&mut self) {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn flush_channel(&mut self, channel: String) {
+        self.flush();
+
+        let newest_id = match ChatEvent::newest_id(&self.db_pool, &channel) {
+            Ok(id) => id,
+            Err(err) => {
+                warn!("Failed to load chat history: {}", err);
+                None
+            }
+        };
+
+        self.state_diff_tx
+            .send(AppStateDiff::ChatHistoryFlushed { channel, newest_id })
+            .unwrap();
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let events = std::mem::take(&mut self.pending);
+        match ChatEvent::insert_all(&self.db_pool, &events) {
+            Ok(ids) => {
+                let ids = events.into_iter().map(|event| event.channel).zip(ids).collect();
+                self.state_diff_tx.send(AppStateDiff::ChatEventsSaved(ids)).unwrap();
+            }
+            Err(err) => warn!("Failed to save chat history: {}", err),
+        }
+    }
+}
+
+pub fn worker_start_history(
+    db_pool: SqlitePool,
+    event_rx: mpsc::Receiver<TwitchEvent>,
+    message_rx: mpsc::Receiver<HistoryWorkerMessage>,
+    state_diff_tx: mpsc::Sender<AppStateDiff>,
+) {
+    tokio::task::spawn_blocking(move || {
+        let mut recorder = HistoryRecorder::new(db_pool, state_diff_tx);
+
+        loop {
+            match event_rx.recv_timeout(MESSAGE_POLL_INTERVAL) {
+                Ok(event) => recorder.handle_event(&event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            while let Ok(message) = message_rx.try_recv() {
+                match message {
+                    HistoryWorkerMessage::Flush(channel) => {
+                        while let Ok(event) = event_rx.try_recv() {
+                            recorder.handle_event(&event);
+                        }
+
+                        recorder.flush_channel(channel);
+                    }
+                }
+            }
+
+            recorder.handle_tick();
+        }
+
+        recorder.flush();
+    });
+}
@@ -1,5 +1,6 @@
 pub mod action;
 pub mod asset;
+pub mod history;
 pub mod stats;
 pub mod timers;
 pub mod twitch;
@@ -7,10 +8,15 @@ pub mod twitch;
 use std::sync::mpsc;
 
 use crate::{
+    models::SqlitePool,
     twitch::types::TwitchEvent,
     ui::state::AppStateDiff,
     workers::{
-        action::worker_start_action, asset::worker_start_assets, stats::worker_start_stats, timers::worker_start_timers,
+        action::{ActionWorkerMessage, worker_start_action},
+        asset::{AssetWorkerMessage, worker_start_assets},
+        history::{HistoryWorkerMessage, worker_start_history},
+        stats::{StatsWorkerMessage, worker_start_stats},
+        timers::{TimersWorkerMessage, worker_start_timers},
     },
 };
 
@@ -21,18 +27,32 @@ pub struct MPSCChannels {
     pub ui_twitch_event_tx: mpsc::Sender<TwitchEvent>,
     pub ui_twitch_event_rx: mpsc::Receiver<TwitchEvent>,
     pub action_worker_tx: mpsc::Sender<TwitchEvent>,
+    pub action_worker_message_tx: mpsc::Sender<ActionWorkerMessage>,
     pub stats_worker_tx: mpsc::Sender<TwitchEvent>,
+    pub stats_worker_message_tx: mpsc::Sender<StatsWorkerMessage>,
     pub asset_worker_tx: mpsc::Sender<TwitchEvent>,
+    pub asset_worker_message_tx: mpsc::Sender<AssetWorkerMessage>,
+    pub history_worker_tx: mpsc::Sender<TwitchEvent>,
+    pub history_worker_message_tx: mpsc::Sender<HistoryWorkerMessage>,
+    pub timers_worker_tx: mpsc::Sender<TwitchEvent>,
+    pub timers_worker_message_tx: mpsc::Sender<TimersWorkerMessage>,
     pub twitch_event_txs: Vec<mpsc::Sender<TwitchEvent>>,
 }
 
-pub fn create_workers() -> MPSCChannels {
+pub fn create_workers(db_pool: &SqlitePool) -> MPSCChannels {
     let (ui_diff_tx, ui_diff_rx) = mpsc::channel::<AppStateDiff>();
     let (ui_twitch_event_tx, ui_twitch_event_rx) = mpsc::channel::<TwitchEvent>();
 
     let (action_worker_tx, action_worker_rx) = mpsc::channel::<TwitchEvent>();
+    let (action_worker_message_tx, action_worker_message_rx) = mpsc::channel::<ActionWorkerMessage>();
     let (stats_worker_tx, stats_worker_rx) = mpsc::channel::<TwitchEvent>();
+    let (stats_worker_message_tx, stats_worker_message_rx) = mpsc::channel::<StatsWorkerMessage>();
     let (asset_worker_tx, asset_worker_rx) = mpsc::channel::<TwitchEvent>();
+    let (asset_worker_message_tx, asset_worker_message_rx) = mpsc::channel::<AssetWorkerMessage>();
+    let (history_worker_tx, history_worker_rx) = mpsc::channel::<TwitchEvent>();
+    let (history_worker_message_tx, history_worker_message_rx) = mpsc::channel::<HistoryWorkerMessage>();
+    let (timers_worker_tx, timers_worker_rx) = mpsc::channel::<TwitchEvent>();
+    let (timers_worker_message_tx, timers_worker_message_rx) = mpsc::channel::<TimersWorkerMessage>();
 
     // who wants to hear about twitch events?
     let twitch_event_txs = vec![
@@ -40,6 +60,8 @@ pub fn create_workers() -> MPSCChannels {
         action_worker_tx.clone(),
         stats_worker_tx.clone(),
         asset_worker_tx.clone(),
+        history_worker_tx.clone(),
+        timers_worker_tx.clone(),
     ];
 
     let channels = MPSCChannels {
@@ -48,16 +70,50 @@ pub fn create_workers() -> MPSCChannels {
         ui_twitch_event_tx,
         ui_twitch_event_rx,
         action_worker_tx,
+        action_worker_message_tx,
         stats_worker_tx,
+        stats_worker_message_tx,
         asset_worker_tx,
+        asset_worker_message_tx,
+        history_worker_tx,
+        history_worker_message_tx,
+        timers_worker_tx,
+        timers_worker_message_tx,
         twitch_event_txs,
     };
 
-    worker_start_action(action_worker_rx, channels.ui_diff_tx.clone());
-    worker_start_assets(asset_worker_rx, channels.ui_diff_tx.clone());
-    worker_start_stats(stats_worker_rx, channels.ui_diff_tx.clone());
+    worker_start_action(
+        db_pool.clone(),
+        action_worker_rx,
+        action_worker_message_rx,
+        channels.ui_diff_tx.clone(),
+    );
+    worker_start_assets(
+        db_pool.clone(),
+        asset_worker_rx,
+        asset_worker_message_rx,
+        channels.ui_diff_tx.clone(),
+    );
+    worker_start_history(
+        db_pool.clone(),
+        history_worker_rx,
+        history_worker_message_rx,
+        channels.ui_diff_tx.clone(),
+    );
+    worker_start_stats(
+        db_pool.clone(),
+        stats_worker_rx,
+        stats_worker_message_rx,
+        channels.ui_diff_tx.clone(),
+    );
 
-    worker_start_timers(channels.ui_diff_tx.clone());
+    worker_start_timers(
+        db_pool.clone(),
+        timers_worker_rx,
+        timers_worker_message_rx,
+        channels.ui_diff_tx.clone(),
+        channels.action_worker_message_tx.clone(),
+    );
 
     return channels;
 }
@@ -1,11 +1,413 @@
-use std::sync::mpsc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
-use crate::{twitch::types::TwitchEvent, ui::state::AppStateDiff};
+use chrono::Utc;
+use tracing::{info, warn};
+use twitch_irc::message::{ClearChatAction, UserNoticeEvent};
 
-pub fn worker_start_stats(event_rx: mpsc::Receiver<TwitchEvent>, _state_diff_tx: mpsc::Sender<AppStateDiff>) {
+use crate::{
+    models::{
+        SqlitePool,
+        chat_user::ChatUser,
+        mod_log::{ModAction, ModLogEntry, NewModLogEntry},
+        stats::{StatsChatter, StatsEmote, StatsMinute, StreamSession, save_stats},
+    },
+    twitch::types::{PrivmsgMessageExt, TwitchEvent},
+    ui::state::AppStateDiff,
+};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// Offline chat sessions end after this long without any activity.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone)]
+pub enum StatsWorkerMessage {
+    SetLive(bool),
+}
+
+struct SessionStats {
+    id: i32,
+    channel: String,
+    live: bool,
+    last_activity: Instant,
+    last_activity_at: i64,
+    minute: StatsMinute,
+    minute_chatters: HashSet<String>,
+    /// Finished minutes that have not been written yet.
+    finished_minutes: Vec<StatsMinute>,
+    chatters: HashMap<String, StatsChatter>,
+    emotes: HashMap<String, StatsEmote>,
+    dirty_chatters: HashSet<String>,
+    dirty_emotes: HashSet<String>,
+}
+
+impl SessionStats {
+    fn minute_for(&mut self, now: i64) -> &mut StatsMinute {
+        let minute = now - now.rem_euclid(60_000);
+
+        if self.minute.minute != minute {
+            let finished = std::mem::replace(
+                &mut self.minute,
+                StatsMinute {
+                    session_id: self.id,
+                    minute,
+                    ..StatsMinute::default()
+                },
+            );
+            self.minute_chatters.clear();
+
+            if finished.minute != 0 {
+                self.finished_minutes.push(finished);
+            }
+        }
+
+        return &mut self.minute;
+    }
+}
+
+/// Aggregates chat activity of the current session into per minute, per chatter and per emote buckets, and keeps
+/// the chat users and the mod log up to date.
+struct StatsRecorder {
+    db_pool: SqlitePool,
+    session: Option<SessionStats>,
+    /// Chat activity per user since the last flush.
+    users: HashMap<String, ChatUser>,
+    is_live: bool,
+    last_flush: Instant,
+}
+
+impl StatsRecorder {
+    fn new(db_pool: SqlitePool) -> Self {
+        match StreamSession::end_dangling(&db_pool) {
+            Ok(0) => {}
+            Ok(count) => info!("Ended {} stream sessions left over from the last run.", count),
+            Err(err) => warn!("Failed to end stream sessions: {}", err),
+        }
+
+        return Self {
+            db_pool,
+            session: None,
+            users: HashMap::new(),
+            is_live: false,
+            last_flush: Instant::now(),
+        };
+    }
+
+    fn start_session(&mut self, channel: &str, live: bool) {
+        self.end_session();
+
+        let now = Utc::now().timestamp_millis();
+        let id = match StreamSession::start(&self.db_pool, channel, now, live) {
+            Ok(id) => id,
+            Err(err) => {
+                warn!("Failed to start stream session: {}", err);
+                return;
+            }
+        };
+
+        info!("Started stream session {} for {}.", id, channel);
+
+        self.session = Some(SessionStats {
+            id,
+            channel: channel.to_owned(),
+            live,
+            last_activity: Instant::now(),
+            last_activity_at: now,
+            minute: StatsMinute::default(),
+            minute_chatters: HashSet::new(),
+            finished_minutes: Vec::new(),
+            chatters: HashMap::new(),
+            emotes: HashMap::new(),
+            dirty_chatters: HashSet::new(),
+            dirty_emotes: HashSet::new(),
+        });
+    }
+
+    fn end_session(&mut self) {
+        self.flush();
+
+        let Some(session) = self.session.take() else {
+            return;
+        };
+
+        let ended_at = if session.live {
+            Utc::now().timestamp_millis()
+        } else {
+            session.last_activity_at
+        };
+
+        if let Err(err) = StreamSession::end(&self.db_pool, session.id, ended_at) {
+            warn!("Failed to end stream session: {}", err);
+        }
+    }
+
+    /// Returns the session of `channel`, starting a new one if we moved on to another channel.
+    fn session_for(&mut self, channel: &str) -> Option<&mut SessionStats> {
+        if self.session.as_ref().is_none_or(|session| session.channel != channel) {
+            self.start_session(channel, self.is_live);
+        }
+
+        let session = self.session.as_mut()?;
+        session.last_activity = Instant::now();
+        session.last_activity_at = Utc::now().timestamp_millis();
+
+        return Some(session);
+    }
+
+    fn set_live(&mut self, is_live: bool) {
+        self.is_live = is_live;
+
+        let Some(session) = &mut self.session else {
+            return;
+        };
+
+        if is_live && !session.live {
+            // chat that started shortly before we learned the stream is live belongs to the stream
+            session.live = true;
+            if let Err(err) = StreamSession::set_live(&self.db_pool, session.id) {
+                warn!("Failed to update stream session: {}", err);
+            }
+        } else if !is_live && session.live {
+            self.end_session();
+        }
+    }
+
+    fn handle_event(&mut self, event: &TwitchEvent) {
+        let now = Utc::now().timestamp_millis();
+
+        match event {
+            TwitchEvent::StreamOnline(online) => {
+                self.is_live = true;
+
+                let channel = online.broadcaster_user_login.as_str();
+                if self
+                    .session
+                    .as_ref()
+                    .is_none_or(|session| !session.live || session.channel != channel)
+                {
+                    self.start_session(channel, true);
+                }
+            }
+            TwitchEvent::StreamOffline(_) => {
+                self.is_live = false;
+                self.end_session();
+            }
+            TwitchEvent::Privmsg(msg) => {
+                let user = self.users.entry(msg.sender.id.clone()).or_insert_with(|| ChatUser {
+                    user_id: msg.sender.id.clone(),
+                    login: msg.sender.login.clone(),
+                    name: msg.sender.name.clone(),
+                    first_seen: now,
+                    last_seen: now,
+                    messages: 0,
+                });
+                user.login = msg.sender.login.clone();
+                user.name = msg.sender.name.clone();
+                user.last_seen = now;
+                user.messages += 1;
+
+                let Some(session) = self.session_for(&msg.channel_login) else {
+                    return;
+                };
+
+                let bits = msg.bits.unwrap_or(0) as i32;
+                let is_first_message = msg.is_first_message();
+                // rolling over to a new minute clears its chatters, so it has to happen first
+                session.minute_for(now);
+                let is_new_minute_chatter = session.minute_chatters.insert(msg.sender.id.clone());
+
+                let minute = &mut session.minute;
+                minute.messages += 1;
+                minute.bits += bits;
+                minute.emotes += msg.emotes.len() as i32;
+                if is_new_minute_chatter {
+                    minute.chatters += 1;
+                }
+                if is_first_message {
+                    minute.first_time_chatters += 1;
+                }
+
+                let chatter = session
+                    .chatters
+                    .entry(msg.sender.id.clone())
+                    .or_insert_with(|| StatsChatter {
+                        session_id: session.id,
+                        user_id: msg.sender.id.clone(),
+                        user_login: msg.sender.login.clone(),
+                        user_name: msg.sender.name.clone(),
+                        messages: 0,
+                        bits: 0,
+                        first_time: false,
+                    });
+                chatter.messages += 1;
+                chatter.bits += bits;
+                chatter.first_time |= is_first_message;
+                session.dirty_chatters.insert(msg.sender.id.clone());
+
+                for emote in &msg.emotes {
+                    session
+                        .emotes
+                        .entry(emote.id.clone())
+                        .or_insert_with(|| StatsEmote {
+                            session_id: session.id,
+                            emote_id: emote.id.clone(),
+                            emote_name: emote.code.clone(),
+                            uses: 0,
+                        })
+                        .uses += 1;
+                    session.dirty_emotes.insert(emote.id.clone());
+                }
+            }
+            TwitchEvent::UserNotice(notice) => {
+                let is_sub = matches!(
+                    notice.event,
+                    UserNoticeEvent::SubOrResub { .. } | UserNoticeEvent::SubGift { .. }
+                );
+                if !is_sub {
+                    return;
+                }
+
+                if let Some(session) = self.session_for(&notice.channel_login) {
+                    session.minute_for(now).subs += 1;
+                }
+            }
+            TwitchEvent::ClearChat(clear_chat) => {
+                if let Some(session) = self.session_for(&clear_chat.channel_login) {
+                    session.minute_for(now).mod_actions += 1;
+                }
+
+                let (action, user_id, user_login, duration_secs) = match &clear_chat.action {
+                    ClearChatAction::ChatCleared => return,
+                    ClearChatAction::UserTimedOut {
+                        user_login,
+                        user_id,
+                        timeout_length,
+                    } => (
+                        ModAction::Timeout,
+                        user_id,
+                        user_login,
+                        Some(timeout_length.as_secs() as i64),
+                    ),
+                    ClearChatAction::UserBanned { user_login, user_id } => (ModAction::Ban, user_id, user_login, None),
+                };
+
+                self.log_mod_action(NewModLogEntry {
+                    channel: clear_chat.channel_login.clone(),
+                    user_id: Some(user_id.clone()),
+                    user_login: user_login.clone(),
+                    action: <&str>::from(action).to_owned(),
+                    duration_secs,
+                    message: None,
+                    created_at: now,
+                });
+            }
+            TwitchEvent::ClearMsg(clear_msg) => {
+                if let Some(session) = self.session_for(&clear_msg.channel_login) {
+                    session.minute_for(now).mod_actions += 1;
+                }
+
+                self.log_mod_action(NewModLogEntry {
+                    channel: clear_msg.channel_login.clone(),
+                    user_id: None,
+                    user_login: clear_msg.sender_login.clone(),
+                    action: <&str>::from(ModAction::DeleteMessage).to_owned(),
+                    duration_secs: None,
+                    message: Some(clear_msg.message_text.clone()),
+                    created_at: now,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_tick(&mut self) {
+        if self
+            .session
+            .as_ref()
+            .is_some_and(|session| !session.live && session.last_activity.elapsed() >= IDLE_TIMEOUT)
+        {
+            self.end_session();
+        }
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn log_mod_action(&self, entry: NewModLogEntry) {
+        if let Err(err) = ModLogEntry::insert(&self.db_pool, &entry) {
+            warn!("Failed to write mod log: {}", err);
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+
+        if !self.users.is_empty() {
+            let users = std::mem::take(&mut self.users).into_values().collect::<Vec<_>>();
+            if let Err(err) = ChatUser::record_activity(&self.db_pool, &users) {
+                warn!("Failed to save chat users: {}", err);
+            }
+        }
+
+        let Some(session) = &mut self.session else {
+            return;
+        };
+
+        let mut minutes = std::mem::take(&mut session.finished_minutes);
+        if session.minute.minute != 0 {
+            minutes.push(session.minute.clone());
+        }
+
+        let chatters = session
+            .dirty_chatters
+            .drain()
+            .filter_map(|user_id| session.chatters.get(&user_id).cloned())
+            .collect::<Vec<_>>();
+        let emotes = session
+            .dirty_emotes
+            .drain()
+            .filter_map(|emote_id| session.emotes.get(&emote_id).cloned())
+            .collect::<Vec<_>>();
+
+        if minutes.is_empty() && chatters.is_empty() && emotes.is_empty() {
+            return;
+        }
+
+        if let Err(err) = save_stats(&self.db_pool, &minutes, &chatters, &emotes) {
+            warn!("Failed to save stats: {}", err);
+        }
+    }
+}
+
+pub fn worker_start_stats(
+    db_pool: SqlitePool,
+    event_rx: mpsc::Receiver<TwitchEvent>,
+    message_rx: mpsc::Receiver<StatsWorkerMessage>,
+    _state_diff_tx: mpsc::Sender<AppStateDiff>,
+) {
     tokio::task::spawn_blocking(move || {
-        while let Ok(_event) = event_rx.recv() {
-            //
+        let mut recorder = StatsRecorder::new(db_pool);
+
+        loop {
+            match event_rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(event) => recorder.handle_event(&event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            while let Ok(message) = message_rx.try_recv() {
+                match message {
+                    StatsWorkerMessage::SetLive(is_live) => recorder.set_live(is_live),
+                }
+            }
+
+            recorder.handle_tick();
         }
+
+        recorder.end_session();
     });
 }
@@ -1,8 +1,140 @@
-use std::{net::TcpStream, sync::mpsc, time::Duration};
+use std::{
+    collections::HashMap,
+    net::TcpStream,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
-use crate::ui::state::AppStateDiff;
+use tracing::{info, warn};
 
-pub fn worker_start_timers(ui_diff_tx: mpsc::Sender<AppStateDiff>) {
+use crate::{
+    engine::host::ActionCommand,
+    models::{SqlitePool, timed_message::TimedMessage},
+    twitch::types::TwitchEvent,
+    ui::state::AppStateDiff,
+    workers::action::ActionWorkerMessage,
+};
+
+#[derive(Debug, Clone)]
+pub enum TimersWorkerMessage {
+    Reload,
+    SetLive(bool),
+}
+
+struct TimerState {
+    last_posted: Instant,
+    chat_messages: u32,
+    next_message: usize,
+}
+
+/// Posts timed messages in rotation, as long as the stream is live and chat is active enough.
+struct TimedMessageScheduler {
+    db_pool: SqlitePool,
+    timers: Vec<TimedMessage>,
+    states: HashMap<i32, TimerState>,
+    is_live: bool,
+}
+
+impl TimedMessageScheduler {
+    fn new(db_pool: SqlitePool) -> Self {
+        return Self {
+            db_pool,
+            timers: Vec::new(),
+            states: HashMap::new(),
+            is_live: false,
+        };
+    }
+
+    fn reload(&mut self) {
+        match TimedMessage::load_all(&self.db_pool) {
+            Ok(timers) => self.timers = timers,
+            Err(err) => {
+                warn!("Failed to load timed messages: {}", err);
+                return;
+            }
+        }
+
+        let now = Instant::now();
+        self.states
+            .retain(|id, _| self.timers.iter().any(|timer| timer.id == *id));
+        for timer in &self.timers {
+            self.states.entry(timer.id).or_insert(TimerState {
+                last_posted: now,
+                chat_messages: 0,
+                next_message: 0,
+            });
+        }
+
+        info!("Loaded {} timed messages.", self.timers.len());
+    }
+
+    fn set_live(&mut self, is_live: bool) {
+        if is_live && !self.is_live {
+            // start counting from when the stream went live instead of posting right away
+            let now = Instant::now();
+            for state in self.states.values_mut() {
+                state.last_posted = now;
+                state.chat_messages = 0;
+            }
+        }
+
+        self.is_live = is_live;
+    }
+
+    fn handle_event(&mut self, event: &TwitchEvent) {
+        if let TwitchEvent::Privmsg(_) = event {
+            for state in self.states.values_mut() {
+                state.chat_messages = state.chat_messages.saturating_add(1);
+            }
+        }
+    }
+
+    fn due_commands(&mut self, now: Instant) -> Vec<ActionCommand> {
+        if !self.is_live {
+            return Vec::new();
+        }
+
+        let mut commands = Vec::new();
+
+        for timer in self.timers.iter().filter(|timer| timer.enabled) {
+            let Some(state) = self.states.get_mut(&timer.id) else {
+                continue;
+            };
+
+            let interval = Duration::from_secs(u64::try_from(timer.interval_minutes.max(1)).unwrap_or(1) * 60);
+            if now.duration_since(state.last_posted) < interval
+                || state.chat_messages < u32::try_from(timer.min_chat_messages).unwrap_or(0)
+            {
+                continue;
+            }
+
+            let lines = timer.message_lines();
+            if lines.is_empty() {
+                continue;
+            }
+
+            let message = lines[state.next_message % lines.len()].to_owned();
+            state.next_message = (state.next_message + 1) % lines.len();
+            state.last_posted = now;
+            state.chat_messages = 0;
+
+            commands.push(match timer.color() {
+                Some(color) => ActionCommand::SendAnnouncement { message, color },
+                None => ActionCommand::SendMessage(message),
+            });
+        }
+
+        return commands;
+    }
+}
+
+pub fn worker_start_timers(
+    db_pool: SqlitePool,
+    event_rx: mpsc::Receiver<TwitchEvent>,
+    message_rx: mpsc::Receiver<TimersWorkerMessage>,
+    ui_diff_tx: mpsc::Sender<AppStateDiff>,
+    action_worker_tx: mpsc::Sender<ActionWorkerMessage>,
+) {
     // save settings every 30 seconds
     let ui_diff_tx_1 = ui_diff_tx.clone();
     tokio::spawn(async move {
@@ -27,4 +159,47 @@ pub fn worker_start_timers(ui_diff_tx: mpsc::Sender<AppStateDiff>) {
             tokio::time::sleep(Duration::from_secs(15)).await;
         }
     });
+
+    // drive scheduled actions every second
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            action_worker_tx.send(ActionWorkerMessage::Tick).unwrap();
+        }
+    });
+
+    // check whether the stream is live every minute
+    let ui_diff_tx_3 = ui_diff_tx.clone();
+    tokio::spawn(async move {
+        loop {
+            ui_diff_tx_3.send(AppStateDiff::CheckStreamStatus).unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+    });
+
+    // post timed messages
+    tokio::task::spawn_blocking(move || {
+        let mut scheduler = TimedMessageScheduler::new(db_pool);
+        scheduler.reload();
+
+        loop {
+            match event_rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(event) => scheduler.handle_event(&event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            while let Ok(message) = message_rx.try_recv() {
+                match message {
+                    TimersWorkerMessage::Reload => scheduler.reload(),
+                    TimersWorkerMessage::SetLive(is_live) => scheduler.set_live(is_live),
+                }
+            }
+
+            let commands = scheduler.due_commands(Instant::now());
+            if !commands.is_empty() {
+                ui_diff_tx.send(AppStateDiff::RunActionCommands(commands)).unwrap();
+            }
+        }
+    });
 }
@@ -1,11 +1,32 @@
-use std::sync::mpsc;
+use std::{sync::mpsc, time::Duration};
 
-use anyhow::Result;
-use tokio::task::AbortHandle;
-use tracing::trace;
+use anyhow::{Result, bail};
+use futures_util::StreamExt;
+use tokio::{net::TcpStream, task::AbortHandle};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message as WsMessage};
+use tracing::{trace, warn};
+use twitch_api::{
+    eventsub::{
+        Event, EventSubscription, EventsubWebsocketData, Transport,
+        channel::{
+            ChannelCheerV1, ChannelFollowV2, ChannelHypeTrainBeginV1, ChannelHypeTrainEndV1,
+            ChannelHypeTrainProgressV1, ChannelPointsCustomRewardRedemptionAddV1, ChannelPollBeginV1, ChannelPollEndV1,
+            ChannelPollProgressV1, ChannelPredictionBeginV1, ChannelPredictionEndV1, ChannelPredictionLockV1,
+            ChannelPredictionProgressV1, ChannelRaidV1, ChannelSubscribeV1, ChannelSubscriptionGiftV1,
+            ChannelSubscriptionMessageV1,
+        },
+        stream::{StreamOfflineV1, StreamOnlineV1},
+    },
+    types::UserId,
+};
 use twitch_irc::{ClientConfig, SecureTCPTransport, TwitchIRCClient, login::StaticLoginCredentials};
 
-use crate::twitch::types::TwitchEvent;
+use crate::twitch::types::{TwitchAccount, TwitchEvent};
+
+const EVENTSUB_URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+const EVENTSUB_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Extra time on top of the keepalive timeout before the connection is considered dead.
+const KEEPALIVE_GRACE: Duration = Duration::from_secs(5);
 
 pub fn worker_start_twitch_irc(channel_name: String, txs: Vec<mpsc::Sender<TwitchEvent>>) -> Result<AbortHandle> {
     let config = ClientConfig::default();
@@ -35,8 +56,241 @@ pub fn worker_start_twitch_irc(channel_name: String, txs: Vec<mpsc::Sender<Twitc
     return Ok(abort_handle);
 }
 
-pub fn worker_start_twitch_pubsub(_txs: Vec<mpsc::Sender<TwitchEvent>>) -> Result<AbortHandle> {
-    let abort_handle = tokio::spawn(async move {}).abort_handle();
+/// Listens to EventSub notifications for the channel, reconnecting whenever the connection drops.
+pub fn worker_start_twitch_eventsub(
+    account: TwitchAccount,
+    broadcaster_id: UserId,
+    txs: Vec<mpsc::Sender<TwitchEvent>>,
+) -> AbortHandle {
+    let abort_handle = tokio::spawn(async move {
+        loop {
+            if let Err(err) = run_eventsub_session(&account, &broadcaster_id, &txs).await {
+                warn!("EventSub connection failed: {}", err);
+                tokio::time::sleep(EVENTSUB_RETRY_DELAY).await;
+            }
+        }
+    })
+    .abort_handle();
 
-    return Ok(abort_handle);
+    return abort_handle;
+}
+
+type EventSubSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// What a message of an EventSub connection asks of the session, notifications are forwarded right away.
+enum SessionMessage {
+    Welcome {
+        session_id: String,
+        keepalive: Option<Duration>,
+    },
+    Reconnect(String),
+    Other,
+}
+
+/// Runs until the connection fails, twitch asking to reconnect is handled within the session.
+async fn run_eventsub_session(
+    account: &TwitchAccount,
+    broadcaster_id: &UserId,
+    txs: &[mpsc::Sender<TwitchEvent>],
+) -> Result<()> {
+    let (mut socket, _) = connect_async(EVENTSUB_URL).await?;
+    let mut keepalive = Duration::from_secs(30);
+
+    loop {
+        match read_message(&mut socket, keepalive, txs).await? {
+            SessionMessage::Welcome {
+                session_id,
+                keepalive: session_keepalive,
+            } => {
+                keepalive = session_keepalive.unwrap_or(keepalive);
+                subscribe_to_events(account, broadcaster_id, &session_id).await;
+            }
+            SessionMessage::Reconnect(url) => {
+                let (new_socket, new_keepalive) = reconnect(&mut socket, &url, keepalive, txs).await?;
+
+                socket = new_socket;
+                keepalive = new_keepalive;
+            }
+            SessionMessage::Other => {}
+        }
+    }
+}
+
+/// Subscriptions carry over to the new connection. The old one keeps delivering notifications until the new one is
+/// welcomed, only then it is closed.
+async fn reconnect(
+    socket: &mut EventSubSocket,
+    url: &str,
+    keepalive: Duration,
+    txs: &[mpsc::Sender<TwitchEvent>],
+) -> Result<(EventSubSocket, Duration)> {
+    let (mut new_socket, _) = connect_async(url).await?;
+    let mut is_old_open = true;
+
+    let new_keepalive = loop {
+        tokio::select! {
+            message = read_message(&mut new_socket, keepalive, txs) => {
+                if let SessionMessage::Welcome { keepalive: session_keepalive, .. } = message? {
+                    break session_keepalive.unwrap_or(keepalive);
+                }
+            }
+            message = read_message(socket, keepalive, txs), if is_old_open => {
+                if let Err(err) = message {
+                    warn!("EventSub connection closed before the reconnect finished: {}", err);
+                    is_old_open = false;
+                }
+            }
+        }
+    };
+
+    if is_old_open && let Err(err) = socket.close(None).await {
+        warn!("Failed to close the old EventSub connection: {}", err);
+    }
+
+    return Ok((new_socket, new_keepalive));
+}
+
+async fn read_message(
+    socket: &mut EventSubSocket,
+    keepalive: Duration,
+    txs: &[mpsc::Sender<TwitchEvent>],
+) -> Result<SessionMessage> {
+    let Some(message) = tokio::time::timeout(keepalive + KEEPALIVE_GRACE, socket.next()).await? else {
+        bail!("Connection closed.");
+    };
+
+    let text = match message? {
+        WsMessage::Text(text) => text,
+        WsMessage::Close(frame) => bail!("Connection closed by twitch: {:?}", frame),
+        _ => return Ok(SessionMessage::Other),
+    };
+
+    match Event::parse_websocket(&text)? {
+        EventsubWebsocketData::Welcome { payload, .. } => {
+            return Ok(SessionMessage::Welcome {
+                session_id: payload.session.id.to_string(),
+                keepalive: payload
+                    .session
+                    .keepalive_timeout_seconds
+                    .map(|seconds| Duration::from_secs(seconds.max(1) as u64)),
+            });
+        }
+        EventsubWebsocketData::Reconnect { payload, .. } => {
+            let Some(reconnect_url) = payload.session.reconnect_url else {
+                bail!("Reconnect message without a reconnect url.");
+            };
+
+            return Ok(SessionMessage::Reconnect(reconnect_url.into_owned()));
+        }
+        EventsubWebsocketData::Notification { payload, .. } => {
+            if let Ok(event) = TwitchEvent::try_from(payload) {
+                trace!("Received Twitch EventSub event: {:?}", event);
+
+                for tx in txs {
+                    tx.send(event.clone()).unwrap();
+                }
+            }
+        }
+        EventsubWebsocketData::Revocation { metadata, .. } => {
+            warn!("EventSub subscription was revoked: {:?}", metadata.subscription_type);
+        }
+        _ => {}
+    }
+
+    return Ok(SessionMessage::Other);
+}
+
+async fn subscribe_to_events(account: &TwitchAccount, broadcaster_id: &UserId, session_id: &str) {
+    let id = broadcaster_id.clone();
+
+    subscribe(
+        account,
+        session_id,
+        ChannelFollowV2::new(id.clone(), account.token.user_id.clone()),
+    )
+    .await;
+    subscribe(account, session_id, ChannelSubscribeV1::broadcaster_user_id(id.clone())).await;
+    subscribe(
+        account,
+        session_id,
+        ChannelSubscriptionMessageV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelSubscriptionGiftV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(account, session_id, ChannelCheerV1::broadcaster_user_id(id.clone())).await;
+    subscribe(account, session_id, ChannelRaidV1::to_broadcaster_user_id(id.clone())).await;
+    subscribe(
+        account,
+        session_id,
+        ChannelPointsCustomRewardRedemptionAddV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(account, session_id, ChannelPollBeginV1::broadcaster_user_id(id.clone())).await;
+    subscribe(
+        account,
+        session_id,
+        ChannelPollProgressV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(account, session_id, ChannelPollEndV1::broadcaster_user_id(id.clone())).await;
+    subscribe(
+        account,
+        session_id,
+        ChannelPredictionBeginV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelPredictionProgressV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelPredictionLockV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelPredictionEndV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelHypeTrainBeginV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelHypeTrainProgressV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(
+        account,
+        session_id,
+        ChannelHypeTrainEndV1::broadcaster_user_id(id.clone()),
+    )
+    .await;
+    subscribe(account, session_id, StreamOnlineV1::broadcaster_user_id(id.clone())).await;
+    subscribe(account, session_id, StreamOfflineV1::broadcaster_user_id(id)).await;
+}
+
+/// Most topics need the linked account to be the broadcaster or a moderator, failures are logged and skipped.
+async fn subscribe<E: EventSubscription + Send>(account: &TwitchAccount, session_id: &str, subscription: E) {
+    if let Err(err) = account
+        .client
+        .create_eventsub_subscription(subscription, Transport::websocket(session_id), &account.token)
+        .await
+    {
+        warn!("Failed to subscribe to {:?}: {}", E::EVENT_TYPE, err);
+    }
 }